@@ -150,6 +150,13 @@ impl module_evm::Config for Runtime {
 	type DeploymentFee = DeploymentFee;
 	type FreeDeploymentOrigin = EnsureSignedBy<CouncilAccount, AccountId32>;
 
+	type BaseFee = ();
+	type HardForkOrigin = EnsureSignedBy<CouncilAccount, AccountId32>;
+	type DeploymentPolicyOrigin = EnsureSignedBy<CouncilAccount, AccountId32>;
+	type RentGracePeriod = ();
+	type UpgradeDelay = ();
+	type MaxCodeHistory = ();
+
 	type WeightInfo = ();
 }
 
@@ -281,10 +288,13 @@ impl ExtBuilder {
 				balance: 0,
 				storage,
 				code: from_hex(include!("../../evm-bridge/src/erc20_demo_contract")).unwrap(),
+				maintainer: Default::default(),
+				deployed: true,
 			},
 		);
 		module_evm::GenesisConfig::<Runtime> {
 			accounts,
+			dev_mode: false,
 		}
 		.assimilate_storage(&mut t)
 		.unwrap();
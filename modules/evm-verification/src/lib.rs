@@ -0,0 +1,209 @@
+//! # Evm Verification Module
+//!
+//! ## Overview
+//!
+//! Registry of the source metadata of contract code, keyed by code hash.
+//! Contract maintainers submit the Solidity metadata hash, compiler version
+//! and ABI of their code, verifiers recompile the source off chain and mark
+//! the matching submissions verified.
+//!
+//! Only the registry is on chain. The source itself is not stored: verifiers
+//! fetch it through the metadata hash, recompile it with the submitted
+//! compiler version and report the result through `VerifierOrigin`. The
+//! node does not recompile sources, automating the verifiers is left to a
+//! separate node-side service.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::unused_unit)]
+
+use frame_support::{
+	ensure,
+	pallet_prelude::*,
+	traits::{Currency, ReservableCurrency},
+	transactional,
+	weights::Weight,
+};
+use frame_system::{ensure_signed, pallet_prelude::*};
+use primitives::evm::{AddressMapping, EvmAddress, SourceInfo};
+use sp_core::H256;
+use sp_runtime::traits::{Saturating, Zero};
+use sp_std::vec::Vec;
+
+mod default_weight;
+mod mock;
+mod tests;
+
+pub use module::*;
+
+pub trait WeightInfo {
+	fn submit_source() -> Weight;
+	fn mark_verified() -> Weight;
+	fn reject_source() -> Weight;
+}
+
+type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+#[frame_support::pallet]
+pub mod module {
+	use super::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config + module_evm::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The currency the submission deposit is reserved in.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// Deposit reserved per byte of submitted metadata.
+		#[pallet::constant]
+		type DepositPerByte: Get<BalanceOf<Self>>;
+
+		/// Max size of the submitted metadata.
+		#[pallet::constant]
+		type MaxSourceSize: Get<u32>;
+
+		/// Required origin for marking submissions verified or rejecting them.
+		type VerifierOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Weight information for the extrinsics in this module.
+		type WeightInfo: WeightInfo;
+	}
+
+	/// Submitted source metadata and its deposit.
+	#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+	pub struct Submission<AccountId, Balance> {
+		pub source: SourceInfo,
+		pub submitter: AccountId,
+		pub deposit: Balance,
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// Submitted source metadata. \[who, code_hash\]
+		SourceSubmitted(T::AccountId, H256),
+		/// Verified the submitted source metadata. \[code_hash\]
+		SourceVerified(H256),
+		/// Rejected the submitted source metadata. \[code_hash\]
+		SourceRejected(H256),
+	}
+
+	/// Error for evm verification module.
+	#[pallet::error]
+	pub enum Error<T> {
+		/// Address not mapped
+		AddressNotMapped,
+		/// Contract not found
+		ContractNotFound,
+		/// No permission
+		NoPermission,
+		/// Submitted metadata exceeds the max source size
+		SourceTooLarge,
+		/// Code hash has no submitted source
+		SourceNotFound,
+		/// Source of the code hash is verified already
+		AlreadyVerified,
+	}
+
+	/// Submitted source metadata by code hash.
+	#[pallet::storage]
+	#[pallet::getter(fn submissions)]
+	pub type Submissions<T: Config> =
+		StorageMap<_, Identity, H256, Submission<T::AccountId, BalanceOf<T>>>;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Submit the source metadata of the code of a contract maintained by
+		/// the caller, replacing any unverified submission.
+		#[pallet::weight(<T as Config>::WeightInfo::submit_source())]
+		#[transactional]
+		pub fn submit_source(
+			origin: OriginFor<T>,
+			contract: EvmAddress,
+			metadata_hash: Vec<u8>,
+			compiler_version: Vec<u8>,
+			abi: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let maintainer = <T as module_evm::Config>::AddressMapping::get_evm_address(&who)
+				.ok_or(Error::<T>::AddressNotMapped)?;
+			let contract_info = module_evm::Pallet::<T>::contract_info(&contract).ok_or(Error::<T>::ContractNotFound)?;
+			ensure!(contract_info.maintainer == maintainer, Error::<T>::NoPermission);
+
+			let size = metadata_hash.len() + compiler_version.len() + abi.len();
+			ensure!(size <= T::MaxSourceSize::get() as usize, Error::<T>::SourceTooLarge);
+
+			let code_hash = contract_info.code_hash;
+			if let Some(submission) = Self::submissions(&code_hash) {
+				ensure!(!submission.source.verified, Error::<T>::AlreadyVerified);
+				<T as Config>::Currency::unreserve(&submission.submitter, submission.deposit);
+			}
+
+			let deposit = T::DepositPerByte::get().saturating_mul((size as u32).into());
+			<T as Config>::Currency::reserve(&who, deposit)?;
+
+			Submissions::<T>::insert(
+				&code_hash,
+				Submission {
+					source: SourceInfo {
+						code_hash,
+						metadata_hash,
+						compiler_version,
+						abi,
+						verified: false,
+					},
+					submitter: who.clone(),
+					deposit,
+				},
+			);
+
+			Self::deposit_event(Event::SourceSubmitted(who, code_hash));
+			Ok(().into())
+		}
+
+		/// Mark the submitted source of the code hash verified, after it was
+		/// recompiled to the stored code.
+		#[pallet::weight(<T as Config>::WeightInfo::mark_verified())]
+		#[transactional]
+		pub fn mark_verified(origin: OriginFor<T>, code_hash: H256) -> DispatchResultWithPostInfo {
+			T::VerifierOrigin::ensure_origin(origin)?;
+			Submissions::<T>::try_mutate(&code_hash, |maybe_submission| -> DispatchResult {
+				let submission = maybe_submission.as_mut().ok_or(Error::<T>::SourceNotFound)?;
+				ensure!(!submission.source.verified, Error::<T>::AlreadyVerified);
+				submission.source.verified = true;
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::SourceVerified(code_hash));
+			Ok(().into())
+		}
+
+		/// Remove the submitted source of the code hash, refunding its deposit.
+		#[pallet::weight(<T as Config>::WeightInfo::reject_source())]
+		#[transactional]
+		pub fn reject_source(origin: OriginFor<T>, code_hash: H256) -> DispatchResultWithPostInfo {
+			T::VerifierOrigin::ensure_origin(origin)?;
+			let submission = Submissions::<T>::take(&code_hash).ok_or(Error::<T>::SourceNotFound)?;
+			if !submission.deposit.is_zero() {
+				<T as Config>::Currency::unreserve(&submission.submitter, submission.deposit);
+			}
+
+			Self::deposit_event(Event::SourceRejected(code_hash));
+			Ok(().into())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Source metadata of the code of the contract, if submitted.
+	pub fn source_info(contract: &EvmAddress) -> Option<SourceInfo> {
+		let contract_info = module_evm::Pallet::<T>::contract_info(contract)?;
+		Self::submissions(&contract_info.code_hash).map(|submission| submission.source)
+	}
+}
@@ -0,0 +1,188 @@
+//! Mocks for the evm-verification module.
+
+#![cfg(test)]
+
+use super::*;
+use frame_support::{construct_runtime, ord_parameter_types, parameter_types};
+use frame_system::EnsureSignedBy;
+use primitives::mocks::MockAddressMapping;
+use sp_core::{crypto::AccountId32, H256};
+use sp_runtime::{testing::Header, traits::IdentityLookup};
+use sp_std::str::FromStr;
+
+pub type AccountId = AccountId32;
+pub type BlockNumber = u64;
+pub type Balance = u128;
+
+mod evm_verification {
+	pub use super::super::*;
+}
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+}
+
+impl frame_system::Config for Runtime {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = BlockNumber;
+	type Hash = H256;
+	type Hashing = ::sp_runtime::traits::BlakeTwo256;
+	type AccountId = AccountId;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type DbWeight = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<Balance>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const MaxLocks: u32 = 50;
+	pub const MaxReserves: u32 = 50;
+}
+
+impl pallet_balances::Config for Runtime {
+	type Balance = Balance;
+	type DustRemoval = ();
+	type Event = Event;
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+	type MaxLocks = MaxLocks;
+	type MaxReserves = MaxReserves;
+	type ReserveIdentifier = [u8; 8];
+}
+
+parameter_types! {
+	pub const MinimumPeriod: u64 = 1000;
+}
+
+impl pallet_timestamp::Config for Runtime {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = MinimumPeriod;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const NewContractExtraBytes: u32 = 1;
+	pub NetworkContractSource: EvmAddress = alice();
+	pub const DepositPerByte: Balance = 2;
+	pub const MaxSourceSize: u32 = 64;
+}
+
+ord_parameter_types! {
+	pub const CouncilAccount: AccountId32 = AccountId32::from([1u8; 32]);
+	pub const NetworkContractAccount: AccountId32 = AccountId32::from([0u8; 32]);
+	pub const VerifierAccount: AccountId32 = AccountId32::from([2u8; 32]);
+	pub const StorageDepositPerByte: u128 = 10;
+	pub const MaxCodeSize: u32 = 60 * 1024;
+	pub const DeveloperDeposit: u64 = 1000;
+	pub const DeploymentFee: u64 = 200;
+}
+
+impl module_evm::Config for Runtime {
+	type AddressMapping = MockAddressMapping;
+	type Currency = Balances;
+	type TransferAll = ();
+	type NewContractExtraBytes = NewContractExtraBytes;
+	type StorageDepositPerByte = StorageDepositPerByte;
+	type MaxCodeSize = MaxCodeSize;
+
+	type Event = Event;
+	type Precompiles = ();
+	type ChainId = ();
+	type GasToWeight = ();
+	type ChargeTransactionPayment = ();
+	type NetworkContractOrigin = EnsureSignedBy<NetworkContractAccount, AccountId32>;
+	type NetworkContractSource = NetworkContractSource;
+
+	type DeveloperDeposit = DeveloperDeposit;
+	type DeploymentFee = DeploymentFee;
+	type FreeDeploymentOrigin = EnsureSignedBy<CouncilAccount, AccountId32>;
+
+	type BaseFee = ();
+	type HardForkOrigin = EnsureSignedBy<CouncilAccount, AccountId32>;
+	type DeploymentPolicyOrigin = EnsureSignedBy<CouncilAccount, AccountId32>;
+	type RentGracePeriod = ();
+	type UpgradeDelay = ();
+	type MaxCodeHistory = ();
+
+	type WeightInfo = ();
+}
+
+impl Config for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type DepositPerByte = DepositPerByte;
+	type MaxSourceSize = MaxSourceSize;
+	type VerifierOrigin = EnsureSignedBy<VerifierAccount, AccountId32>;
+	type WeightInfo = ();
+}
+pub type EvmVerificationModule = Pallet<Runtime>;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Runtime>;
+type Block = frame_system::mocking::MockBlock<Runtime>;
+
+construct_runtime!(
+	pub enum Runtime where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Storage, Config, Event<T>},
+		EvmVerification: evm_verification::{Pallet, Call, Storage, Event<T>},
+		EVM: module_evm::{Pallet, Config<T>, Call, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+	}
+);
+
+pub const INITIAL_BALANCE: Balance = 1_000_000;
+
+pub fn alice() -> EvmAddress {
+	EvmAddress::from_str("1000000000000000000000000000000000000001").unwrap()
+}
+
+pub fn bob() -> EvmAddress {
+	EvmAddress::from_str("1000000000000000000000000000000000000002").unwrap()
+}
+
+pub fn account_id(address: EvmAddress) -> AccountId {
+	<Runtime as module_evm::Config>::AddressMapping::get_account_id(&address)
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default()
+		.build_storage::<Runtime>()
+		.unwrap();
+
+	pallet_balances::GenesisConfig::<Runtime> {
+		balances: vec![(account_id(alice()), INITIAL_BALANCE), (account_id(bob()), INITIAL_BALANCE)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	module_evm::GenesisConfig::<Runtime> {
+		accounts: Default::default(),
+		dev_mode: false,
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
@@ -0,0 +1,132 @@
+//! Unit tests for the evm-verification module.
+
+#![cfg(test)]
+
+use super::*;
+use frame_support::{assert_noop, assert_ok};
+use mock::{Event, *};
+use sp_runtime::traits::BadOrigin;
+
+fn create_contract() -> (EvmAddress, H256) {
+	// PUSH1 0x00 PUSH1 0x00 MSTORE8 PUSH1 0x01 PUSH1 0x00 RETURN
+	let init = vec![0x60, 0x00, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xf3];
+	let address = module_evm::Runner::<Runtime>::create(
+		alice(),
+		init,
+		0,
+		1_000_000,
+		1_000_000,
+		<Runtime as module_evm::Config>::config(),
+	)
+	.unwrap()
+	.address;
+	(address, module_evm::code_hash(&[0x00]))
+}
+
+#[test]
+fn should_submit_and_verify_source() {
+	new_test_ext().execute_with(|| {
+		let (contract, code_hash) = create_contract();
+		let reserved = Balances::reserved_balance(account_id(alice()));
+
+		assert_noop!(
+			EvmVerificationModule::submit_source(
+				Origin::signed(account_id(bob())),
+				contract,
+				vec![1; 32],
+				b"0.8.4".to_vec(),
+				b"[]".to_vec()
+			),
+			Error::<Runtime>::NoPermission
+		);
+		assert_noop!(
+			EvmVerificationModule::submit_source(
+				Origin::signed(account_id(alice())),
+				contract,
+				vec![1; 32],
+				b"0.8.4".to_vec(),
+				vec![0; 64]
+			),
+			Error::<Runtime>::SourceTooLarge
+		);
+
+		assert_ok!(EvmVerificationModule::submit_source(
+			Origin::signed(account_id(alice())),
+			contract,
+			vec![1; 32],
+			b"0.8.4".to_vec(),
+			b"[]".to_vec()
+		));
+		assert_eq!(Balances::reserved_balance(account_id(alice())), reserved + 39 * 2);
+		let event = Event::EvmVerification(crate::Event::SourceSubmitted(account_id(alice()), code_hash));
+		assert!(System::events().iter().any(|record| record.event == event));
+
+		// resubmitting replaces the unverified source and its deposit
+		assert_ok!(EvmVerificationModule::submit_source(
+			Origin::signed(account_id(alice())),
+			contract,
+			vec![2; 32],
+			b"0.8.4".to_vec(),
+			b"[{}]".to_vec()
+		));
+		assert_eq!(Balances::reserved_balance(account_id(alice())), reserved + 41 * 2);
+
+		assert_noop!(
+			EvmVerificationModule::mark_verified(Origin::signed(account_id(alice())), code_hash),
+			BadOrigin
+		);
+		assert_ok!(EvmVerificationModule::mark_verified(
+			Origin::signed(VerifierAccount::get()),
+			code_hash
+		));
+		assert_eq!(
+			EvmVerificationModule::source_info(&contract),
+			Some(SourceInfo {
+				code_hash,
+				metadata_hash: vec![2; 32],
+				compiler_version: b"0.8.4".to_vec(),
+				abi: b"[{}]".to_vec(),
+				verified: true,
+			})
+		);
+
+		assert_noop!(
+			EvmVerificationModule::submit_source(
+				Origin::signed(account_id(alice())),
+				contract,
+				vec![3; 32],
+				b"0.8.4".to_vec(),
+				b"[]".to_vec()
+			),
+			Error::<Runtime>::AlreadyVerified
+		);
+	});
+}
+
+#[test]
+fn should_reject_source() {
+	new_test_ext().execute_with(|| {
+		let (contract, code_hash) = create_contract();
+		let reserved = Balances::reserved_balance(account_id(alice()));
+
+		assert_noop!(
+			EvmVerificationModule::reject_source(Origin::signed(VerifierAccount::get()), code_hash),
+			Error::<Runtime>::SourceNotFound
+		);
+
+		assert_ok!(EvmVerificationModule::submit_source(
+			Origin::signed(account_id(alice())),
+			contract,
+			vec![1; 32],
+			b"0.8.4".to_vec(),
+			b"[]".to_vec()
+		));
+		assert_ok!(EvmVerificationModule::reject_source(
+			Origin::signed(VerifierAccount::get()),
+			code_hash
+		));
+
+		assert_eq!(Balances::reserved_balance(account_id(alice())), reserved);
+		assert_eq!(EvmVerificationModule::source_info(&contract), None);
+	});
+}
@@ -1,18 +1,26 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 #![allow(clippy::all)]
 
-use ethereum_types::H160;
-use primitives::evm::{CallInfo, CreateInfo, EstimateResourcesRequest};
+use ethereum_types::{H160, H256, U256};
+use primitives::evm::{
+	AccessListInfo, AccessListItem, Account, BatchCall, CallInfo, CreateInfo, DevOperation, DryRunInfo,
+	EstimateResourcesRequest, EthereumHeader, Log, Receipt, SourceInfo, StorageRange, TraceConfig, TraceInfo,
+};
 use sp_runtime::{
 	codec::Codec,
-	traits::{MaybeDisplay, MaybeFromStr},
+	traits::{Block as BlockT, MaybeDisplay, MaybeFromStr},
+	Perbill,
 };
 use sp_std::vec::Vec;
 
 sp_api::decl_runtime_apis! {
+	/// Version 2 adds access lists to `call` and `create`, and the methods
+	/// after `get_estimate_resources_request`.
+	#[api_version(2)]
 	pub trait EVMRuntimeRPCApi<Balance> where
 		Balance: Codec + MaybeDisplay + MaybeFromStr,
 	{
+		#[changed_in(2)]
 		fn call(
 			from: H160,
 			to: H160,
@@ -23,17 +31,163 @@ sp_api::decl_runtime_apis! {
 			estimate: bool,
 		) -> Result<CallInfo, sp_runtime::DispatchError>;
 
+		fn call(
+			from: H160,
+			to: H160,
+			data: Vec<u8>,
+			value: Balance,
+			gas_limit: u64,
+			storage_limit: u32,
+			access_list: Option<Vec<AccessListItem>>,
+			estimate: bool,
+		) -> Result<CallInfo, sp_runtime::DispatchError>;
+
+		#[changed_in(2)]
+		fn create(
+			from: H160,
+			data: Vec<u8>,
+			value: Balance,
+			gas_limit: u64,
+			storage_limit: u32,
+			estimate: bool,
+		) -> Result<CreateInfo, sp_runtime::DispatchError>;
+
 		fn create(
 			from: H160,
 			data: Vec<u8>,
 			value: Balance,
 			gas_limit: u64,
 			storage_limit: u32,
+			access_list: Option<Vec<AccessListItem>>,
 			estimate: bool,
 		) -> Result<CreateInfo, sp_runtime::DispatchError>;
 
  		fn get_estimate_resources_request(
 			data: Vec<u8>
 		) -> Result<EstimateResourcesRequest, sp_runtime::DispatchError>;
+
+		fn chain_id() -> u64;
+
+		fn account_basic(address: H160) -> Account;
+
+		fn code_at(address: H160) -> Vec<u8>;
+
+		fn storage_at(address: H160, index: H256) -> H256;
+
+		/// Page through the storage of a contract, at most `limit` slots starting
+		/// from the `start` key.
+		fn storage_range(address: H160, start: Option<H256>, limit: u32) -> StorageRange;
+
+		/// Submitted source metadata of the code of a contract.
+		fn source_info(address: H160) -> Option<SourceInfo>;
+
+		/// EVM logs emitted in the current block, with the index of the emitting extrinsic.
+		fn block_logs() -> Vec<(u32, Log)>;
+
+		/// Receipts of the EVM extrinsics of the current block.
+		fn block_receipts() -> Vec<Receipt>;
+
+		/// Ethereum header projection of a recent block, `None` for the
+		/// genesis block and the blocks whose header is no longer kept.
+		fn ethereum_header(number: u64) -> Option<EthereumHeader>;
+
+		/// EVM base fee per gas of the next block.
+		fn base_fee() -> U256;
+
+		/// Ratio of the maximum block weight used by the current block.
+		fn block_fullness() -> Perbill;
+
+		/// Run each call against its own state overrides, discarding its changes.
+		fn call_batch(calls: Vec<BatchCall<Balance>>) -> Vec<Result<CallInfo, sp_runtime::DispatchError>>;
+
+		/// Generate the access list of a call, or of a create if `to` is `None`.
+		fn create_access_list(
+			from: H160,
+			to: Option<H160>,
+			data: Vec<u8>,
+			value: Balance,
+			gas_limit: u64,
+			storage_limit: u32,
+		) -> Result<AccessListInfo, sp_runtime::DispatchError>;
+
+		/// Unsigned extrinsic executing a raw Ethereum transaction.
+		fn transact_extrinsic(transaction: Vec<u8>) -> <Block as BlockT>::Extrinsic;
+
+		/// Hash of the EVM transaction of the extrinsic: the raw Ethereum
+		/// transaction hash or the extrinsic hash, `None` for other extrinsics.
+		fn transaction_hash(extrinsic: <Block as BlockT>::Extrinsic) -> Option<H256>;
+
+		/// Unsigned extrinsic applying a dev operation, `None` unless the chain
+		/// is in dev mode.
+		fn dev_extrinsic(operation: DevOperation, salt: u64) -> Option<<Block as BlockT>::Extrinsic>;
+	}
+
+	/// Version 2 adds access lists to `trace_call` and `trace_create`.
+	#[api_version(2)]
+	pub trait EVMTraceApi<Balance> where
+		Balance: Codec + MaybeDisplay + MaybeFromStr,
+	{
+		#[changed_in(2)]
+		fn trace_call(
+			from: H160,
+			to: H160,
+			data: Vec<u8>,
+			value: Balance,
+			gas_limit: u64,
+			storage_limit: u32,
+			config: TraceConfig,
+		) -> Result<TraceInfo, sp_runtime::DispatchError>;
+
+		fn trace_call(
+			from: H160,
+			to: H160,
+			data: Vec<u8>,
+			value: Balance,
+			gas_limit: u64,
+			storage_limit: u32,
+			access_list: Option<Vec<AccessListItem>>,
+			config: TraceConfig,
+		) -> Result<TraceInfo, sp_runtime::DispatchError>;
+
+		#[changed_in(2)]
+		fn trace_create(
+			from: H160,
+			data: Vec<u8>,
+			value: Balance,
+			gas_limit: u64,
+			storage_limit: u32,
+			config: TraceConfig,
+		) -> Result<TraceInfo, sp_runtime::DispatchError>;
+
+		fn trace_create(
+			from: H160,
+			data: Vec<u8>,
+			value: Balance,
+			gas_limit: u64,
+			storage_limit: u32,
+			access_list: Option<Vec<AccessListItem>>,
+			config: TraceConfig,
+		) -> Result<TraceInfo, sp_runtime::DispatchError>;
+
+		/// Apply `pending` extrinsics and trace the EVM call of `extrinsic`.
+		fn trace_extrinsic(
+			pending: Vec<<Block as BlockT>::Extrinsic>,
+			extrinsic: <Block as BlockT>::Extrinsic,
+			config: TraceConfig,
+		) -> Result<TraceInfo, sp_runtime::DispatchError>;
+	}
+
+	pub trait DryRunApi<AccountId, Balance> where
+		AccountId: Codec,
+		Balance: Codec + MaybeDisplay + MaybeFromStr,
+	{
+		/// Apply `extrinsic` on top of the initialized block and collect its side
+		/// effects, discarding its changes.
+		///
+		/// An unsigned `extrinsic` is dispatched as `from` without charging fees.
+		fn dry_run_extrinsic(
+			extrinsic: <Block as BlockT>::Extrinsic,
+			from: Option<AccountId>,
+		) -> DryRunInfo<AccountId, Balance>;
 	}
 }
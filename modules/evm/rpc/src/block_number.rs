@@ -0,0 +1,57 @@
+use serde::{de::Error, Deserialize, Deserializer};
+
+/// Block number parameter of the `eth_*` rpc methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockNumber {
+	/// Latest block
+	Latest,
+	/// Earliest block (genesis)
+	Earliest,
+	/// Pending block, there is no pending state so it is the same as latest
+	Pending,
+	/// Block by number
+	Num(u64),
+}
+
+impl Default for BlockNumber {
+	fn default() -> Self {
+		BlockNumber::Latest
+	}
+}
+
+impl<'de> Deserialize<'de> for BlockNumber {
+	fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let value = String::deserialize(deserializer)?;
+		match value.as_str() {
+			"latest" => Ok(BlockNumber::Latest),
+			"earliest" => Ok(BlockNumber::Earliest),
+			"pending" => Ok(BlockNumber::Pending),
+			_ if value.starts_with("0x") => u64::from_str_radix(&value[2..], 16)
+				.map(BlockNumber::Num)
+				.map_err(|e| D::Error::custom(format!("Invalid block number: {}", e))),
+			_ => Err(D::Error::custom("Invalid block number: missing 0x prefix")),
+		}
+	}
+}
+
+#[test]
+fn block_number_deserialize_should_work() {
+	let numbers: Vec<BlockNumber> =
+		serde_json::from_str(r#"["latest", "earliest", "pending", "0x0", "0x1f"]"#).unwrap();
+	assert_eq!(
+		numbers,
+		vec![
+			BlockNumber::Latest,
+			BlockNumber::Earliest,
+			BlockNumber::Pending,
+			BlockNumber::Num(0),
+			BlockNumber::Num(31),
+		]
+	);
+
+	assert!(serde_json::from_str::<BlockNumber>(r#""31""#).is_err());
+	assert!(serde_json::from_str::<BlockNumber>(r#""0xzz""#).is_err());
+}
@@ -1,7 +1,9 @@
-use ethereum_types::{H160, U256};
+use ethereum_types::{Bloom, H160, H256, H64, U256};
+use module_evm::AccessListItem;
  use serde::{Deserialize, Serialize};
 use sp_core::Bytes;
 use sp_rpc::number::NumberOrHex;
+use std::collections::BTreeMap;
 
 /// Call request
 #[derive(Debug, Default, PartialEq, Deserialize, Clone)]
@@ -20,6 +22,8 @@ pub struct CallRequest {
 	pub value: Option<NumberOrHex>,
 	/// Data
 	pub data: Option<Bytes>,
+	/// Access list (EIP-2930)
+	pub access_list: Option<Vec<AccessListItem>>,
 }
 
  /// EstimateResources response
@@ -32,4 +36,313 @@ pub struct EstimateResourcesResponse {
 	pub storage: i32,
 	/// Adjusted weight fee
 	pub weight_fee: U256,
+	/// Limits found by exact estimations
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub limits: Option<ExactLimits>,
+}
+
+/// Options of gas and resources estimations
+#[derive(Debug, Default, PartialEq, Eq, Deserialize, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct EstimateOptions {
+	/// Binary search the minimal gas and storage limits the execution succeeds
+	/// with, instead of stopping once the gas used changes by less than 10%
+	#[serde(default)]
+	pub exact: bool,
+	/// Margin added to the minimal limits for the recommended ones, in percent
+	pub margin_percent: Option<u32>,
+}
+
+/// Limits found by exact estimations
+#[derive(Debug, Eq, PartialEq, Default, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExactLimits {
+	/// Minimal gas limit
+	pub gas_limit: u64,
+	/// Minimal storage limit, with the minimal gas limit
+	pub storage_limit: u32,
+	/// Margin-adjusted gas limit
+	pub recommended_gas_limit: u64,
+	/// Margin-adjusted storage limit
+	pub recommended_storage_limit: u32,
+}
+
+/// Ethereum call request, as sent by `eth_call` and `eth_estimateGas`
+#[derive(Debug, Default, PartialEq, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EthCallRequest {
+	/// From
+	pub from: Option<H160>,
+	/// To
+	pub to: Option<H160>,
+	/// Gas
+	pub gas: Option<U256>,
+	/// Gas Price, ignored
+	pub gas_price: Option<U256>,
+	/// Value
+	pub value: Option<U256>,
+	/// Data
+	pub data: Option<Bytes>,
+	/// Input, takes precedence over `data`
+	pub input: Option<Bytes>,
+	/// Access list (EIP-2930)
+	pub access_list: Option<Vec<AccessListItem>>,
+}
+
+impl From<EthCallRequest> for CallRequest {
+	fn from(request: EthCallRequest) -> Self {
+		CallRequest {
+			from: request.from,
+			to: request.to,
+			gas_limit: request.gas.map(|gas| gas.min(U256::from(u64::max_value())).as_u64()),
+			storage_limit: None,
+			value: request.value.map(NumberOrHex::Hex),
+			data: request.input.or(request.data),
+			access_list: request.access_list,
+		}
+	}
+}
+
+/// State override of an account, as in the geth `eth_call` override set
+#[derive(Debug, Default, PartialEq, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountOverride {
+	/// Free balance
+	pub balance: Option<NumberOrHex>,
+	/// Nonce
+	pub nonce: Option<U256>,
+	/// Code
+	pub code: Option<Bytes>,
+	/// Replaces the whole storage of the account
+	pub state: Option<BTreeMap<H256, H256>>,
+	/// Replaces the given storage slots of the account
+	pub state_diff: Option<BTreeMap<H256, H256>>,
+}
+
+/// Batch call request
+#[derive(Debug, Default, PartialEq, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchCallRequest {
+	/// Call
+	pub call: CallRequest,
+	/// State overrides by address
+	pub state_overrides: Option<BTreeMap<H160, AccountOverride>>,
+}
+
+/// Batch call response
+#[derive(Debug, Eq, PartialEq, Default, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchCallResponse {
+	/// Output data
+	pub output: Bytes,
+	/// Used gas
+	pub used_gas: U256,
+	/// Used storage
+	pub used_storage: i32,
+	/// Execution error, if the call failed
+	pub error: Option<String>,
+}
+
+/// Access list response, as returned by `eth_createAccessList`
+#[derive(Debug, Eq, PartialEq, Default, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessListResponse {
+	/// Accessed addresses and storage keys, excluding the sender and target
+	pub access_list: Vec<AccessListItem>,
+	/// Gas used when sending the call with the access list
+	pub gas_used: U256,
+	/// Execution error, if the call failed
+	pub error: Option<String>,
+}
+
+#[test]
+fn batch_call_request_deserialize_should_work() {
+	let request: BatchCallRequest = serde_json::from_str(
+		r#"{
+			"call": {
+				"to": "0x0000000000000000000000000000000000000001",
+				"data": "0x1234"
+			},
+			"stateOverrides": {
+				"0x0000000000000000000000000000000000000002": {
+					"balance": "0x10",
+					"code": "0x6000",
+					"stateDiff": {
+						"0x0000000000000000000000000000000000000000000000000000000000000001": "0x0000000000000000000000000000000000000000000000000000000000000002"
+					}
+				}
+			}
+		}"#,
+	)
+	.unwrap();
+
+	let mut state_diff = BTreeMap::new();
+	state_diff.insert(H256::from_low_u64_be(1), H256::from_low_u64_be(2));
+	let mut state_overrides = BTreeMap::new();
+	state_overrides.insert(
+		H160::from_low_u64_be(2),
+		AccountOverride {
+			balance: Some(NumberOrHex::Hex(U256::from(16))),
+			code: Some(Bytes(vec![0x60, 0x00])),
+			state_diff: Some(state_diff),
+			..Default::default()
+		},
+	);
+
+	assert_eq!(
+		request,
+		BatchCallRequest {
+			call: CallRequest {
+				to: Some(H160::from_low_u64_be(1)),
+				data: Some(Bytes(vec![0x12, 0x34])),
+				..Default::default()
+			},
+			state_overrides: Some(state_overrides),
+		}
+	);
+
+	assert!(serde_json::from_str::<BatchCallRequest>(r#"{"call": {}, "unknown": 1}"#).is_err());
+}
+
+#[test]
+fn eth_call_request_access_list_should_work() {
+	let request: EthCallRequest = serde_json::from_str(
+		r#"{
+			"to": "0x0000000000000000000000000000000000000001",
+			"accessList": [{
+				"address": "0x0000000000000000000000000000000000000001",
+				"storageKeys": ["0x0000000000000000000000000000000000000000000000000000000000000002"]
+			}]
+		}"#,
+	)
+	.unwrap();
+
+	let access_list = vec![AccessListItem {
+		address: H160::from_low_u64_be(1),
+		storage_keys: vec![H256::from_low_u64_be(2)],
+	}];
+	assert_eq!(CallRequest::from(request).access_list, Some(access_list));
+}
+
+/// Balance change of an account in a dry run
+#[derive(Debug, Eq, PartialEq, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceChangeResponse<AccountId> {
+	/// Account
+	pub who: AccountId,
+	/// Free balance before the extrinsic
+	pub free_before: U256,
+	/// Free balance after the extrinsic
+	pub free_after: U256,
+	/// Reserved balance before the extrinsic
+	pub reserved_before: U256,
+	/// Reserved balance after the extrinsic
+	pub reserved_after: U256,
+}
+
+/// Storage deposit reserved for or refunded by a contract in a dry run
+#[derive(Debug, Eq, PartialEq, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageDepositResponse {
+	/// Account paying or receiving the deposit
+	pub who: H160,
+	/// Contract
+	pub contract: H160,
+	/// Amount
+	pub amount: U256,
+	/// Whether the deposit was refunded
+	pub refunded: bool,
+}
+
+/// Log emitted in a dry run
+#[derive(Debug, Eq, PartialEq, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LogResponse {
+	/// Emitting contract
+	pub address: H160,
+	/// Topics
+	pub topics: Vec<H256>,
+	/// Data
+	pub data: Bytes,
+}
+
+/// Dry run response
+#[derive(Debug, Eq, PartialEq, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunResponse<AccountId> {
+	/// Dispatch error or validity error, if the extrinsic failed
+	pub error: Option<String>,
+	/// SCALE encoded event records
+	pub events: Vec<Bytes>,
+	/// EVM logs
+	pub logs: Vec<LogResponse>,
+	/// Changed balances
+	pub balance_changes: Vec<BalanceChangeResponse<AccountId>>,
+	/// Storage deposits
+	pub storage_deposits: Vec<StorageDepositResponse>,
+	/// EVM execution error, if the EVM execution failed
+	pub evm_error: Option<String>,
+	/// Decoded revert reason, if the EVM execution reverted
+	pub revert_reason: Option<String>,
+}
+
+/// Fee history, as returned by `eth_feeHistory`
+#[derive(Debug, PartialEq, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeHistory {
+	/// Lowest block number of the returned range
+	pub oldest_block: U256,
+	/// Base fee per gas of each block, including the block after the newest one
+	pub base_fee_per_gas: Vec<U256>,
+	/// Ratio of the block weight used by each block
+	pub gas_used_ratio: Vec<f64>,
+	/// Priority fees at the requested percentiles, always zero
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub reward: Option<Vec<Vec<U256>>>,
+}
+
+/// Ethereum block, as returned by `eth_getBlockByNumber`
+#[derive(Debug, PartialEq, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EthBlock {
+	pub number: U256,
+	pub hash: H256,
+	pub parent_hash: H256,
+	/// Always zero, there is no proof of work
+	pub nonce: H64,
+	/// Hash of the empty uncle list
+	pub sha3_uncles: H256,
+	pub logs_bloom: Bloom,
+	/// Extrinsics root of the block
+	pub transactions_root: H256,
+	pub state_root: H256,
+	/// Always zero, receipts are not merkleized
+	pub receipts_root: H256,
+	pub miner: H160,
+	pub difficulty: U256,
+	pub total_difficulty: U256,
+	pub extra_data: Bytes,
+	pub gas_limit: U256,
+	pub gas_used: U256,
+	pub timestamp: U256,
+	/// Hashes of the EVM transactions of the block
+	pub transactions: Vec<H256>,
+	pub uncles: Vec<H256>,
+	pub base_fee_per_gas: U256,
+}
+
+#[test]
+fn estimate_options_deserialize_should_work() {
+	assert_eq!(serde_json::from_str::<EstimateOptions>("{}").unwrap(), EstimateOptions::default());
+	assert_eq!(
+		serde_json::from_str::<EstimateOptions>(r#"{"exact": true, "marginPercent": 20}"#).unwrap(),
+		EstimateOptions {
+			exact: true,
+			margin_percent: Some(20),
+		}
+	);
+	assert!(serde_json::from_str::<EstimateOptions>(r#"{"threshold": 10}"#).is_err());
 }
@@ -0,0 +1,185 @@
+use ethereum_types::{H256, U256};
+use jsonrpc_core::{Error, ErrorCode, Result};
+use sc_client_api::BlockBackend;
+use sc_rpc::DenyUnsafe;
+use sp_api::{ApiExt, Core, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::hashing::blake2_256;
+use sp_runtime::{
+	codec::{Codec, Encode},
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, MaybeDisplay, MaybeFromStr},
+};
+use std::convert::{TryFrom, TryInto};
+use std::{marker::PhantomData, sync::Arc};
+
+use module_evm::{TraceConfig, TraceInfo};
+use module_evm_rpc_runtime_api::EVMTraceApi;
+
+use crate::{
+	call_request::CallRequest, debug_api::DebugApi as DebugApiT, ensure_no_access_list, internal_err, to_u128,
+	GAS_LIMIT, STORAGE_LIMIT,
+};
+
+/// EVM debug rpc handler, backed by the `EVMTraceApi`.
+pub struct DebugApi<B, C, Balance> {
+	client: Arc<C>,
+	deny_unsafe: DenyUnsafe,
+	_marker: PhantomData<(B, Balance)>,
+}
+
+impl<B, C, Balance> DebugApi<B, C, Balance> {
+	pub fn new(client: Arc<C>, deny_unsafe: DenyUnsafe) -> Self {
+		Self {
+			client,
+			deny_unsafe,
+			_marker: Default::default(),
+		}
+	}
+}
+
+fn invalid_params<T: ToString>(message: T) -> Error {
+	Error {
+		code: ErrorCode::InvalidParams,
+		message: message.to_string(),
+		data: None,
+	}
+}
+
+impl<B, C, Balance> DebugApiT<<B as BlockT>::Hash> for DebugApi<B, C, Balance>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + BlockBackend<B> + Send + Sync + 'static,
+	C::Api: Core<B>,
+	C::Api: EVMTraceApi<B, Balance>,
+	Balance: Codec + MaybeDisplay + MaybeFromStr + Default + Send + Sync + 'static + TryFrom<u128> + Into<U256>,
+{
+	fn trace_call(
+		&self,
+		request: CallRequest,
+		config: Option<TraceConfig>,
+		at: Option<<B as BlockT>::Hash>,
+	) -> Result<TraceInfo> {
+		self.deny_unsafe.check_if_safe()?;
+		let hash = at.unwrap_or_else(|| self.client.info().best_hash);
+
+		let CallRequest {
+			from,
+			to,
+			gas_limit,
+			storage_limit,
+			value,
+			data,
+			access_list,
+		} = request;
+
+		let gas_limit = gas_limit.unwrap_or(GAS_LIMIT).min(GAS_LIMIT);
+		let storage_limit = storage_limit.unwrap_or(STORAGE_LIMIT).min(STORAGE_LIMIT);
+		let data = data.map(|d| d.0).unwrap_or_default();
+		let config = config.unwrap_or_default();
+
+		let balance_value = if let Some(value) = value {
+			to_u128(value).and_then(|v| TryInto::<Balance>::try_into(v).map_err(|_| ()))
+		} else {
+			Ok(Default::default())
+		};
+
+		let balance_value =
+			balance_value.map_err(|_| invalid_params(format!("Invalid parameter value: {:?}", value)))?;
+
+		let id = BlockId::Hash(hash);
+		let api = self.client.runtime_api();
+		let has_access_list = api
+			.has_api_with::<dyn EVMTraceApi<B, Balance>, _>(&id, |version| version >= 2)
+			.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?;
+		if !has_access_list {
+			ensure_no_access_list(&access_list)?;
+		}
+
+		#[allow(deprecated)]
+		let info = match (to, has_access_list) {
+			(Some(to), true) => api.trace_call(
+				&id,
+				from.unwrap_or_default(),
+				to,
+				data,
+				balance_value,
+				gas_limit,
+				storage_limit,
+				access_list,
+				config,
+			),
+			(Some(to), false) => api.trace_call_before_version_2(
+				&id,
+				from.unwrap_or_default(),
+				to,
+				data,
+				balance_value,
+				gas_limit,
+				storage_limit,
+				config,
+			),
+			(None, true) => api.trace_create(
+				&id,
+				from.unwrap_or_default(),
+				data,
+				balance_value,
+				gas_limit,
+				storage_limit,
+				access_list,
+				config,
+			),
+			(None, false) => api.trace_create_before_version_2(
+				&id,
+				from.unwrap_or_default(),
+				data,
+				balance_value,
+				gas_limit,
+				storage_limit,
+				config,
+			),
+		};
+
+		info.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
+		.map_err(|err| internal_err(format!("execution fatal: {:?}", err)))
+	}
+
+	fn trace_transaction(
+		&self,
+		block_hash: <B as BlockT>::Hash,
+		extrinsic_hash: H256,
+		config: Option<TraceConfig>,
+	) -> Result<TraceInfo> {
+		self.deny_unsafe.check_if_safe()?;
+		let id = BlockId::Hash(block_hash);
+
+		let header = self
+			.client
+			.header(id)
+			.map_err(|err| internal_err(format!("fetch header failed: {:?}", err)))?
+			.ok_or_else(|| invalid_params("Unknown block hash"))?;
+		let mut extrinsics = self
+			.client
+			.block_body(&id)
+			.map_err(|err| internal_err(format!("fetch block body failed: {:?}", err)))?
+			.ok_or_else(|| invalid_params("Unknown block hash"))?;
+
+		let index = extrinsics
+			.iter()
+			.position(|xt| xt.using_encoded(blake2_256) == extrinsic_hash.0)
+			.ok_or_else(|| invalid_params("Extrinsic not found in block"))?;
+
+		let extrinsic = extrinsics.remove(index);
+		extrinsics.truncate(index);
+
+		// replay the block on top of its parent up to the traced extrinsic
+		let parent_id = BlockId::Hash(*header.parent_hash());
+		let api = self.client.runtime_api();
+		api.initialize_block(&parent_id, &header)
+			.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?;
+
+		api.trace_extrinsic(&parent_id, extrinsics, extrinsic, config.unwrap_or_default())
+			.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
+			.map_err(|err| internal_err(format!("execution fatal: {:?}", err)))
+	}
+}
@@ -0,0 +1,27 @@
+//! EVM debug rpc interface.
+
+use ethereum_types::H256;
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+
+pub use rpc_impl_DebugApi::gen_server::DebugApi as DebugApiServer;
+
+use crate::call_request::CallRequest;
+use module_evm::{TraceConfig, TraceInfo};
+
+/// EVM debug rpc interface. The methods are unsafe, they replay blocks.
+#[rpc(server)]
+pub trait DebugApi<BlockHash> {
+	/// Trace a call against the state of the given block.
+	#[rpc(name = "debug_traceCall")]
+	fn trace_call(&self, _: CallRequest, config: Option<TraceConfig>, at: Option<BlockHash>) -> Result<TraceInfo>;
+
+	/// Replay the given block up to the extrinsic and trace its execution.
+	#[rpc(name = "debug_traceTransaction")]
+	fn trace_transaction(
+		&self,
+		block_hash: BlockHash,
+		extrinsic_hash: H256,
+		config: Option<TraceConfig>,
+	) -> Result<TraceInfo>;
+}
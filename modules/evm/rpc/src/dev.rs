@@ -0,0 +1,290 @@
+use ethereum_types::{H160, H256, U256, U64};
+use futures::{
+	channel::{mpsc::Sender, oneshot},
+	future, FutureExt, SinkExt, TryFutureExt,
+};
+use jsonrpc_core::{BoxFuture, Error, ErrorCode, Result};
+use sc_client_api::BlockBackend;
+use sc_consensus_manual_seal::EngineCommand;
+use sc_rpc::DenyUnsafe;
+use sc_transaction_pool_api::{TransactionPool, TransactionSource};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{
+	codec::{Codec, Encode},
+	generic::BlockId,
+	traits::{Block as BlockT, Extrinsic, Header as HeaderT, MaybeDisplay, MaybeFromStr, UniqueSaturatedInto},
+	SaturatedConversion,
+};
+use std::convert::TryFrom;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{marker::PhantomData, sync::Arc};
+
+use module_evm::{DevOperation, StateOverride};
+use module_evm_rpc_runtime_api::EVMRuntimeRPCApi;
+
+use crate::{dev_api::DevApi as DevApiT, ensure_api_v2, internal_err};
+
+/// Maximum number of blocks sealed by one `evm_mine` call.
+pub const MAX_MINED_BLOCKS: u64 = 10_000;
+
+/// Development chain rpc handler, submitting `dev_operation` extrinsics and
+/// sealing blocks through the manual seal task.
+pub struct DevApi<B: BlockT, C, P, Balance> {
+	client: Arc<C>,
+	pool: Arc<P>,
+	command_sink: Option<Sender<EngineCommand<B::Hash>>>,
+	deny_unsafe: DenyUnsafe,
+	_marker: PhantomData<Balance>,
+}
+
+impl<B: BlockT, C, P, Balance> DevApi<B, C, P, Balance> {
+	pub fn new(
+		client: Arc<C>,
+		pool: Arc<P>,
+		command_sink: Option<Sender<EngineCommand<B::Hash>>>,
+		deny_unsafe: DenyUnsafe,
+	) -> Self {
+		Self {
+			client,
+			pool,
+			command_sink,
+			deny_unsafe,
+			_marker: Default::default(),
+		}
+	}
+
+	/// Channel to the manual seal task, if blocks are sealed on demand.
+	fn command_sink(&self) -> Result<Sender<EngineCommand<B::Hash>>> {
+		self.deny_unsafe.check_if_safe()?;
+		self.command_sink.clone().ok_or_else(|| Error {
+			code: ErrorCode::MethodNotFound,
+			message: "sealing blocks needs the node to run with --sealing".into(),
+			data: None,
+		})
+	}
+}
+
+/// Seal a block on `parent_hash`, or on the best block, returning its hash.
+async fn seal<Hash>(mut command_sink: Sender<EngineCommand<Hash>>, parent_hash: Option<Hash>) -> Result<Hash> {
+	let (sender, receiver) = oneshot::channel();
+	command_sink
+		.send(EngineCommand::SealNewBlock {
+			create_empty: true,
+			finalize: false,
+			parent_hash,
+			sender: Some(sender),
+		})
+		.await
+		.map_err(|err| internal_err(format!("sealing failed: {:?}", err)))?;
+
+	match receiver.await {
+		Ok(Ok(created_block)) => Ok(created_block.hash),
+		Ok(Err(err)) => Err(internal_err(format!("sealing failed: {:?}", err))),
+		Err(_) => Err(internal_err("sealing was canceled")),
+	}
+}
+
+impl<B, C, P, Balance> DevApi<B, C, P, Balance>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + BlockBackend<B> + Send + Sync + 'static,
+	C::Api: EVMRuntimeRPCApi<B, Balance>,
+	P: TransactionPool<Block = B> + 'static,
+	Balance: Codec + MaybeDisplay + MaybeFromStr,
+{
+	fn submit(&self, operation: DevOperation) -> BoxFuture<Result<H256>> {
+		if let Err(err) = self.deny_unsafe.check_if_safe() {
+			return future::err(err.into()).boxed();
+		}
+
+		let hash = self.client.info().best_hash;
+		if let Err(err) = ensure_api_v2::<B, C, Balance>(&*self.client, &BlockId::Hash(hash)) {
+			return future::err(err).boxed();
+		}
+		// identical operations must not replace each other in the pool
+		let salt = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map(|duration| duration.as_nanos().saturated_into::<u64>())
+			.unwrap_or_default();
+
+		let extrinsic = match self
+			.client
+			.runtime_api()
+			.dev_extrinsic(&BlockId::Hash(hash), operation, salt)
+		{
+			Ok(Some(extrinsic)) => extrinsic,
+			Ok(None) => {
+				return future::err(Error {
+					code: ErrorCode::MethodNotFound,
+					message: "dev operations are disabled on this chain".into(),
+					data: None,
+				})
+				.boxed()
+			}
+			Err(err) => return future::err(internal_err(format!("runtime error: {:?}", err))).boxed(),
+		};
+
+		let extrinsic_hash = H256::from(extrinsic.using_encoded(sp_io::hashing::blake2_256));
+
+		self.pool
+			.submit_one(&BlockId::Hash(hash), TransactionSource::Local, extrinsic)
+			.map_ok(move |_| extrinsic_hash)
+			.map_err(|err| internal_err(format!("submit transaction failed: {:?}", err)))
+			.boxed()
+	}
+
+	/// Ban the signed extrinsics of the blocks after `snapshot` from the
+	/// pool, which resubmits the extrinsics of retracted blocks.
+	fn ban_extrinsics_after(&self, snapshot: B::Hash, best: B::Hash) -> Result<()> {
+		let mut hashes = Vec::new();
+		let mut hash = best;
+		while hash != snapshot {
+			let header = self
+				.client
+				.header(BlockId::Hash(hash))
+				.map_err(|err| internal_err(format!("fetch header failed: {:?}", err)))?
+				.ok_or_else(|| internal_err(format!("unknown block {:?}", hash)))?;
+			let extrinsics = self
+				.client
+				.block_body(&BlockId::Hash(hash))
+				.map_err(|err| internal_err(format!("fetch block body failed: {:?}", err)))?
+				.unwrap_or_default();
+
+			hashes.extend(
+				extrinsics
+					.iter()
+					.filter(|extrinsic| extrinsic.is_signed().unwrap_or(true))
+					.map(|extrinsic| self.pool.hash_of(extrinsic)),
+			);
+			hash = *header.parent_hash();
+		}
+
+		self.pool.remove_invalid(&hashes);
+		Ok(())
+	}
+}
+
+impl<B, C, P, Balance> DevApiT for DevApi<B, C, P, Balance>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + BlockBackend<B> + Send + Sync + 'static,
+	C::Api: EVMRuntimeRPCApi<B, Balance>,
+	P: TransactionPool<Block = B> + 'static,
+	Balance: Codec + MaybeDisplay + MaybeFromStr + Send + Sync + 'static,
+{
+	fn increase_time(&self, seconds: u64) -> BoxFuture<Result<H256>> {
+		self.submit(DevOperation::IncreaseTime(seconds.saturating_mul(1_000)))
+	}
+
+	fn set_next_block_timestamp(&self, timestamp: u64) -> BoxFuture<Result<H256>> {
+		self.submit(DevOperation::SetNextBlockTimestamp(timestamp.saturating_mul(1_000)))
+	}
+
+	fn increase_block_number(&self, blocks: u32) -> BoxFuture<Result<H256>> {
+		self.submit(DevOperation::IncreaseBlockNumber(blocks))
+	}
+
+	fn mine(&self, blocks: Option<U64>) -> BoxFuture<Result<U64>> {
+		let command_sink = match self.command_sink() {
+			Ok(command_sink) => command_sink,
+			Err(err) => return future::err(err).boxed(),
+		};
+		let blocks = blocks.map_or(1, |blocks| blocks.as_u64());
+		if blocks > MAX_MINED_BLOCKS {
+			return future::err(Error {
+				code: ErrorCode::InvalidParams,
+				message: format!("at most {} blocks are mined at once", MAX_MINED_BLOCKS),
+				data: None,
+			})
+			.boxed();
+		}
+
+		let client = self.client.clone();
+		async move {
+			for _ in 0..blocks {
+				seal(command_sink.clone(), None).await?;
+			}
+			Ok(U64::from(UniqueSaturatedInto::<u64>::unique_saturated_into(
+				client.info().best_number,
+			)))
+		}
+		.boxed()
+	}
+
+	fn set_balance(&self, address: H160, balance: U256) -> BoxFuture<Result<H256>> {
+		let balance = match u128::try_from(balance) {
+			Ok(balance) => balance,
+			Err(_) => {
+				return future::err(Error {
+					code: ErrorCode::InvalidParams,
+					message: "balance exceeds the maximum balance".into(),
+					data: None,
+				})
+				.boxed()
+			}
+		};
+		self.submit(DevOperation::SetState(StateOverride {
+			address,
+			balance: Some(balance),
+			..Default::default()
+		}))
+	}
+
+	fn set_code(&self, address: H160, code: Bytes) -> BoxFuture<Result<H256>> {
+		self.submit(DevOperation::SetState(StateOverride {
+			address,
+			code: Some(code.0),
+			..Default::default()
+		}))
+	}
+
+	fn set_storage_at(&self, address: H160, index: H256, value: H256) -> BoxFuture<Result<H256>> {
+		self.submit(DevOperation::SetState(StateOverride {
+			address,
+			state_diff: vec![(index, value)],
+			..Default::default()
+		}))
+	}
+
+	fn snapshot(&self) -> Result<U64> {
+		self.command_sink()?;
+		// snapshots are the blocks of the best chain
+		Ok(U64::from(UniqueSaturatedInto::<u64>::unique_saturated_into(
+			self.client.info().best_number,
+		)))
+	}
+
+	fn revert(&self, id: U64) -> BoxFuture<Result<bool>> {
+		let command_sink = match self.command_sink() {
+			Ok(command_sink) => command_sink,
+			Err(err) => return future::err(err).boxed(),
+		};
+		let info = self.client.info();
+		let best: u64 = info.best_number.unique_saturated_into();
+		let id = id.as_u64();
+		if id > best {
+			return future::ok(false).boxed();
+		}
+		let snapshot = match self.client.hash(id.saturated_into()) {
+			Ok(Some(hash)) => hash,
+			Ok(None) => return future::ok(false).boxed(),
+			Err(err) => return future::err(internal_err(format!("fetch block hash failed: {:?}", err))).boxed(),
+		};
+		if let Err(err) = self.ban_extrinsics_after(snapshot, info.best_hash) {
+			return future::err(err).boxed();
+		}
+
+		async move {
+			// the new blocks become the best chain once they outnumber the
+			// blocks after the snapshot
+			let mut parent_hash = snapshot;
+			for _ in id..=best {
+				parent_hash = seal(command_sink.clone(), Some(parent_hash)).await?;
+			}
+			Ok(true)
+		}
+		.boxed()
+	}
+}
@@ -0,0 +1,59 @@
+//! Development chain rpc interface.
+
+use ethereum_types::{H160, H256, U256, U64};
+use jsonrpc_core::{BoxFuture, Result};
+use jsonrpc_derive::rpc;
+use sp_core::Bytes;
+
+pub use rpc_impl_DevApi::gen_server::DevApi as DevApiServer;
+
+/// Development chain rpc interface, manipulating the time and the state of
+/// the chain for contract tests.
+///
+/// The manipulations are unsigned extrinsics applied in the next block, the
+/// returned hash is the hash of the extrinsic. Mining, snapshots and reverts
+/// seal blocks and need the node to run with `--sealing`. All the methods are
+/// unsafe.
+#[rpc(server)]
+pub trait DevApi {
+	/// Move the time of the current and next blocks forward by `seconds`.
+	#[rpc(name = "evm_increaseTime")]
+	fn increase_time(&self, seconds: u64) -> BoxFuture<Result<H256>>;
+
+	/// Set the timestamp of the next block, in seconds. It must be after the
+	/// current time of the chain.
+	#[rpc(name = "evm_setNextBlockTimestamp")]
+	fn set_next_block_timestamp(&self, timestamp: u64) -> BoxFuture<Result<H256>>;
+
+	/// Move the block number seen by the block number based periods, such as
+	/// the PoC unbonding periods, forward by `blocks`.
+	#[rpc(name = "evm_increaseBlockNumber")]
+	fn increase_block_number(&self, blocks: u32) -> BoxFuture<Result<H256>>;
+
+	/// Seal `blocks` blocks, one by default, returning the new best block
+	/// number.
+	#[rpc(name = "evm_mine")]
+	fn mine(&self, blocks: Option<U64>) -> BoxFuture<Result<U64>>;
+
+	/// Set the free balance of an account.
+	#[rpc(name = "evm_setBalance")]
+	fn set_balance(&self, address: H160, balance: U256) -> BoxFuture<Result<H256>>;
+
+	/// Set the code of an account, the account becomes a deployed contract.
+	#[rpc(name = "evm_setCode")]
+	fn set_code(&self, address: H160, code: Bytes) -> BoxFuture<Result<H256>>;
+
+	/// Set a storage slot of a contract.
+	#[rpc(name = "evm_setStorageAt")]
+	fn set_storage_at(&self, address: H160, index: H256, value: H256) -> BoxFuture<Result<H256>>;
+
+	/// Snapshot the state of the chain, returning the id to revert to.
+	#[rpc(name = "evm_snapshot")]
+	fn snapshot(&self) -> Result<U64>;
+
+	/// Revert the chain to a snapshot, sealing blocks on top of it until they
+	/// replace the later blocks as the best chain. Returns `false` for unknown
+	/// snapshots.
+	#[rpc(name = "evm_revert")]
+	fn revert(&self, id: U64) -> BoxFuture<Result<bool>>;
+}
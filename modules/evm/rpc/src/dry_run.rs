@@ -0,0 +1,151 @@
+use ethereum_types::U256;
+use jsonrpc_core::{Error, ErrorCode, Result};
+use sc_rpc::DenyUnsafe;
+use sp_api::{Core, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{Bytes, Decode};
+use sp_runtime::{
+	codec::Codec,
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, MaybeDisplay, MaybeFromStr, One},
+};
+use std::{marker::PhantomData, sync::Arc};
+
+use module_evm::ExitReason;
+use module_evm_rpc_runtime_api::DryRunApi as DryRunRuntimeApi;
+
+use crate::{
+	call_request::{BalanceChangeResponse, DryRunResponse, LogResponse, StorageDepositResponse},
+	decode_revert_message,
+	dry_run_api::DryRunApi as DryRunApiT,
+	error_on_execution_failure, internal_err,
+};
+
+/// Dry run rpc handler, backed by the `DryRunApi` runtime api.
+pub struct DryRunApi<B, C, AccountId, Balance> {
+	client: Arc<C>,
+	deny_unsafe: DenyUnsafe,
+	_marker: PhantomData<(B, AccountId, Balance)>,
+}
+
+impl<B, C, AccountId, Balance> DryRunApi<B, C, AccountId, Balance> {
+	pub fn new(client: Arc<C>, deny_unsafe: DenyUnsafe) -> Self {
+		Self {
+			client,
+			deny_unsafe,
+			_marker: Default::default(),
+		}
+	}
+}
+
+impl<B, C, AccountId, Balance> DryRunApiT<<B as BlockT>::Hash, AccountId> for DryRunApi<B, C, AccountId, Balance>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + 'static,
+	C::Api: Core<B>,
+	C::Api: DryRunRuntimeApi<B, AccountId, Balance>,
+	AccountId: Codec + Send + Sync + 'static,
+	Balance: Codec + MaybeDisplay + MaybeFromStr + Send + Sync + 'static + Into<U256>,
+{
+	fn dry_run(
+		&self,
+		extrinsic: Bytes,
+		from: Option<AccountId>,
+		at: Option<<B as BlockT>::Hash>,
+	) -> Result<DryRunResponse<AccountId>> {
+		self.deny_unsafe.check_if_safe()?;
+		let hash = at.unwrap_or_else(|| self.client.info().best_hash);
+		let id = BlockId::Hash(hash);
+
+		let extrinsic: <B as BlockT>::Extrinsic = Decode::decode(&mut &*extrinsic).map_err(|err| Error {
+			code: ErrorCode::InvalidParams,
+			message: "Unable to decode extrinsic.".into(),
+			data: Some(format!("{:?}", err).into()),
+		})?;
+
+		let parent = self
+			.client
+			.header(id)
+			.map_err(|err| internal_err(format!("fetch header failed: {:?}", err)))?
+			.ok_or_else(|| Error {
+				code: ErrorCode::InvalidParams,
+				message: "Unknown block hash".into(),
+				data: None,
+			})?;
+
+		// apply the extrinsic in a new block on top of the given one
+		let header = <<B as BlockT>::Header as HeaderT>::new(
+			*parent.number() + One::one(),
+			Default::default(),
+			Default::default(),
+			hash,
+			Default::default(),
+		);
+		let api = self.client.runtime_api();
+		api.initialize_block(&id, &header)
+			.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?;
+
+		let info = api
+			.dry_run_extrinsic(&id, extrinsic, from)
+			.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?;
+
+		let error = match info.result {
+			Ok(Ok(())) => None,
+			Ok(Err(err)) => Some(format!("dispatch error: {:?}", err)),
+			Err(err) => Some(format!("invalid transaction: {:?}", err)),
+		};
+
+		let (evm_error, revert_reason) = match info.evm_failure {
+			Some((exit_reason, output)) => {
+				let revert_reason = match exit_reason {
+					ExitReason::Revert(_) => decode_revert_message(&output),
+					_ => None,
+				};
+				(
+					error_on_execution_failure(&exit_reason, &output)
+						.err()
+						.map(|err| err.message),
+					revert_reason,
+				)
+			}
+			None => (None, None),
+		};
+
+		Ok(DryRunResponse {
+			error,
+			events: info.events.into_iter().map(Bytes).collect(),
+			logs: info
+				.logs
+				.into_iter()
+				.map(|log| LogResponse {
+					address: log.address,
+					topics: log.topics,
+					data: Bytes(log.data),
+				})
+				.collect(),
+			balance_changes: info
+				.balance_changes
+				.into_iter()
+				.map(|change| BalanceChangeResponse {
+					who: change.who,
+					free_before: change.free_before.into(),
+					free_after: change.free_after.into(),
+					reserved_before: change.reserved_before.into(),
+					reserved_after: change.reserved_after.into(),
+				})
+				.collect(),
+			storage_deposits: info
+				.storage_deposits
+				.into_iter()
+				.map(|deposit| StorageDepositResponse {
+					who: deposit.who,
+					contract: deposit.contract,
+					amount: deposit.amount.into(),
+					refunded: deposit.refunded,
+				})
+				.collect(),
+			evm_error,
+			revert_reason,
+		})
+	}
+}
@@ -0,0 +1,18 @@
+//! Dry run rpc interface.
+
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+use sp_core::Bytes;
+
+pub use rpc_impl_DryRunApi::gen_server::DryRunApi as DryRunApiServer;
+
+use crate::call_request::DryRunResponse;
+
+/// Dry run rpc interface. The method is unsafe.
+#[rpc(server)]
+pub trait DryRunApi<BlockHash, AccountId> {
+	/// Apply the SCALE encoded extrinsic on top of the given block and return
+	/// its side effects. An unsigned extrinsic is dispatched as `from`.
+	#[rpc(name = "evm_dryRun")]
+	fn dry_run(&self, extrinsic: Bytes, from: Option<AccountId>, at: Option<BlockHash>) -> Result<DryRunResponse<AccountId>>;
+}
@@ -0,0 +1,574 @@
+use ethereum_types::{H160, H256, H64, U256, U64};
+use futures::{future, FutureExt, TryFutureExt};
+use jsonrpc_core::{BoxFuture, Error, ErrorCode, Result};
+use sc_client_api::{backend::AuxStore, BlockBackend};
+use sc_transaction_pool_api::{TransactionPool, TransactionSource};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{
+	codec::{Codec, Decode},
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, MaybeDisplay, MaybeFromStr, UniqueSaturatedInto},
+	SaturatedConversion,
+};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use module_evm::EthereumHeader;
+use pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi;
+
+use crate::{
+	block_number::BlockNumber,
+	call_request::{AccessListResponse, EthBlock, EthCallRequest, FeeHistory},
+	ensure_api_v2,
+	eth_api::EthApi as EthApiT,
+	has_api_v2, internal_err,
+	logs::{EthLog, Filter, LogIndexer},
+	receipt::{EthReceipt, ReceiptIndexer},
+	EVMApi, EVMRuntimeRPCApi,
+};
+
+/// Maximum number of blocks scanned by a single log query.
+pub const MAX_LOG_BLOCK_RANGE: u64 = 10_000;
+
+/// Maximum number of blocks returned by `eth_feeHistory`.
+pub const MAX_FEE_HISTORY_BLOCKS: u64 = 1024;
+
+/// Keccak hash of the RLP encoded empty uncle list.
+const EMPTY_UNCLES_HASH: [u8; 32] = [
+	0x1d, 0xcc, 0x4d, 0xe8, 0xde, 0xc7, 0x5d, 0x7a, 0xab, 0x85, 0xb5, 0x67, 0xb6, 0xcc, 0xd4, 0x1a, 0xd3, 0x12, 0x45,
+	0x1b, 0x94, 0x8a, 0x74, 0x13, 0xf0, 0xa1, 0x42, 0xfd, 0x40, 0xd4, 0x93, 0x47,
+];
+
+/// Maximum number of installed filters.
+pub const MAX_FILTERS: usize = 500;
+
+/// Filters not polled for this long are uninstalled.
+pub const FILTER_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// An installed log filter, the next block to poll and the time of the last
+/// poll.
+struct FilterPoll {
+	filter: Filter,
+	next_block: u64,
+	last_poll: Instant,
+}
+
+#[derive(Default)]
+struct Filters {
+	next_id: u64,
+	polls: BTreeMap<u64, FilterPoll>,
+}
+
+impl Filters {
+	/// Uninstall the filters not polled since `FILTER_TIMEOUT`.
+	fn prune(&mut self, now: Instant) {
+		self.polls
+			.retain(|_, poll| now.saturating_duration_since(poll.last_poll) < FILTER_TIMEOUT);
+	}
+
+	/// Install a filter, `None` if `MAX_FILTERS` are installed.
+	fn insert(&mut self, filter: Filter, next_block: u64, now: Instant) -> Option<u64> {
+		self.prune(now);
+		if self.polls.len() >= MAX_FILTERS {
+			return None;
+		}
+
+		let id = self.next_id;
+		self.next_id += 1;
+		self.polls.insert(
+			id,
+			FilterPoll {
+				filter,
+				next_block,
+				last_poll: now,
+			},
+		);
+		Some(id)
+	}
+
+	/// The installed filter `id`, refreshing its last poll.
+	fn poll(&mut self, id: u64, now: Instant) -> Result<&mut FilterPoll> {
+		self.prune(now);
+		let poll = self
+			.polls
+			.get_mut(&id)
+			.ok_or_else(|| invalid_params(format!("Unknown filter id: {}", id)))?;
+		poll.last_poll = now;
+		Ok(poll)
+	}
+}
+
+/// Ethereum compatible rpc handler, backed by the `EVMRuntimeRPCApi`.
+pub struct EthApi<B, C, P, Balance> {
+	client: Arc<C>,
+	pool: Arc<P>,
+	evm: EVMApi<B, C, Balance>,
+	logs: LogIndexer<B, C, Balance>,
+	receipts: ReceiptIndexer<B, C, Balance>,
+	filters: Mutex<Filters>,
+}
+
+impl<B, C, P, Balance> EthApi<B, C, P, Balance> {
+	pub fn new(client: Arc<C>, pool: Arc<P>) -> Self {
+		Self {
+			evm: EVMApi::new(client.clone()),
+			logs: LogIndexer::new(client.clone()),
+			receipts: ReceiptIndexer::new(client.clone()),
+			filters: Default::default(),
+			client,
+			pool,
+		}
+	}
+}
+
+impl<B, C, P, Balance> EthApi<B, C, P, Balance>
+where
+	B: BlockT,
+	C: HeaderBackend<B>,
+{
+	/// Resolve the block hash for an optional block number, defaults to the best block.
+	fn block_hash(&self, number: Option<BlockNumber>) -> Result<B::Hash> {
+		let number = match number.unwrap_or_default() {
+			BlockNumber::Latest | BlockNumber::Pending => return Ok(self.client.info().best_hash),
+			BlockNumber::Earliest => 0,
+			BlockNumber::Num(number) => number,
+		};
+
+		self.client
+			.hash(number.saturated_into())
+			.map_err(|err| internal_err(format!("fetch block hash failed: {:?}", err)))?
+			.ok_or(Error {
+				code: ErrorCode::InvalidParams,
+				message: format!("Unknown block number: {}", number),
+				data: None,
+			})
+	}
+
+	/// Resolve an optional block number, defaults to the best block.
+	fn block_number_of(&self, number: Option<BlockNumber>) -> u64 {
+		match number.unwrap_or_default() {
+			BlockNumber::Latest | BlockNumber::Pending => self.client.info().best_number.unique_saturated_into(),
+			BlockNumber::Earliest => 0,
+			BlockNumber::Num(number) => number,
+		}
+	}
+}
+
+fn invalid_params<T: ToString>(message: T) -> Error {
+	Error {
+		code: ErrorCode::InvalidParams,
+		message: message.to_string(),
+		data: None,
+	}
+}
+
+/// Ethereum projection of the block, `None` if it is unknown.
+pub(crate) fn eth_block<B, C, Balance>(
+	client: &C,
+	receipts: &ReceiptIndexer<B, C, Balance>,
+	hash: B::Hash,
+) -> Result<Option<EthBlock>>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + BlockBackend<B> + AuxStore,
+	C::Api: EVMRuntimeRPCApi<B, Balance>,
+	Balance: Codec + MaybeDisplay + MaybeFromStr,
+{
+	let header = match client
+		.header(BlockId::Hash(hash))
+		.map_err(|err| internal_err(format!("fetch block header failed: {:?}", err)))?
+	{
+		Some(header) => header,
+		None => return Ok(None),
+	};
+
+	let api = client.runtime_api();
+	let number: u64 = (*header.number()).unique_saturated_into();
+	let parent_hash = H256::from_slice(header.parent_hash().as_ref());
+	let ethereum_header_at = |at: B::Hash| -> Result<Option<EthereumHeader>> {
+		if !has_api_v2::<B, C, Balance>(client, &BlockId::Hash(at))? {
+			return Ok(None);
+		}
+		api.ethereum_header(&BlockId::Hash(at), number)
+			.map_err(|err| internal_err(format!("runtime error: {:?}", err)))
+	};
+	// the best block keeps the headers of the recent blocks, whose own state
+	// may be pruned, blocks of other forks have their header in their state
+	let ethereum_header = match ethereum_header_at(client.info().best_hash)? {
+		Some(ethereum_header) if ethereum_header.parent_hash == parent_hash => Some(ethereum_header),
+		_ => ethereum_header_at(hash)?,
+	}
+	.unwrap_or_default();
+	// the base fee of a block is set by its parent, runtimes before version 2
+	// of the api have no base fee
+	let base_fee_at = BlockId::Hash(if number == 0 { hash } else { *header.parent_hash() });
+	let base_fee_per_gas = if has_api_v2::<B, C, Balance>(client, &base_fee_at)? {
+		api.base_fee(&base_fee_at)
+			.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
+	} else {
+		U256::zero()
+	};
+
+	let transactions = receipts
+		.block_receipts(hash)
+		.map_err(internal_err)?
+		.into_iter()
+		.map(|(extrinsic_hash, receipt)| receipt.transaction_hash.unwrap_or(extrinsic_hash))
+		.collect();
+
+	Ok(Some(EthBlock {
+		number: U256::from(number),
+		hash: H256::from_slice(hash.as_ref()),
+		parent_hash,
+		nonce: H64::zero(),
+		sha3_uncles: H256::from(EMPTY_UNCLES_HASH),
+		logs_bloom: ethereum_header.logs_bloom,
+		transactions_root: H256::from_slice(header.extrinsics_root().as_ref()),
+		state_root: H256::from_slice(header.state_root().as_ref()),
+		receipts_root: H256::zero(),
+		miner: H160::zero(),
+		difficulty: U256::zero(),
+		total_difficulty: U256::zero(),
+		extra_data: Bytes(Vec::new()),
+		gas_limit: U256::from(crate::GAS_LIMIT),
+		gas_used: U256::from(ethereum_header.gas_used),
+		timestamp: U256::from(ethereum_header.timestamp),
+		transactions,
+		uncles: Vec::new(),
+		base_fee_per_gas,
+	}))
+}
+
+fn filter_id(id: U256) -> Result<u64> {
+	if id > U256::from(u64::MAX) {
+		return Err(invalid_params(format!("Unknown filter id: {}", id)));
+	}
+	Ok(id.low_u64())
+}
+
+impl<B, C, P, Balance> EthApi<B, C, P, Balance>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + BlockBackend<B> + AuxStore,
+	C::Api: EVMRuntimeRPCApi<B, Balance>,
+	Balance: Codec + MaybeDisplay + MaybeFromStr,
+{
+	/// Logs matching the filter in the inclusive block range `from..=to`.
+	fn logs_in_range(&self, filter: &Filter, from: u64, to: u64) -> Result<Vec<EthLog>> {
+		if to >= from && to - from >= MAX_LOG_BLOCK_RANGE {
+			return Err(invalid_params(format!(
+				"Block range too large, the maximum is {} blocks",
+				MAX_LOG_BLOCK_RANGE
+			)));
+		}
+
+		let mut logs = Vec::new();
+		for number in from..=to {
+			let hash = match self
+				.client
+				.hash(number.saturated_into())
+				.map_err(|err| internal_err(format!("fetch block hash failed: {:?}", err)))?
+			{
+				Some(hash) => hash,
+				None => break,
+			};
+
+			logs.extend(self.logs.filter_block(hash, number, filter).map_err(internal_err)?);
+		}
+
+		Ok(logs)
+	}
+
+	/// Logs matching the filter, either in the given block or in the block range.
+	fn filtered_logs(&self, filter: &Filter) -> Result<Vec<EthLog>> {
+		if let Some(block_hash) = filter.block_hash {
+			let hash = B::Hash::decode(&mut block_hash.as_bytes())
+				.map_err(|_| invalid_params(format!("Invalid block hash: {:?}", block_hash)))?;
+			let number = self
+				.client
+				.number(hash)
+				.map_err(|err| internal_err(format!("fetch block number failed: {:?}", err)))?
+				.ok_or_else(|| invalid_params(format!("Unknown block hash: {:?}", block_hash)))?;
+
+			return self
+				.logs
+				.filter_block(hash, number.unique_saturated_into(), filter)
+				.map_err(internal_err);
+		}
+
+		let from = self.block_number_of(filter.from_block);
+		let to = self.block_number_of(filter.to_block);
+		self.logs_in_range(filter, from, to)
+	}
+}
+
+impl<B, C, P, Balance> EthApiT for EthApi<B, C, P, Balance>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + BlockBackend<B> + AuxStore + Send + Sync + 'static,
+	C::Api: EVMRuntimeRPCApi<B, Balance>,
+	C::Api: TransactionPaymentApi<B, Balance>,
+	P: TransactionPool<Block = B> + Send + Sync + 'static,
+	Balance: Codec + MaybeDisplay + MaybeFromStr + Default + Send + Sync + 'static + TryFrom<u128> + Into<U256>,
+{
+	fn chain_id(&self) -> Result<U64> {
+		let hash = self.client.info().best_hash;
+		ensure_api_v2::<B, C, Balance>(&*self.client, &BlockId::Hash(hash))?;
+		let chain_id = self
+			.client
+			.runtime_api()
+			.chain_id(&BlockId::Hash(hash))
+			.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?;
+
+		Ok(U64::from(chain_id))
+	}
+
+	fn block_number(&self) -> Result<U256> {
+		let number: u64 = self.client.info().best_number.unique_saturated_into();
+		Ok(U256::from(number))
+	}
+
+	fn balance(&self, address: H160, number: Option<BlockNumber>) -> Result<U256> {
+		let hash = self.block_hash(number)?;
+		ensure_api_v2::<B, C, Balance>(&*self.client, &BlockId::Hash(hash))?;
+		let account = self
+			.client
+			.runtime_api()
+			.account_basic(&BlockId::Hash(hash), address)
+			.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?;
+
+		Ok(account.balance)
+	}
+
+	fn code_at(&self, address: H160, number: Option<BlockNumber>) -> Result<Bytes> {
+		let hash = self.block_hash(number)?;
+		ensure_api_v2::<B, C, Balance>(&*self.client, &BlockId::Hash(hash))?;
+		let code = self
+			.client
+			.runtime_api()
+			.code_at(&BlockId::Hash(hash), address)
+			.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?;
+
+		Ok(Bytes(code))
+	}
+
+	fn storage_at(&self, address: H160, index: U256, number: Option<BlockNumber>) -> Result<H256> {
+		let hash = self.block_hash(number)?;
+		let mut key = [0u8; 32];
+		index.to_big_endian(&mut key);
+
+		ensure_api_v2::<B, C, Balance>(&*self.client, &BlockId::Hash(hash))?;
+		self.client
+			.runtime_api()
+			.storage_at(&BlockId::Hash(hash), address, H256::from(key))
+			.map_err(|err| internal_err(format!("runtime error: {:?}", err)))
+	}
+
+	fn call(&self, request: EthCallRequest, number: Option<BlockNumber>) -> Result<Bytes> {
+		let hash = self.block_hash(number)?;
+		self.evm.call_at(request.into(), hash)
+	}
+
+	fn estimate_gas(&self, request: EthCallRequest, number: Option<BlockNumber>) -> Result<U256> {
+		let hash = self.block_hash(number)?;
+		self.evm.estimate_gas_at(request.into(), hash, Default::default())
+	}
+
+	fn create_access_list(&self, request: EthCallRequest, number: Option<BlockNumber>) -> Result<AccessListResponse> {
+		let hash = self.block_hash(number)?;
+		self.evm.create_access_list_at(request.into(), hash)
+	}
+
+	fn gas_price(&self) -> Result<U256> {
+		let hash = self.client.info().best_hash;
+		ensure_api_v2::<B, C, Balance>(&*self.client, &BlockId::Hash(hash))?;
+		self.client
+			.runtime_api()
+			.base_fee(&BlockId::Hash(hash))
+			.map_err(|err| internal_err(format!("runtime error: {:?}", err)))
+	}
+
+	fn fee_history(
+		&self,
+		block_count: U256,
+		newest_block: BlockNumber,
+		reward_percentiles: Option<Vec<f64>>,
+	) -> Result<FeeHistory> {
+		let block_count = block_count.min(U256::from(MAX_FEE_HISTORY_BLOCKS)).as_u64();
+		let best: u64 = self.client.info().best_number.unique_saturated_into();
+		let newest = self.block_number_of(Some(newest_block)).min(best);
+		let oldest = (newest + 1).saturating_sub(block_count);
+
+		let api = self.client.runtime_api();
+		let base_fee = |number: u64| -> Result<U256> {
+			let hash = self.block_hash(Some(BlockNumber::Num(number)))?;
+			ensure_api_v2::<B, C, Balance>(&*self.client, &BlockId::Hash(hash))?;
+			api.base_fee(&BlockId::Hash(hash))
+				.map_err(|err| internal_err(format!("runtime error: {:?}", err)))
+		};
+
+		let mut base_fee_per_gas = Vec::new();
+		let mut gas_used_ratio = Vec::new();
+		if block_count > 0 {
+			for number in oldest..=newest {
+				// the base fee of a block is set by its parent
+				base_fee_per_gas.push(base_fee(number.saturating_sub(1))?);
+
+				let hash = self.block_hash(Some(BlockNumber::Num(number)))?;
+				ensure_api_v2::<B, C, Balance>(&*self.client, &BlockId::Hash(hash))?;
+				let fullness = api
+					.block_fullness(&BlockId::Hash(hash))
+					.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?;
+				gas_used_ratio.push(fullness.deconstruct() as f64 / 1_000_000_000f64);
+			}
+			base_fee_per_gas.push(base_fee(newest)?);
+		}
+
+		// there are no priority fees
+		let reward = reward_percentiles
+			.map(|percentiles| vec![vec![U256::zero(); percentiles.len()]; gas_used_ratio.len()]);
+
+		Ok(FeeHistory {
+			oldest_block: U256::from(oldest),
+			base_fee_per_gas,
+			gas_used_ratio,
+			reward,
+		})
+	}
+
+	fn transaction_count(&self, address: H160, number: Option<BlockNumber>) -> Result<U256> {
+		let hash = self.block_hash(number)?;
+		ensure_api_v2::<B, C, Balance>(&*self.client, &BlockId::Hash(hash))?;
+		let account = self
+			.client
+			.runtime_api()
+			.account_basic(&BlockId::Hash(hash), address)
+			.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?;
+
+		Ok(account.nonce)
+	}
+
+	fn logs(&self, filter: Filter) -> Result<Vec<EthLog>> {
+		self.filtered_logs(&filter)
+	}
+
+	fn new_filter(&self, filter: Filter) -> Result<U256> {
+		let next_block = self.block_number_of(None) + 1;
+		let mut filters = self.filters.lock().map_err(|_| internal_err("filters lock poisoned"))?;
+
+		let id = filters
+			.insert(filter, next_block, Instant::now())
+			.ok_or_else(|| invalid_params(format!("Too many filters, the maximum is {}", MAX_FILTERS)))?;
+
+		Ok(U256::from(id))
+	}
+
+	fn filter_changes(&self, id: U256) -> Result<Vec<EthLog>> {
+		let id = filter_id(id)?;
+		let best = self.block_number_of(None);
+
+		let (filter, from, to) = {
+			let mut filters = self.filters.lock().map_err(|_| internal_err("filters lock poisoned"))?;
+			let poll = filters.poll(id, Instant::now())?;
+
+			let to = match poll.filter.to_block {
+				Some(to_block) => best.min(self.block_number_of(Some(to_block))),
+				None => best,
+			};
+			let from = poll
+				.next_block
+				.max(self.block_number_of(poll.filter.from_block.or(Some(BlockNumber::Earliest))))
+				.max(to.saturating_sub(MAX_LOG_BLOCK_RANGE - 1));
+			if from > to {
+				return Ok(Vec::new());
+			}
+			poll.next_block = to + 1;
+
+			(poll.filter.clone(), from, to)
+		};
+
+		self.logs_in_range(&filter, from, to)
+	}
+
+	fn filter_logs(&self, id: U256) -> Result<Vec<EthLog>> {
+		let id = filter_id(id)?;
+		let filter = {
+			let mut filters = self.filters.lock().map_err(|_| internal_err("filters lock poisoned"))?;
+			filters.poll(id, Instant::now())?.filter.clone()
+		};
+
+		self.filtered_logs(&filter)
+	}
+
+	fn uninstall_filter(&self, id: U256) -> Result<bool> {
+		let id = filter_id(id)?;
+		let mut filters = self.filters.lock().map_err(|_| internal_err("filters lock poisoned"))?;
+		filters.prune(Instant::now());
+		Ok(filters.polls.remove(&id).is_some())
+	}
+
+	fn send_raw_transaction(&self, bytes: Bytes) -> BoxFuture<Result<H256>> {
+		let hash = self.client.info().best_hash;
+		if let Err(err) = ensure_api_v2::<B, C, Balance>(&*self.client, &BlockId::Hash(hash)) {
+			return future::err(err).boxed();
+		}
+		let extrinsic = match self
+			.client
+			.runtime_api()
+			.transact_extrinsic(&BlockId::Hash(hash), bytes.to_vec())
+		{
+			Ok(extrinsic) => extrinsic,
+			Err(err) => return future::err(internal_err(format!("runtime error: {:?}", err))).boxed(),
+		};
+		let transaction_hash = H256::from(sp_io::hashing::keccak_256(&bytes));
+
+		self.pool
+			.submit_one(&BlockId::Hash(hash), TransactionSource::External, extrinsic)
+			.map_ok(move |_| transaction_hash)
+			.map_err(|err| internal_err(format!("submit transaction failed: {:?}", err)))
+			.boxed()
+	}
+
+	fn transaction_receipt(&self, hash: H256) -> Result<Option<EthReceipt>> {
+		self.receipts.receipt(hash).map_err(internal_err)
+	}
+
+	fn block_by_number(&self, number: BlockNumber, _full: bool) -> Result<Option<EthBlock>> {
+		match self
+			.client
+			.hash(self.block_number_of(Some(number)).saturated_into())
+			.map_err(|err| internal_err(format!("fetch block hash failed: {:?}", err)))?
+		{
+			Some(hash) => eth_block(&*self.client, &self.receipts, hash),
+			None => Ok(None),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn filters_should_expire_and_be_bounded() {
+		let mut filters = Filters::default();
+		let start = Instant::now();
+
+		let id = filters.insert(Filter::default(), 1, start).unwrap();
+		let polled = filters.insert(Filter::default(), 1, start).unwrap();
+		assert!(filters.poll(polled, start + FILTER_TIMEOUT / 2).is_ok());
+
+		// the filter not polled since the timeout is uninstalled
+		assert!(filters.poll(id, start + FILTER_TIMEOUT).is_err());
+		assert!(filters.poll(polled, start + FILTER_TIMEOUT).is_ok());
+
+		for _ in 1..MAX_FILTERS {
+			assert!(filters.insert(Filter::default(), 1, start + FILTER_TIMEOUT).is_some());
+		}
+		assert_eq!(filters.insert(Filter::default(), 1, start + FILTER_TIMEOUT), None);
+	}
+}
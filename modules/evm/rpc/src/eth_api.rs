@@ -0,0 +1,103 @@
+//! Ethereum compatible rpc interface.
+
+use ethereum_types::{H160, H256, U256, U64};
+use jsonrpc_core::{BoxFuture, Result};
+use jsonrpc_derive::rpc;
+use sp_core::Bytes;
+
+pub use rpc_impl_EthApi::gen_server::EthApi as EthApiServer;
+
+use crate::{
+	block_number::BlockNumber,
+	call_request::{AccessListResponse, EthBlock, EthCallRequest, FeeHistory},
+	logs::{EthLog, Filter},
+	receipt::EthReceipt,
+};
+
+/// Ethereum compatible rpc interface.
+#[rpc(server)]
+pub trait EthApi {
+	/// Returns the chain id used for signing replay-protected transactions.
+	#[rpc(name = "eth_chainId")]
+	fn chain_id(&self) -> Result<U64>;
+
+	/// Returns the number of the most recent block.
+	#[rpc(name = "eth_blockNumber")]
+	fn block_number(&self) -> Result<U256>;
+
+	/// Returns the balance of the account of given address.
+	#[rpc(name = "eth_getBalance")]
+	fn balance(&self, address: H160, number: Option<BlockNumber>) -> Result<U256>;
+
+	/// Returns the code at given address.
+	#[rpc(name = "eth_getCode")]
+	fn code_at(&self, address: H160, number: Option<BlockNumber>) -> Result<Bytes>;
+
+	/// Returns the value from a storage position at a given address.
+	#[rpc(name = "eth_getStorageAt")]
+	fn storage_at(&self, address: H160, index: U256, number: Option<BlockNumber>) -> Result<H256>;
+
+	/// Call contract, returning the output data.
+	#[rpc(name = "eth_call")]
+	fn call(&self, request: EthCallRequest, number: Option<BlockNumber>) -> Result<Bytes>;
+
+	/// Estimate gas needed for execution of given contract.
+	#[rpc(name = "eth_estimateGas")]
+	fn estimate_gas(&self, request: EthCallRequest, number: Option<BlockNumber>) -> Result<U256>;
+
+	/// Generate the access list of given call, with the gas used when sending it.
+	#[rpc(name = "eth_createAccessList")]
+	fn create_access_list(&self, request: EthCallRequest, number: Option<BlockNumber>) -> Result<AccessListResponse>;
+
+	/// Returns the current gas price.
+	#[rpc(name = "eth_gasPrice")]
+	fn gas_price(&self) -> Result<U256>;
+
+	/// Returns the base fee and fullness of a range of blocks.
+	#[rpc(name = "eth_feeHistory")]
+	fn fee_history(
+		&self,
+		block_count: U256,
+		newest_block: BlockNumber,
+		reward_percentiles: Option<Vec<f64>>,
+	) -> Result<FeeHistory>;
+
+	/// Returns the number of transactions sent from an address.
+	#[rpc(name = "eth_getTransactionCount")]
+	fn transaction_count(&self, address: H160, number: Option<BlockNumber>) -> Result<U256>;
+
+	/// Returns the logs matching the given filter.
+	#[rpc(name = "eth_getLogs")]
+	fn logs(&self, filter: Filter) -> Result<Vec<EthLog>>;
+
+	/// Installs a log filter, returning its id.
+	#[rpc(name = "eth_newFilter")]
+	fn new_filter(&self, filter: Filter) -> Result<U256>;
+
+	/// Returns the logs matching the filter since the last poll.
+	#[rpc(name = "eth_getFilterChanges")]
+	fn filter_changes(&self, id: U256) -> Result<Vec<EthLog>>;
+
+	/// Returns all the logs matching the filter.
+	#[rpc(name = "eth_getFilterLogs")]
+	fn filter_logs(&self, id: U256) -> Result<Vec<EthLog>>;
+
+	/// Uninstalls a filter, returns whether it existed.
+	#[rpc(name = "eth_uninstallFilter")]
+	fn uninstall_filter(&self, id: U256) -> Result<bool>;
+
+	/// Submits a signed raw transaction to the transaction pool, returns its
+	/// transaction hash.
+	#[rpc(name = "eth_sendRawTransaction")]
+	fn send_raw_transaction(&self, bytes: Bytes) -> BoxFuture<Result<H256>>;
+
+	/// Returns the receipt of a transaction by Ethereum transaction hash or
+	/// extrinsic hash.
+	#[rpc(name = "eth_getTransactionReceipt")]
+	fn transaction_receipt(&self, hash: H256) -> Result<Option<EthReceipt>>;
+
+	/// Returns the block with the given number, with its transaction hashes
+	/// whether `full` transactions are requested or not.
+	#[rpc(name = "eth_getBlockByNumber")]
+	fn block_by_number(&self, number: BlockNumber, full: bool) -> Result<Option<EthBlock>>;
+}
@@ -0,0 +1,141 @@
+use futures::{future, Future, FutureExt, SinkExt, Stream, StreamExt};
+use jsonrpc_core::Result;
+use jsonrpc_pubsub::{
+	manager::SubscriptionManager,
+	typed::{Sink, Subscriber},
+	SubscriptionId,
+};
+use sc_client_api::{backend::AuxStore, BlockBackend, BlockchainEvents};
+use sc_rpc::SubscriptionTaskExecutor;
+use sc_transaction_pool_api::{InPoolTransaction, TransactionPool};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{
+	codec::Codec,
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, MaybeDisplay, MaybeFromStr, UniqueSaturatedInto},
+};
+use std::{marker::PhantomData, sync::Arc};
+
+use crate::{
+	eth::eth_block,
+	eth_pubsub_api::{EthPubSubApi as EthPubSubApiT, Kind, PubSubResult},
+	has_api_v2,
+	logs::{Filter, LogIndexer},
+	receipt::ReceiptIndexer,
+	EVMRuntimeRPCApi,
+};
+
+/// Ethereum compatible pub-sub rpc handler, fed by the block import and
+/// transaction pool notifications.
+pub struct EthPubSubApi<B, C, P, Balance> {
+	client: Arc<C>,
+	pool: Arc<P>,
+	subscriptions: SubscriptionManager,
+	_marker: PhantomData<(B, Balance)>,
+}
+
+impl<B, C, P, Balance> EthPubSubApi<B, C, P, Balance> {
+	pub fn new(client: Arc<C>, pool: Arc<P>, executor: SubscriptionTaskExecutor) -> Self {
+		Self {
+			client,
+			pool,
+			subscriptions: SubscriptionManager::new(Arc::new(executor)),
+			_marker: Default::default(),
+		}
+	}
+}
+
+/// Send the notifications of the stream to the subscriber until it goes away.
+fn forward<S>(stream: S, sink: Sink<PubSubResult>) -> impl Future<Output = ()>
+where
+	S: Stream<Item = PubSubResult> + Send + 'static,
+{
+	stream
+		.map(|result| Ok::<_, ()>(Ok(result)))
+		.forward(sink.sink_map_err(|err| {
+			log::warn!(target: "evm", "send eth_subscription notification failed: {:?}", err)
+		}))
+		.map(|_| ())
+}
+
+impl<B, C, P, Balance> EthPubSubApiT for EthPubSubApi<B, C, P, Balance>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + BlockBackend<B> + BlockchainEvents<B> + AuxStore,
+	C: Send + Sync + 'static,
+	C::Api: EVMRuntimeRPCApi<B, Balance>,
+	P: TransactionPool<Block = B> + Send + Sync + 'static,
+	Balance: Codec + MaybeDisplay + MaybeFromStr + Send + Sync + 'static,
+{
+	type Metadata = sc_rpc::Metadata;
+
+	fn subscribe(
+		&self,
+		_metadata: Self::Metadata,
+		subscriber: Subscriber<PubSubResult>,
+		kind: Kind,
+		filter: Option<Filter>,
+	) {
+		let client = self.client.clone();
+		let new_best_blocks = || {
+			self.client
+				.import_notification_stream()
+				.filter(|notification| future::ready(notification.is_new_best))
+		};
+
+		match kind {
+			Kind::NewHeads => {
+				let receipts = ReceiptIndexer::<B, C, Balance>::new(client.clone());
+				let stream = new_best_blocks().filter_map(move |notification| {
+					let block = eth_block(&*client, &receipts, notification.hash).unwrap_or_else(|err| {
+						log::warn!(target: "evm", "build block {:?} failed: {:?}", notification.hash, err);
+						None
+					});
+					future::ready(block.map(|block| PubSubResult::Header(Box::new(block))))
+				});
+				self.subscriptions.add(subscriber, |sink| forward(stream, sink));
+			}
+			Kind::Logs => {
+				let filter = filter.unwrap_or_default();
+				let logs = LogIndexer::<B, C, Balance>::new(client);
+				let stream = new_best_blocks()
+					.map(move |notification| {
+						let number = UniqueSaturatedInto::<u64>::unique_saturated_into(*notification.header.number());
+						let logs = logs
+							.filter_block(notification.hash, number, &filter)
+							.unwrap_or_else(|err| {
+								log::warn!(target: "evm", "filter logs of block {:?} failed: {}", notification.hash, err);
+								Vec::new()
+							});
+						futures::stream::iter(logs.into_iter().map(|log| PubSubResult::Log(Box::new(log))))
+					})
+					.flatten();
+				self.subscriptions.add(subscriber, |sink| forward(stream, sink));
+			}
+			Kind::NewPendingTransactions => {
+				let pool = self.pool.clone();
+				let stream = self.pool.import_notification_stream().filter_map(move |hash| {
+					// transactions waiting for an earlier nonce are not ready and skipped
+					let transaction_hash = pool.ready_transaction(&hash).and_then(|transaction| {
+						let at = BlockId::Hash(client.info().best_hash);
+						if !has_api_v2::<B, C, Balance>(&*client, &at).unwrap_or_default() {
+							return None;
+						}
+						client
+							.runtime_api()
+							.transaction_hash(&at, transaction.data().clone())
+							.ok()
+							.flatten()
+					});
+					future::ready(transaction_hash.map(PubSubResult::TransactionHash))
+				});
+				self.subscriptions.add(subscriber, |sink| forward(stream, sink));
+			}
+		}
+	}
+
+	fn unsubscribe(&self, _metadata: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
+}
@@ -0,0 +1,68 @@
+//! Ethereum compatible pub-sub rpc interface.
+
+use ethereum_types::H256;
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
+use serde::{Deserialize, Serialize};
+
+pub use rpc_impl_EthPubSubApi::gen_server::EthPubSubApi as EthPubSubApiServer;
+
+use crate::{
+	call_request::EthBlock,
+	logs::{EthLog, Filter},
+};
+
+/// Kind of `eth_subscribe` subscription.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Kind {
+	/// Every new best block.
+	NewHeads,
+	/// Logs of new best blocks matching the filter.
+	Logs,
+	/// Hashes of the EVM transactions entering the transaction pool.
+	NewPendingTransactions,
+}
+
+/// Notification of an `eth_subscribe` subscription.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum PubSubResult {
+	Header(Box<EthBlock>),
+	Log(Box<EthLog>),
+	TransactionHash(H256),
+}
+
+/// Ethereum compatible pub-sub rpc interface.
+#[rpc(server)]
+pub trait EthPubSubApi {
+	/// RPC metadata
+	type Metadata;
+
+	/// Subscribes to new heads, logs matching the filter or pending
+	/// transactions.
+	#[pubsub(subscription = "eth_subscription", subscribe, name = "eth_subscribe")]
+	fn subscribe(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<PubSubResult>,
+		kind: Kind,
+		filter: Option<Filter>,
+	);
+
+	/// Cancels a subscription, returns whether it existed.
+	#[pubsub(subscription = "eth_subscription", unsubscribe, name = "eth_unsubscribe")]
+	fn unsubscribe(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool>;
+}
+
+#[test]
+fn kind_deserialize_should_work() {
+	assert_eq!(serde_json::from_str::<Kind>(r#""newHeads""#).unwrap(), Kind::NewHeads);
+	assert_eq!(serde_json::from_str::<Kind>(r#""logs""#).unwrap(), Kind::Logs);
+	assert_eq!(
+		serde_json::from_str::<Kind>(r#""newPendingTransactions""#).unwrap(),
+		Kind::NewPendingTransactions
+	);
+	assert!(serde_json::from_str::<Kind>(r#""syncing""#).is_err());
+}
@@ -1,13 +1,17 @@
 //! EVM rpc interface.
 
-use ethereum_types::{U256, H160};
+use ethereum_types::{U256, H160, H256};
 use jsonrpc_core::Result;
 use jsonrpc_derive::rpc;
 use sp_core::Bytes;
 
 pub use rpc_impl_EVMApi::gen_server::EVMApi as EVMApiServer;
 
-use crate::call_request::{CallRequest, EstimateResourcesResponse};
+use module_evm::{SourceInfo, StorageRange};
+
+use crate::call_request::{
+	AccessListResponse, BatchCallRequest, BatchCallResponse, CallRequest, EstimateOptions, EstimateResourcesResponse,
+};
 
 /// EVM rpc interface.
 #[rpc(server)]
@@ -16,11 +20,43 @@ pub trait EVMApi<BlockHash> {
 	#[rpc(name = "evm_call")]
 	fn call(&self, _: CallRequest, at: Option<BlockHash>) -> Result<Bytes>;
 
-	/// Estimate gas needed for execution of given contract.
+	/// Estimate gas needed for execution of given contract. Exact estimations
+	/// return the recommended gas limit.
 	#[rpc(name = "evm_estimateGas")]
-	fn estimate_gas(&self, _: CallRequest, at: Option<BlockHash>) -> Result<U256>;
+	fn estimate_gas(&self, _: CallRequest, at: Option<BlockHash>, options: Option<EstimateOptions>) -> Result<U256>;
 
 	/// Estimate resources needed for execution of given contract.
 	#[rpc(name = "evm_estimateResources")]
-	fn estimate_resources(&self, from: H160, unsigned_extrinsic: Bytes, at: Option<BlockHash>) -> Result<EstimateResourcesResponse>;
+	fn estimate_resources(
+		&self,
+		from: H160,
+		unsigned_extrinsic: Bytes,
+		at: Option<BlockHash>,
+		options: Option<EstimateOptions>,
+	) -> Result<EstimateResourcesResponse>;
+
+	/// Call contracts at the same block, each against its own state overrides.
+	/// At most `MAX_BATCH_CALLS` calls are accepted.
+	#[rpc(name = "evm_callBatch")]
+	fn call_batch(&self, requests: Vec<BatchCallRequest>, at: Option<BlockHash>) -> Result<Vec<BatchCallResponse>>;
+
+	/// Generate the access list of given call, with the gas used when sending it.
+	#[rpc(name = "evm_createAccessList")]
+	fn create_access_list(&self, _: CallRequest, at: Option<BlockHash>) -> Result<AccessListResponse>;
+
+	/// Page through the storage of a contract, starting from the `start` key.
+	/// Returns the key to request the next page from.
+	#[rpc(name = "evm_storageRange")]
+	fn storage_range(
+		&self,
+		address: H160,
+		start: Option<H256>,
+		limit: Option<u32>,
+		at: Option<BlockHash>,
+	) -> Result<StorageRange>;
+
+	/// Submitted source metadata of the code of a contract, with whether it
+	/// was verified.
+	#[rpc(name = "evm_sourceInfo")]
+	fn source_info(&self, address: H160, at: Option<BlockHash>) -> Result<Option<SourceInfo>>;
 }
@@ -1,9 +1,9 @@
 #![allow(clippy::upper_case_acronyms)]
 
-use ethereum_types::{U256, H160};
+use ethereum_types::{U256, H160, H256};
 use jsonrpc_core::{Error, ErrorCode, Result, Value};
 use rustc_hex::ToHex;
-use sp_api::ProvideRuntimeApi;
+use sp_api::{ApiExt, ProvideRuntimeApi};
 use sp_blockchain::HeaderBackend;
 use sp_core::{Bytes, Decode};
 use sp_rpc::number::NumberOrHex;
@@ -16,16 +16,48 @@ use sp_runtime::{
 use std::convert::{TryFrom, TryInto};
 use std::{marker::PhantomData, sync::Arc};
 
-use call_request::{CallRequest, EstimateResourcesResponse};
+use call_request::{
+	AccessListResponse, BatchCallRequest, BatchCallResponse, CallRequest, EstimateOptions, EstimateResourcesResponse,
+	ExactLimits,
+};
 pub use module_evm::{AddressMapping, ExitError, ExitReason};
+use module_evm::{AccessListItem, BatchCall, CallInfo, CreateInfo, SourceInfo, StateOverride, StorageRange};
 pub use module_evm_rpc_runtime_api::EVMRuntimeRPCApi;
 
 use pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi;
 
+pub use crate::debug::DebugApi;
+pub use crate::debug_api::{DebugApi as DebugApiT, DebugApiServer};
+pub use crate::dev::DevApi;
+pub use crate::dev_api::{DevApi as DevApiT, DevApiServer};
+pub use crate::dry_run::DryRunApi;
+pub use crate::dry_run_api::{DryRunApi as DryRunApiT, DryRunApiServer};
 pub use crate::evm_api::{EVMApi as EVMApiT, EVMApiServer};
-
+pub use crate::eth::EthApi;
+pub use crate::eth_api::{EthApi as EthApiT, EthApiServer};
+pub use crate::eth_pubsub::EthPubSubApi;
+pub use crate::eth_pubsub_api::{EthPubSubApi as EthPubSubApiT, EthPubSubApiServer};
+pub use crate::logs::{log_indexer_task, LogIndexer};
+pub use crate::metrics::{metrics_task, EvmMetrics};
+pub use crate::receipt::ReceiptIndexer;
+pub use module_evm_rpc_runtime_api::{DryRunApi as DryRunRuntimeApi, EVMTraceApi};
+
+mod block_number;
 mod call_request;
+mod debug;
+mod debug_api;
+mod dev;
+mod dev_api;
+mod dry_run;
+mod dry_run_api;
+mod eth;
+mod eth_api;
+mod eth_pubsub;
+mod eth_pubsub_api;
 mod evm_api;
+mod logs;
+mod metrics;
+mod receipt;
 
 // default gas and storage limits:
 // limits only apply to call() API
@@ -33,6 +65,15 @@ mod evm_api;
 pub const GAS_LIMIT:     u64 = 100_000_000;
 pub const STORAGE_LIMIT: u32 =   1_000_000;
 
+/// Maximum number of calls of a single `evm_callBatch` request.
+pub const MAX_BATCH_CALLS: usize = 100;
+
+/// Maximum number of storage slots returned by a single `evm_storageRange` call.
+pub const MAX_STORAGE_RANGE_LIMIT: u32 = 1_000;
+
+/// Default margin added to exact estimations for the recommended limits, in percent.
+pub const ESTIMATE_MARGIN_PERCENT: u32 = 10;
+
 fn internal_err<T: ToString>(message: T) -> Error {
 	Error {
 		code: ErrorCode::InternalError,
@@ -41,6 +82,52 @@ fn internal_err<T: ToString>(message: T) -> Error {
 	}
 }
 
+/// Whether the runtime at `at` implements version 2 of `EVMRuntimeRPCApi`,
+/// with access lists and the methods added after version 1.
+fn has_api_v2<B, C, Balance>(client: &C, at: &BlockId<B>) -> Result<bool>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B>,
+	C::Api: EVMRuntimeRPCApi<B, Balance>,
+	Balance: Codec + MaybeDisplay + MaybeFromStr,
+{
+	client
+		.runtime_api()
+		.has_api_with::<dyn EVMRuntimeRPCApi<B, Balance>, _>(at, |version| version >= 2)
+		.map_err(|err| internal_err(format!("runtime error: {:?}", err)))
+}
+
+/// Fail unless the runtime at `at` implements version 2 of `EVMRuntimeRPCApi`.
+fn ensure_api_v2<B, C, Balance>(client: &C, at: &BlockId<B>) -> Result<()>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B>,
+	C::Api: EVMRuntimeRPCApi<B, Balance>,
+	Balance: Codec + MaybeDisplay + MaybeFromStr,
+{
+	if has_api_v2::<B, C, Balance>(client, at)? {
+		Ok(())
+	} else {
+		Err(Error {
+			code: ErrorCode::MethodNotFound,
+			message: "not supported by the runtime of this block".into(),
+			data: None,
+		})
+	}
+}
+
+/// Fail if an access list is given to a runtime without access lists.
+fn ensure_no_access_list(access_list: &Option<Vec<AccessListItem>>) -> Result<()> {
+	match access_list {
+		Some(access_list) if !access_list.is_empty() => Err(Error {
+			code: ErrorCode::InvalidParams,
+			message: "access lists are not supported by the runtime of this block".into(),
+			data: None,
+		}),
+		_ => Ok(()),
+	}
+}
+
 #[allow(dead_code)]
 fn error_on_execution_failure(reason: &ExitReason, data: &[u8]) -> Result<()> {
 	match reason {
@@ -74,6 +161,20 @@ fn error_on_execution_failure(reason: &ExitReason, data: &[u8]) -> Result<()> {
 	}
 }
 
+/// Smallest value in `lower..=upper` the execution succeeds with, given it
+/// succeeds with `upper` and with any value above the smallest one.
+fn binary_search(mut lower: u64, mut upper: u64, mut succeeds: impl FnMut(u64) -> bool) -> u64 {
+	while lower < upper {
+		let mid = lower + (upper - lower) / 2;
+		if succeeds(mid) {
+			upper = mid;
+		} else {
+			lower = mid + 1;
+		}
+	}
+	upper
+}
+
 fn decode_revert_message(data: &[u8]) -> Option<String> {
 	// A minimum size of error function selector (4) + offset (32) + string length
 	// (32) should contain a utf-8 encoded revert reason.
@@ -94,6 +195,7 @@ fn decode_revert_message(data: &[u8]) -> Option<String> {
 
 pub struct EVMApi<B, C, Balance> {
 	client: Arc<C>,
+	metrics: Option<EvmMetrics>,
 	_marker: PhantomData<(B, Balance)>,
 }
 
@@ -101,16 +203,29 @@ impl<B, C, Balance> EVMApi<B, C, Balance> {
 	pub fn new(client: Arc<C>) -> Self {
 		Self {
 			client,
+			metrics: None,
 			_marker: Default::default(),
 		}
 	}
+
+	/// Record the latency of `evm_call` and the iterations of estimations.
+	pub fn with_metrics(mut self, metrics: Option<EvmMetrics>) -> Self {
+		self.metrics = metrics;
+		self
+	}
+
+	fn observe_estimate_iterations(&self, iterations: u32) {
+		if let Some(metrics) = &self.metrics {
+			metrics.estimate_iterations.observe(iterations as f64);
+		}
+	}
 }
 
 fn to_u128(val: NumberOrHex) -> std::result::Result<u128, ()> {
 	val.into_u256().try_into().map_err(|_| ())
 }
 
-impl<B, C, Balance> EVMApiT<B> for EVMApi<B, C, Balance>
+impl<B, C, Balance> EVMApi<B, C, Balance>
 where
 	B: BlockT,
 	C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + 'static,
@@ -118,12 +233,61 @@ where
 	C::Api: TransactionPaymentApi<B, Balance>,
 	Balance: Codec + MaybeDisplay + MaybeFromStr + Default + Send + Sync + 'static + TryFrom<u128> + Into<U256>,
 {
-	fn call(&self, request: CallRequest, at: Option<B>) -> Result<Bytes> {
-		let hash = match at {
-			Some(hash) => hash.hash(),
-			None => self.client.info().best_hash,
+	/// `EVMRuntimeRPCApi::call` at the given block, without the access list
+	/// on runtimes before version 2.
+	#[allow(clippy::too_many_arguments, deprecated)]
+	fn runtime_call(
+		&self,
+		at: &BlockId<B>,
+		from: H160,
+		to: H160,
+		data: Vec<u8>,
+		value: Balance,
+		gas_limit: u64,
+		storage_limit: u32,
+		access_list: Option<Vec<AccessListItem>>,
+		estimate: bool,
+	) -> Result<CallInfo> {
+		let api = self.client.runtime_api();
+		let info = if has_api_v2::<B, C, Balance>(&*self.client, at)? {
+			api.call(at, from, to, data, value, gas_limit, storage_limit, access_list, estimate)
+		} else {
+			ensure_no_access_list(&access_list)?;
+			api.call_before_version_2(at, from, to, data, value, gas_limit, storage_limit, estimate)
 		};
 
+		info.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
+			.map_err(|err| internal_err(format!("execution fatal: {:?}", err)))
+	}
+
+	/// `EVMRuntimeRPCApi::create` at the given block, without the access list
+	/// on runtimes before version 2.
+	#[allow(clippy::too_many_arguments, deprecated)]
+	fn runtime_create(
+		&self,
+		at: &BlockId<B>,
+		from: H160,
+		data: Vec<u8>,
+		value: Balance,
+		gas_limit: u64,
+		storage_limit: u32,
+		access_list: Option<Vec<AccessListItem>>,
+		estimate: bool,
+	) -> Result<CreateInfo> {
+		let api = self.client.runtime_api();
+		let info = if has_api_v2::<B, C, Balance>(&*self.client, at)? {
+			api.create(at, from, data, value, gas_limit, storage_limit, access_list, estimate)
+		} else {
+			ensure_no_access_list(&access_list)?;
+			api.create_before_version_2(at, from, data, value, gas_limit, storage_limit, estimate)
+		};
+
+		info.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
+			.map_err(|err| internal_err(format!("execution fatal: {:?}", err)))
+	}
+
+	/// Call contract at the given block hash, returning the output data.
+	pub(crate) fn call_at(&self, request: CallRequest, hash: B::Hash) -> Result<Bytes> {
 		let CallRequest {
 			from,
 			to,
@@ -131,14 +295,13 @@ where
 			storage_limit,
 			value,
 			data,
+			access_list,
 		} = request;
 
 		let gas_limit = gas_limit.unwrap_or(GAS_LIMIT).min(GAS_LIMIT);
 		let storage_limit = storage_limit.unwrap_or(STORAGE_LIMIT).min(STORAGE_LIMIT);
 		let data = data.map(|d| d.0).unwrap_or_default();
 
-		let api = self.client.runtime_api();
-
 		let balance_value = if let Some(value) = value {
 			to_u128(value).and_then(|v| TryInto::<Balance>::try_into(v).map_err(|_| ()))
 		} else {
@@ -153,37 +316,33 @@ where
 
 		match to {
 			Some(to) => {
-				let info = api
-					.call(
-						&BlockId::Hash(hash),
-						from.unwrap_or_default(),
-						to,
-						data,
-						balance_value,
-						gas_limit,
-						storage_limit,
-						false,
-					)
-					.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
-					.map_err(|err| internal_err(format!("execution fatal: {:?}", err)))?;
+				let info = self.runtime_call(
+					&BlockId::Hash(hash),
+					from.unwrap_or_default(),
+					to,
+					data,
+					balance_value,
+					gas_limit,
+					storage_limit,
+					access_list,
+					false,
+				)?;
 
 				error_on_execution_failure(&info.exit_reason, &info.output)?;
 
 				Ok(Bytes(info.output))
 			}
 			None => {
-				let info = api
-					.create(
-						&BlockId::Hash(hash),
-						from.unwrap_or_default(),
-						data,
-						balance_value,
-						gas_limit,
-						storage_limit,
-						false,
-					)
-					.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
-					.map_err(|err| internal_err(format!("execution fatal: {:?}", err)))?;
+				let info = self.runtime_create(
+					&BlockId::Hash(hash),
+					from.unwrap_or_default(),
+					data,
+					balance_value,
+					gas_limit,
+					storage_limit,
+					access_list,
+					false,
+				)?;
 
 				error_on_execution_failure(&info.exit_reason, &info.output)?;
 
@@ -192,13 +351,23 @@ where
 		}
 	}
 
-	fn estimate_gas(&self, request: CallRequest, at: Option<B>) -> Result<U256> {
-		let hash = match at {
-			Some(hash) => hash.hash(),
-			None => self.client.info().best_hash,
+	/// Call contracts at the given block hash, each against its own state overrides.
+	pub(crate) fn call_batch_at(&self, requests: Vec<BatchCallRequest>, hash: B::Hash) -> Result<Vec<BatchCallResponse>> {
+		let invalid_value = |value| Error {
+			code: ErrorCode::InvalidParams,
+			message: format!("Invalid parameter value: {:?}", value),
+			data: None,
 		};
+		if requests.len() > MAX_BATCH_CALLS {
+			return Err(Error {
+				code: ErrorCode::InvalidParams,
+				message: format!("Too many calls in batch, the maximum is {}", MAX_BATCH_CALLS),
+				data: None,
+			});
+		}
 
-		let calculate_gas_used = |request| {
+		let mut calls = Vec::with_capacity(requests.len());
+		for BatchCallRequest { call, state_overrides } in requests {
 			let CallRequest {
 				from,
 				to,
@@ -206,130 +375,395 @@ where
 				storage_limit,
 				value,
 				data,
-			} = request;
+				access_list,
+			} = call;
 
-			let gas_limit = gas_limit.unwrap_or(GAS_LIMIT).min(GAS_LIMIT);
-			let storage_limit = storage_limit.unwrap_or(STORAGE_LIMIT).min(STORAGE_LIMIT);
-			let data = data.map(|d| d.0).unwrap_or_default();
-
-			let balance_value = if let Some(value) = value {
-				to_u128(value).and_then(|v| TryInto::<Balance>::try_into(v).map_err(|_| ()))
-			} else {
-				Ok(Default::default())
-			};
-
-			let balance_value = balance_value.map_err(|_| Error {
+			let to = to.ok_or_else(|| Error {
 				code: ErrorCode::InvalidParams,
-				message: format!("Invalid parameter value: {:?}", value),
+				message: "Batch calls require a `to` address".into(),
 				data: None,
 			})?;
 
-			let used_gas = match to {
-				Some(to) => {
-					let info = self
-						.client
-						.runtime_api()
-						.call(
-							&BlockId::Hash(hash),
-							from.unwrap_or_default(),
-							to,
-							data,
-							balance_value,
-							gas_limit,
-							storage_limit,
-							true,
-						)
-						.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
-						.map_err(|err| internal_err(format!("execution fatal: {:?}", err)))?;
-
-					error_on_execution_failure(&info.exit_reason, &info.output)?;
-
-					info.used_gas
-				}
-				None => {
-					let info = self
-						.client
-						.runtime_api()
-						.create(
-							&BlockId::Hash(hash),
-							from.unwrap_or_default(),
-							data,
-							balance_value,
-							gas_limit,
-							storage_limit,
-							true,
-						)
-						.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
-						.map_err(|err| internal_err(format!("execution fatal: {:?}", err)))?;
-
-					error_on_execution_failure(&info.exit_reason, &[])?;
-
-					info.used_gas
-				}
+			let value = match value {
+				Some(value) => to_u128(value)
+					.and_then(|v| TryInto::<Balance>::try_into(v).map_err(|_| ()))
+					.map_err(|_| invalid_value(value))?,
+				None => Default::default(),
 			};
 
-			Ok(used_gas)
+			let mut overrides = Vec::new();
+			for (address, account_override) in state_overrides.unwrap_or_default() {
+				let balance = match account_override.balance {
+					Some(balance) => Some(to_u128(balance).map_err(|_| invalid_value(balance))?),
+					None => None,
+				};
+
+				overrides.push(StateOverride {
+					address,
+					balance,
+					nonce: account_override
+						.nonce
+						.map(|nonce| nonce.min(U256::from(u64::max_value())).as_u64()),
+					code: account_override.code.map(|code| code.0),
+					state: account_override.state.map(|state| state.into_iter().collect()),
+					state_diff: account_override.state_diff.unwrap_or_default().into_iter().collect(),
+				});
+			}
+
+			calls.push(BatchCall {
+				from: from.unwrap_or_default(),
+				to,
+				data: data.map(|d| d.0).unwrap_or_default(),
+				value,
+				gas_limit: gas_limit.unwrap_or(GAS_LIMIT).min(GAS_LIMIT),
+				storage_limit: storage_limit.unwrap_or(STORAGE_LIMIT).min(STORAGE_LIMIT),
+				access_list: access_list.unwrap_or_default(),
+				overrides,
+			});
+		}
+
+		ensure_api_v2::<B, C, Balance>(&*self.client, &BlockId::Hash(hash))?;
+		let results = self
+			.client
+			.runtime_api()
+			.call_batch(&BlockId::Hash(hash), calls)
+			.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?;
+
+		Ok(results
+			.into_iter()
+			.map(|result| match result {
+				Ok(info) => BatchCallResponse {
+					error: error_on_execution_failure(&info.exit_reason, &info.output)
+						.err()
+						.map(|err| err.message),
+					output: Bytes(info.output),
+					used_gas: info.used_gas,
+					used_storage: info.used_storage,
+				},
+				Err(err) => BatchCallResponse {
+					error: Some(format!("execution fatal: {:?}", err)),
+					..Default::default()
+				},
+			})
+			.collect())
+	}
+
+	/// Generate the access list of given call at the given block hash.
+	pub(crate) fn create_access_list_at(&self, request: CallRequest, hash: B::Hash) -> Result<AccessListResponse> {
+		let CallRequest {
+			from,
+			to,
+			gas_limit,
+			storage_limit,
+			value,
+			data,
+			..
+		} = request;
+
+		let balance_value = match value {
+			Some(value) => to_u128(value)
+				.and_then(|v| TryInto::<Balance>::try_into(v).map_err(|_| ()))
+				.map_err(|_| Error {
+					code: ErrorCode::InvalidParams,
+					message: format!("Invalid parameter value: {:?}", value),
+					data: None,
+				})?,
+			None => Default::default(),
 		};
 
-		if cfg!(feature = "rpc_binary_search_estimate") {
-			let mut lower = U256::from(21_000);
-			// get a good upper limit, but below U64::max to operation overflow
-			let mut upper = U256::from(GAS_LIMIT);
-			let mut mid = upper;
-			let mut best = mid;
-			let mut old_best: U256;
+		ensure_api_v2::<B, C, Balance>(&*self.client, &BlockId::Hash(hash))?;
+		let info = self
+			.client
+			.runtime_api()
+			.create_access_list(
+				&BlockId::Hash(hash),
+				from.unwrap_or_default(),
+				to,
+				data.map(|d| d.0).unwrap_or_default(),
+				balance_value,
+				gas_limit.unwrap_or(GAS_LIMIT).min(GAS_LIMIT),
+				storage_limit.unwrap_or(STORAGE_LIMIT).min(STORAGE_LIMIT),
+			)
+			.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
+			.map_err(|err| internal_err(format!("execution fatal: {:?}", err)))?;
 
-			// if the gas estimation depends on the gas limit, then we want to binary
-			// search until the change is under some threshold. but if not dependent,
-			// we want to stop immediately.
-			let mut change_pct = U256::from(100);
-			let threshold_pct = U256::from(10);
+		Ok(AccessListResponse {
+			error: error_on_execution_failure(&info.exit_reason, &info.output)
+				.err()
+				.map(|err| err.message),
+			access_list: info.access_list,
+			gas_used: info.used_gas,
+		})
+	}
 
-			// invariant: lower <= mid <= upper
-			while change_pct > threshold_pct {
-				let mut test_request = request.clone();
-				test_request.gas_limit = Some(mid.as_u64());
-				match calculate_gas_used(test_request) {
-					// if Ok -- try to reduce the gas used
-					Ok(used_gas) => {
-						old_best = best;
-						best = used_gas;
-						change_pct = (U256::from(100) * (old_best - best))
-							.checked_div(old_best)
-							.unwrap_or_default();
-						upper = mid;
-						mid = (lower + upper + 1) / 2;
-					}
+	/// Gas and storage used by the execution of given contract at the given
+	/// block hash, failing unless it succeeds.
+	fn used_resources_at(&self, request: CallRequest, hash: B::Hash) -> Result<(U256, i32)> {
+		let CallRequest {
+			from,
+			to,
+			gas_limit,
+			storage_limit,
+			value,
+			data,
+			access_list,
+		} = request;
 
-					// if Err -- we need more gas
-					Err(_) => {
-						lower = mid;
-						mid = (lower + upper + 1) / 2;
+		let gas_limit = gas_limit.unwrap_or(GAS_LIMIT).min(GAS_LIMIT);
+		let storage_limit = storage_limit.unwrap_or(STORAGE_LIMIT).min(STORAGE_LIMIT);
+		let data = data.map(|d| d.0).unwrap_or_default();
 
-						// exit the loop
-						if mid == lower {
-							break;
-						}
+		let balance_value = if let Some(value) = value {
+			to_u128(value).and_then(|v| TryInto::<Balance>::try_into(v).map_err(|_| ()))
+		} else {
+			Ok(Default::default())
+		};
+
+		let balance_value = balance_value.map_err(|_| Error {
+			code: ErrorCode::InvalidParams,
+			message: format!("Invalid parameter value: {:?}", value),
+			data: None,
+		})?;
+
+		match to {
+			Some(to) => {
+				let info = self.runtime_call(
+					&BlockId::Hash(hash),
+					from.unwrap_or_default(),
+					to,
+					data,
+					balance_value,
+					gas_limit,
+					storage_limit,
+					access_list,
+					true,
+				)?;
+
+				error_on_execution_failure(&info.exit_reason, &info.output)?;
+
+				Ok((info.used_gas, info.used_storage))
+			}
+			None => {
+				let info = self.runtime_create(
+					&BlockId::Hash(hash),
+					from.unwrap_or_default(),
+					data,
+					balance_value,
+					gas_limit,
+					storage_limit,
+					access_list,
+					true,
+				)?;
+
+				error_on_execution_failure(&info.exit_reason, &[])?;
+
+				Ok((info.used_gas, info.used_storage))
+			}
+		}
+	}
+
+	/// Binary search the minimal gas limit, then the minimal storage limit,
+	/// the execution of given contract succeeds with at the given block hash.
+	fn exact_limits_at(&self, request: CallRequest, hash: B::Hash, options: EstimateOptions) -> Result<ExactLimits> {
+		let gas_limit = request.gas_limit.unwrap_or(GAS_LIMIT).min(GAS_LIMIT);
+		let storage_limit = request.storage_limit.unwrap_or(STORAGE_LIMIT).min(STORAGE_LIMIT);
+
+		// fails with the execution error if even the upper limits are not enough
+		let (used_gas, used_storage) = self.used_resources_at(request.clone(), hash)?;
+		let mut iterations = 1;
+
+		// the gas limit can't be below the gas used, but can be above it when
+		// the contract checks the gas left
+		let gas_limit = binary_search(used_gas.min(U256::from(gas_limit)).as_u64(), gas_limit, |mid| {
+			iterations += 1;
+			let mut test_request = request.clone();
+			test_request.gas_limit = Some(mid);
+			test_request.storage_limit = Some(storage_limit);
+			self.used_resources_at(test_request, hash).is_ok()
+		});
+
+		let storage_limit = binary_search(used_storage.max(0) as u64, u64::from(storage_limit), |mid| {
+			iterations += 1;
+			let mut test_request = request.clone();
+			test_request.gas_limit = Some(gas_limit);
+			test_request.storage_limit = Some(mid as u32);
+			self.used_resources_at(test_request, hash).is_ok()
+		}) as u32;
+		self.observe_estimate_iterations(iterations);
+
+		log::debug!(
+			target: "evm",
+			"exact estimate, gas_limit: {:?}, storage_limit: {:?}",
+			gas_limit, storage_limit,
+		);
+
+		let margin = u64::from(options.margin_percent.unwrap_or(ESTIMATE_MARGIN_PERCENT));
+		let with_margin = |limit: u64| limit.saturating_add(limit.saturating_mul(margin) / 100);
+
+		Ok(ExactLimits {
+			gas_limit,
+			storage_limit,
+			recommended_gas_limit: with_margin(gas_limit).min(GAS_LIMIT),
+			recommended_storage_limit: with_margin(u64::from(storage_limit)).min(u64::from(STORAGE_LIMIT)) as u32,
+		})
+	}
+
+	/// Estimate gas needed for execution of given contract at the given block hash.
+	pub(crate) fn estimate_gas_at(&self, request: CallRequest, hash: B::Hash, options: EstimateOptions) -> Result<U256> {
+		if options.exact {
+			return self
+				.exact_limits_at(request, hash, options)
+				.map(|limits| U256::from(limits.recommended_gas_limit));
+		}
+
+		let calculate_gas_used = |request| self.used_resources_at(request, hash).map(|(used_gas, _)| used_gas);
+
+		let mut lower = U256::from(21_000);
+		// get a good upper limit, but below U64::max to operation overflow
+		let mut upper = U256::from(GAS_LIMIT);
+		let mut mid = upper;
+		let mut best = mid;
+		let mut old_best: U256;
+
+		// if the gas estimation depends on the gas limit, then we want to binary
+		// search until the change is under some threshold. but if not dependent,
+		// we want to stop immediately.
+		let mut change_pct = U256::from(100);
+		let threshold_pct = U256::from(10);
+		let mut iterations = 0;
+
+		// invariant: lower <= mid <= upper
+		while change_pct > threshold_pct {
+			iterations += 1;
+			let mut test_request = request.clone();
+			test_request.gas_limit = Some(mid.as_u64());
+			match calculate_gas_used(test_request) {
+				// if Ok -- try to reduce the gas used
+				Ok(used_gas) => {
+					old_best = best;
+					best = used_gas;
+					change_pct = (U256::from(100) * (old_best - best))
+						.checked_div(old_best)
+						.unwrap_or_default();
+					upper = mid;
+					mid = (lower + upper + 1) / 2;
+				}
+
+				// if Err -- we need more gas
+				Err(_) => {
+					lower = mid;
+					mid = (lower + upper + 1) / 2;
+
+					// exit the loop
+					if mid == lower {
+						break;
 					}
 				}
 			}
-			Ok(best)
-		} else {
-			calculate_gas_used(request)
 		}
+		self.observe_estimate_iterations(iterations);
+		Ok(best)
 	}
+}
 
+impl<B, C, Balance> EVMApiT<B> for EVMApi<B, C, Balance>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + 'static,
+	C::Api: EVMRuntimeRPCApi<B, Balance>,
+	C::Api: TransactionPaymentApi<B, Balance>,
+	Balance: Codec + MaybeDisplay + MaybeFromStr + Default + Send + Sync + 'static + TryFrom<u128> + Into<U256>,
+{
+	fn call(&self, request: CallRequest, at: Option<B>) -> Result<Bytes> {
+		let _timer = self.metrics.as_ref().map(|metrics| metrics.call_duration.start_timer());
+		let hash = match at {
+			Some(hash) => hash.hash(),
+			None => self.client.info().best_hash,
+		};
+
+		self.call_at(request, hash)
+	}
+
+	fn estimate_gas(&self, request: CallRequest, at: Option<B>, options: Option<EstimateOptions>) -> Result<U256> {
+		let hash = match at {
+			Some(hash) => hash.hash(),
+			None => self.client.info().best_hash,
+		};
+
+		self.estimate_gas_at(request, hash, options.unwrap_or_default())
+	}
+
+	fn call_batch(&self, requests: Vec<BatchCallRequest>, at: Option<B>) -> Result<Vec<BatchCallResponse>> {
+		let hash = match at {
+			Some(hash) => hash.hash(),
+			None => self.client.info().best_hash,
+		};
+
+		self.call_batch_at(requests, hash)
+	}
+
+	fn create_access_list(&self, request: CallRequest, at: Option<B>) -> Result<AccessListResponse> {
+		let hash = match at {
+			Some(hash) => hash.hash(),
+			None => self.client.info().best_hash,
+		};
+
+		self.create_access_list_at(request, hash)
+	}
+
+	fn storage_range(
+		&self,
+		address: H160,
+		start: Option<H256>,
+		limit: Option<u32>,
+		at: Option<B>,
+	) -> Result<StorageRange> {
+		let hash = match at {
+			Some(hash) => hash.hash(),
+			None => self.client.info().best_hash,
+		};
+
+		if limit == Some(0) {
+			return Err(Error {
+				code: ErrorCode::InvalidParams,
+				message: "Invalid parameter limit, it must be at least 1".into(),
+				data: None,
+			});
+		}
+		let limit = limit.unwrap_or(MAX_STORAGE_RANGE_LIMIT).min(MAX_STORAGE_RANGE_LIMIT);
+
+		ensure_api_v2::<B, C, Balance>(&*self.client, &BlockId::Hash(hash))?;
+		self.client
+			.runtime_api()
+			.storage_range(&BlockId::Hash(hash), address, start, limit)
+			.map_err(|err| internal_err(format!("runtime error: {:?}", err)))
+	}
+
+	fn source_info(&self, address: H160, at: Option<B>) -> Result<Option<SourceInfo>> {
+		let hash = match at {
+			Some(hash) => hash.hash(),
+			None => self.client.info().best_hash,
+		};
+
+		ensure_api_v2::<B, C, Balance>(&*self.client, &BlockId::Hash(hash))?;
+		self.client
+			.runtime_api()
+			.source_info(&BlockId::Hash(hash), address)
+			.map_err(|err| internal_err(format!("runtime error: {:?}", err)))
+	}
 
 	fn estimate_resources(
 		&self,
 		from: H160,
 		unsigned_extrinsic: Bytes,
 		at: Option<B>,
+		options: Option<EstimateOptions>,
 	) -> Result<EstimateResourcesResponse> {
 		let hash = match at {
 			Some(hash) => hash.hash(),
 			None => self.client.info().best_hash,
 		};
+		let options = options.unwrap_or_default();
 		let request = self
 			.client
 			.runtime_api()
@@ -344,83 +778,21 @@ where
 			storage_limit: request.storage_limit,
 			value: request.value.map(|v| NumberOrHex::Hex(U256::from(v))),
 			data: request.data.map(Bytes),
+			access_list: None,
 		};
 
-		let calculate_gas_used = |request| -> Result<(U256, i32)> {
-
-			let CallRequest {
-				from,
-				to,
-				gas_limit,
-				storage_limit,
-				value,
-				data,
-			} = request;
-
-			let gas_limit = gas_limit.unwrap_or(GAS_LIMIT).min(GAS_LIMIT);
-			let storage_limit = storage_limit.unwrap_or(STORAGE_LIMIT).min(STORAGE_LIMIT);
-			let data = data.map(|d| d.0).unwrap_or_default();
+		let calculate_gas_used = |request| self.used_resources_at(request, hash);
 
-			let balance_value = if let Some(value) = value {
-				to_u128(value).and_then(|v| TryInto::<Balance>::try_into(v).map_err(|_| ()))
-			} else {
-				Ok(Default::default())
-			};
+		let (best, storage, limits) = if options.exact {
+			let limits = self.exact_limits_at(request.clone(), hash, options)?;
 
-			let balance_value = balance_value.map_err(|_| Error {
-				code: ErrorCode::InvalidParams,
-				message: format!("Invalid parameter value: {:?}", value),
-				data: None,
-			})?;
+			let mut test_request = request;
+			test_request.gas_limit = Some(limits.gas_limit);
+			test_request.storage_limit = Some(limits.storage_limit);
+			let (used_gas, used_storage) = calculate_gas_used(test_request)?;
 
-			let (used_gas, used_storage) = match to {
-				Some(to) => {
-					let info = self
-						.client
-						.runtime_api()
-						.call(
-							&BlockId::Hash(hash),
-							from.unwrap_or_default(),
-							to,
-							data,
-							balance_value,
-							gas_limit,
-							storage_limit,
-							true,
-						)
-						.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
-						.map_err(|err| internal_err(format!("execution fatal: {:?}", err)))?;
-
-					error_on_execution_failure(&info.exit_reason, &info.output)?;
-
-					(info.used_gas, info.used_storage)
-				}
-				None => {
-					let info = self
-						.client
-						.runtime_api()
-						.create(
-							&BlockId::Hash(hash),
-							from.unwrap_or_default(),
-							data,
-							balance_value,
-							gas_limit,
-							storage_limit,
-							true,
-						)
-						.map_err(|err| internal_err(format!("runtime error: {:?}", err)))?
-						.map_err(|err| internal_err(format!("execution fatal: {:?}", err)))?;
-
-					error_on_execution_failure(&info.exit_reason, &[])?;
-
-					(info.used_gas, info.used_storage)
-				}
-			};
-
-			Ok((used_gas, used_storage))
-		};
-
-		if cfg!(feature = "rpc_binary_search_estimate") {
+			(used_gas, used_storage, Some(limits))
+		} else {
 			let mut lower = U256::from(21_000);
 			// get a good upper limit, but below U64::max to operation overflow
 			let mut upper = U256::from(GAS_LIMIT);
@@ -434,9 +806,11 @@ where
 			// we want to stop immediately.
 			let mut change_pct = U256::from(100);
 			let threshold_pct = U256::from(10);
+			let mut iterations = 0;
 
 			// invariant: lower <= mid <= upper
 			while change_pct > threshold_pct {
+				iterations += 1;
 				let mut test_request = request.clone();
 				test_request.gas_limit = Some(mid.as_u64());
 				match calculate_gas_used(test_request) {
@@ -479,68 +853,55 @@ where
 					}
 				}
 			}
+			self.observe_estimate_iterations(iterations);
 
-			let uxt: <B as BlockT>::Extrinsic =
-				Decode::decode(&mut &*unsigned_extrinsic)
-				.map_err(|e| Error {
-					code: ErrorCode::InternalError,
-					message: "Unable to dry run extrinsic.".into(),
-					data: Some(format!("{:?}", e).into()),
-				})?;
-
-			let fee = self
-				.client
-				.runtime_api()
-				.query_fee_details(&BlockId::Hash(hash), uxt, unsigned_extrinsic.len() as u32)
-				.map_err(|e| Error {
-					code: ErrorCode::InternalError,
-					message: "Unable to query fee details.".into(),
-					data: Some(format!("{:?}", e).into()),
-				})?;
-
-			let adjusted_weight_fee = fee
-				.inclusion_fee
-				.map_or_else(Default::default, |inclusion| inclusion.adjusted_weight_fee);
-
-			Ok(EstimateResourcesResponse {
-				gas: best,
-				storage,
-				weight_fee: adjusted_weight_fee.into(),
-			})
-		} else {
-			let (used_gas, used_storage) = calculate_gas_used(request)?;
-
-			let uxt: <B as BlockT>::Extrinsic =
-				Decode::decode(&mut &*unsigned_extrinsic)
-				.map_err(|e| Error {
-					code: ErrorCode::InternalError,
-					message: "Unable to dry run extrinsic.".into(),
-					data: Some(format!("{:?}", e).into()),
-				})?;
-
-			let fee = self
-				.client
-				.runtime_api()
-				.query_fee_details(&BlockId::Hash(hash), uxt, unsigned_extrinsic.len() as u32)
-				.map_err(|e| Error {
-					code: ErrorCode::InternalError,
-					message: "Unable to query fee details.".into(),
-					data: Some(format!("{:?}", e).into()),
-				})?;
-
-			let adjusted_weight_fee = fee
-				.inclusion_fee
-				.map_or_else(Default::default, |inclusion| inclusion.adjusted_weight_fee);
-
-			Ok(EstimateResourcesResponse {
-				gas: used_gas,
-				storage: used_storage,
-				weight_fee: adjusted_weight_fee.into(),
-			})
-		}
-	}
+			(best, storage, None)
+		};
+
+		let uxt: <B as BlockT>::Extrinsic =
+			Decode::decode(&mut &*unsigned_extrinsic)
+			.map_err(|e| Error {
+				code: ErrorCode::InternalError,
+				message: "Unable to dry run extrinsic.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})?;
+
+		let fee = self
+			.client
+			.runtime_api()
+			.query_fee_details(&BlockId::Hash(hash), uxt, unsigned_extrinsic.len() as u32)
+			.map_err(|e| Error {
+				code: ErrorCode::InternalError,
+				message: "Unable to query fee details.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})?;
 
+		let adjusted_weight_fee = fee
+			.inclusion_fee
+			.map_or_else(Default::default, |inclusion| inclusion.adjusted_weight_fee);
 
+		Ok(EstimateResourcesResponse {
+			gas: best,
+			storage,
+			weight_fee: adjusted_weight_fee.into(),
+			limits,
+		})
+	}
+}
+
+#[test]
+fn binary_search_should_work() {
+	assert_eq!(binary_search(0, 100, |mid| mid >= 42), 42);
+	assert_eq!(binary_search(0, 100, |_| true), 0);
+	assert_eq!(binary_search(0, 100, |mid| mid >= 100), 100);
+	assert_eq!(binary_search(7, 7, |_| true), 7);
+
+	let mut runs = 0;
+	binary_search(21_000, GAS_LIMIT, |mid| {
+		runs += 1;
+		mid >= 53_211
+	});
+	assert!(runs <= 27);
 }
 
 #[test]
@@ -0,0 +1,305 @@
+//! EVM log indexing and filtering.
+
+use ethereum_types::{H160, H256, U256};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use sc_client_api::{backend::AuxStore, BlockBackend, BlockchainEvents};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{hashing::blake2_256, Bytes};
+use sp_runtime::{
+	codec::{Codec, Decode, Encode},
+	generic::BlockId,
+	traits::{Block as BlockT, MaybeDisplay, MaybeFromStr},
+};
+use std::{marker::PhantomData, sync::Arc};
+
+use module_evm::Log;
+
+use crate::{block_number::BlockNumber, receipt::ReceiptIndexer, EVMRuntimeRPCApi};
+
+/// Prefix of the aux storage key holding the indexed logs of a block.
+const LOGS_KEY_PREFIX: &[u8] = b"evm_logs:";
+
+/// A single value or a list of accepted values.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum VariadicValue<T> {
+	Single(T),
+	Multiple(Vec<T>),
+}
+
+impl<T: PartialEq> VariadicValue<T> {
+	pub fn contains(&self, value: &T) -> bool {
+		match self {
+			VariadicValue::Single(v) => v == value,
+			VariadicValue::Multiple(values) => values.contains(value),
+		}
+	}
+}
+
+/// Log filter, as accepted by `eth_getLogs` and `eth_newFilter`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Filter {
+	/// First block to scan, defaults to latest.
+	pub from_block: Option<BlockNumber>,
+	/// Last block to scan, defaults to latest.
+	pub to_block: Option<BlockNumber>,
+	/// Only scan the given block, excludes `from_block` and `to_block`.
+	pub block_hash: Option<H256>,
+	/// Emitting contract address(es).
+	pub address: Option<VariadicValue<H160>>,
+	/// Topics by position, `None` matches any topic.
+	pub topics: Option<Vec<Option<VariadicValue<H256>>>>,
+}
+
+impl Filter {
+	/// Whether the log matches the address and topics of the filter.
+	pub fn matches(&self, log: &Log) -> bool {
+		if let Some(address) = &self.address {
+			if !address.contains(&log.address) {
+				return false;
+			}
+		}
+
+		if let Some(topics) = &self.topics {
+			for (index, topic) in topics.iter().enumerate() {
+				if let Some(topic) = topic {
+					match log.topics.get(index) {
+						Some(value) if topic.contains(value) => {}
+						_ => return false,
+					}
+				}
+			}
+		}
+
+		true
+	}
+}
+
+/// Ethereum compatible log.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EthLog {
+	pub address: H160,
+	pub topics: Vec<H256>,
+	pub data: Bytes,
+	pub block_hash: H256,
+	pub block_number: U256,
+	/// Hash of the emitting extrinsic.
+	pub transaction_hash: H256,
+	/// Index of the emitting extrinsic in the block.
+	pub transaction_index: U256,
+	/// Index of the log in the block.
+	pub log_index: U256,
+	pub transaction_log_index: U256,
+	pub removed: bool,
+}
+
+/// Logs of a block, as stored in the index: extrinsic index, extrinsic hash and log.
+pub type BlockLogs = Vec<(u32, H256, Log)>;
+
+/// Indexes the EVM logs of blocks into the client aux storage.
+pub struct LogIndexer<B, C, Balance> {
+	client: Arc<C>,
+	_marker: PhantomData<(B, Balance)>,
+}
+
+impl<B, C, Balance> LogIndexer<B, C, Balance> {
+	pub fn new(client: Arc<C>) -> Self {
+		Self {
+			client,
+			_marker: Default::default(),
+		}
+	}
+}
+
+impl<B, C, Balance> LogIndexer<B, C, Balance>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + BlockBackend<B> + AuxStore,
+	C::Api: EVMRuntimeRPCApi<B, Balance>,
+	Balance: Codec + MaybeDisplay + MaybeFromStr,
+{
+	fn key(hash: &B::Hash) -> Vec<u8> {
+		let mut key = LOGS_KEY_PREFIX.to_vec();
+		key.extend(hash.encode());
+		key
+	}
+
+	/// Logs of the given block, indexing the block first if required.
+	pub fn block_logs(&self, hash: B::Hash) -> Result<BlockLogs, String> {
+		let key = Self::key(&hash);
+		if let Some(encoded) = self.client.get_aux(&key).map_err(|err| format!("{:?}", err))? {
+			return BlockLogs::decode(&mut &encoded[..]).map_err(|err| format!("{:?}", err));
+		}
+
+		let id = BlockId::Hash(hash);
+		let api = self.client.runtime_api();
+		// runtimes before version 2 of the api do not expose their logs
+		let logs = if api
+			.has_api_with::<dyn EVMRuntimeRPCApi<B, Balance>, _>(&id, |version| version >= 2)
+			.map_err(|err| format!("{:?}", err))?
+		{
+			api.block_logs(&id).map_err(|err| format!("{:?}", err))?
+		} else {
+			Vec::new()
+		};
+
+		let logs: BlockLogs = if logs.is_empty() {
+			Vec::new()
+		} else {
+			let extrinsics = self
+				.client
+				.block_body(&id)
+				.map_err(|err| format!("{:?}", err))?
+				.ok_or_else(|| format!("missing body of block {:?}", hash))?;
+
+			logs.into_iter()
+				.map(|(index, log)| {
+					let extrinsic_hash = extrinsics
+						.get(index as usize)
+						.map(|xt| H256::from(xt.using_encoded(blake2_256)))
+						.unwrap_or_default();
+					(index, extrinsic_hash, log)
+				})
+				.collect()
+		};
+
+		self.client
+			.insert_aux(&[(&key[..], &logs.encode()[..])], &[])
+			.map_err(|err| format!("{:?}", err))?;
+
+		Ok(logs)
+	}
+
+	/// Logs of the given block matching the filter.
+	pub fn filter_block(&self, hash: B::Hash, number: u64, filter: &Filter) -> Result<Vec<EthLog>, String> {
+		let block_hash = H256::from_slice(hash.as_ref());
+
+		let mut logs = Vec::new();
+		let mut previous = None;
+		let mut transaction_log_index = 0u64;
+		for (log_index, (index, extrinsic_hash, log)) in self.block_logs(hash)?.into_iter().enumerate() {
+			if previous == Some(index) {
+				transaction_log_index += 1;
+			} else {
+				previous = Some(index);
+				transaction_log_index = 0;
+			}
+
+			if !filter.matches(&log) {
+				continue;
+			}
+
+			logs.push(EthLog {
+				address: log.address,
+				topics: log.topics,
+				data: Bytes(log.data),
+				block_hash,
+				block_number: U256::from(number),
+				transaction_hash: extrinsic_hash,
+				transaction_index: U256::from(index),
+				log_index: U256::from(log_index),
+				transaction_log_index: U256::from(transaction_log_index),
+				removed: false,
+			});
+		}
+
+		Ok(logs)
+	}
+}
+
+/// Index the EVM logs and receipts of every imported block, so `eth_getLogs`
+/// does not have to execute runtime calls on demand and transactions can be
+/// looked up by hash.
+pub async fn log_indexer_task<B, C, Balance>(client: Arc<C>)
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + BlockBackend<B> + BlockchainEvents<B> + AuxStore,
+	C::Api: EVMRuntimeRPCApi<B, Balance>,
+	Balance: Codec + MaybeDisplay + MaybeFromStr,
+{
+	let indexer = LogIndexer::<B, C, Balance>::new(client.clone());
+	let receipts = ReceiptIndexer::<B, C, Balance>::new(client.clone());
+	let mut notifications = client.import_notification_stream();
+
+	// blocks imported without notification, before the node started or
+	// during the initial sync, are indexed from the last indexed block
+	let index_canonical_blocks = || {
+		if let Err(err) = receipts.index_canonical_blocks(client.info().best_number) {
+			log::warn!(target: "evm", "index receipts of canonical blocks failed: {}", err);
+		}
+	};
+	index_canonical_blocks();
+
+	while let Some(notification) = notifications.next().await {
+		if let Err(err) = indexer.block_logs(notification.hash) {
+			log::warn!(target: "evm", "index logs of block {:?} failed: {}", notification.hash, err);
+		}
+		if let Err(err) = receipts.block_receipts(notification.hash) {
+			log::warn!(target: "evm", "index receipts of block {:?} failed: {}", notification.hash, err);
+		}
+		index_canonical_blocks();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn log(address: H160, topics: Vec<H256>) -> Log {
+		Log {
+			address,
+			topics,
+			data: vec![],
+		}
+	}
+
+	#[test]
+	fn filter_deserialize_should_work() {
+		let filter: Filter = serde_json::from_str(
+			r#"{
+				"fromBlock": "0x1",
+				"address": "0x0000000000000000000000000000000000000001",
+				"topics": [null, ["0x0000000000000000000000000000000000000000000000000000000000000002"]]
+			}"#,
+		)
+		.unwrap();
+
+		assert_eq!(
+			filter,
+			Filter {
+				from_block: Some(BlockNumber::Num(1)),
+				to_block: None,
+				block_hash: None,
+				address: Some(VariadicValue::Single(H160::from_low_u64_be(1))),
+				topics: Some(vec![None, Some(VariadicValue::Multiple(vec![H256::from_low_u64_be(2)]))]),
+			}
+		);
+	}
+
+	#[test]
+	fn filter_matches_should_work() {
+		let address = H160::from_low_u64_be(1);
+		let topics = vec![H256::from_low_u64_be(1), H256::from_low_u64_be(2)];
+
+		assert!(Filter::default().matches(&log(address, topics.clone())));
+
+		let filter = Filter {
+			address: Some(VariadicValue::Multiple(vec![H160::from_low_u64_be(2), address])),
+			topics: Some(vec![None, Some(VariadicValue::Single(H256::from_low_u64_be(2)))]),
+			..Default::default()
+		};
+		assert!(filter.matches(&log(address, topics.clone())));
+		assert!(!filter.matches(&log(H160::from_low_u64_be(3), topics.clone())));
+		assert!(!filter.matches(&log(address, vec![H256::from_low_u64_be(2)])));
+
+		let filter = Filter {
+			topics: Some(vec![Some(VariadicValue::Single(H256::from_low_u64_be(2)))]),
+			..Default::default()
+		};
+		assert!(!filter.matches(&log(address, topics)));
+	}
+}
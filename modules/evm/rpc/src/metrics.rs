@@ -0,0 +1,285 @@
+//! Prometheus metrics of the EVM execution.
+
+use ethereum_types::H160;
+use futures::StreamExt;
+use prometheus_endpoint::{
+	exponential_buckets, register, Counter, CounterVec, GaugeVec, Histogram, HistogramOpts, Opts, PrometheusError,
+	Registry, U64,
+};
+use sc_client_api::{backend::AuxStore, BlockBackend, BlockchainEvents};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{
+	codec::Codec,
+	traits::{Block as BlockT, MaybeDisplay, MaybeFromStr},
+};
+use std::collections::{hash_map::Entry, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use module_evm::{ExitError, ExitReason, Receipt};
+
+use crate::{receipt::ReceiptIndexer, EVMRuntimeRPCApi};
+
+/// Number of contracts exported by `evm_top_contracts_gas_used`.
+pub const TOP_CONTRACTS: usize = 10;
+
+/// Number of recent blocks `evm_top_contracts_gas_used` covers.
+pub const GAS_USED_WINDOW: usize = 600;
+
+/// EVM metrics of the imported blocks and of the rpc handlers, registered in
+/// the Prometheus registry of the node.
+#[derive(Clone)]
+pub struct EvmMetrics {
+	block_gas_used: Histogram,
+	top_contracts_gas_used: GaugeVec<U64>,
+	executions: CounterVec<U64>,
+	storage_charged: Counter<U64>,
+	pub(crate) call_duration: Histogram,
+	pub(crate) estimate_iterations: Histogram,
+	/// Gas used by each contract in the recent blocks.
+	contracts_gas_used: Arc<Mutex<ContractsGasUsed>>,
+}
+
+/// Gas used by the contracts in the last `GAS_USED_WINDOW` blocks.
+#[derive(Default)]
+struct ContractsGasUsed {
+	/// Gas used by each contract in the window.
+	totals: HashMap<H160, u64>,
+	/// Gas used by the contracts in each block of the window, oldest first.
+	blocks: VecDeque<Vec<(H160, u64)>>,
+}
+
+impl ContractsGasUsed {
+	/// Add the gas used in a new block, the oldest block leaves the window.
+	fn push_block(&mut self, block: Vec<(H160, u64)>) {
+		if self.blocks.len() >= GAS_USED_WINDOW {
+			for (contract, gas_used) in self.blocks.pop_front().unwrap_or_default() {
+				if let Entry::Occupied(mut total) = self.totals.entry(contract) {
+					*total.get_mut() = total.get().saturating_sub(gas_used);
+					if *total.get() == 0 {
+						total.remove();
+					}
+				}
+			}
+		}
+
+		for (contract, gas_used) in &block {
+			let total = self.totals.entry(*contract).or_default();
+			*total = total.saturating_add(*gas_used);
+		}
+		self.blocks.push_back(block);
+	}
+
+	/// The `count` contracts using the most gas, most first.
+	fn top(&self, count: usize) -> Vec<(H160, u64)> {
+		let mut contracts: Vec<_> = self.totals.iter().map(|(contract, gas_used)| (*contract, *gas_used)).collect();
+		if contracts.len() > count {
+			contracts.select_nth_unstable_by(count, |a, b| b.1.cmp(&a.1));
+			contracts.truncate(count);
+		}
+		contracts.sort_by(|a, b| b.1.cmp(&a.1));
+		contracts
+	}
+}
+
+impl EvmMetrics {
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			block_gas_used: register(
+				Histogram::with_opts(
+					HistogramOpts::new("evm_block_gas_used", "EVM gas used per block")
+						.buckets(exponential_buckets(21_000.0, 4.0, 8)?),
+				)?,
+				registry,
+			)?,
+			top_contracts_gas_used: register(
+				GaugeVec::new(
+					Opts::new(
+						"evm_top_contracts_gas_used",
+						"EVM gas used in the recent blocks by the contracts using the most gas",
+					),
+					&["contract"],
+				)?,
+				registry,
+			)?,
+			executions: register(
+				CounterVec::new(
+					Opts::new("evm_executions_total", "EVM executions by exit reason"),
+					&["exit_reason"],
+				)?,
+				registry,
+			)?,
+			storage_charged: register(
+				Counter::new("evm_storage_charged_bytes_total", "Storage bytes charged to EVM executions")?,
+				registry,
+			)?,
+			call_duration: register(
+				Histogram::with_opts(HistogramOpts::new(
+					"evm_rpc_call_duration_seconds",
+					"Duration of the evm_call RPC",
+				))?,
+				registry,
+			)?,
+			estimate_iterations: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"evm_rpc_estimate_iterations",
+						"Executions of the gas and storage estimation RPCs",
+					)
+					.buckets(exponential_buckets(1.0, 2.0, 7)?),
+				)?,
+				registry,
+			)?,
+			contracts_gas_used: Default::default(),
+		})
+	}
+
+	/// Record the receipts of a new best block.
+	pub fn record_block<'a>(&self, receipts: impl IntoIterator<Item = &'a Receipt>) {
+		let mut block_gas_used = 0u64;
+		let mut block_contracts_gas_used = Vec::new();
+
+		for receipt in receipts {
+			block_gas_used = block_gas_used.saturating_add(receipt.used_gas);
+			self.executions
+				.with_label_values(&[&exit_reason_label(receipt.exit_reason.as_ref())])
+				.inc();
+			if receipt.used_storage > 0 {
+				self.storage_charged.inc_by(receipt.used_storage as u64);
+			}
+			if let Some(contract) = receipt.to.or(receipt.contract_address) {
+				block_contracts_gas_used.push((contract, receipt.used_gas));
+			}
+		}
+		self.block_gas_used.observe(block_gas_used as f64);
+
+		let mut contracts_gas_used = match self.contracts_gas_used.lock() {
+			Ok(contracts_gas_used) => contracts_gas_used,
+			Err(_) => return,
+		};
+		contracts_gas_used.push_block(block_contracts_gas_used);
+		self.top_contracts_gas_used.reset();
+		for (contract, gas_used) in contracts_gas_used.top(TOP_CONTRACTS) {
+			self.top_contracts_gas_used
+				.with_label_values(&[&format!("{:?}", contract)])
+				.set(gas_used);
+		}
+	}
+}
+
+/// Label of the exit reason of an execution, `None` if it succeeded. Errors
+/// are labelled by kind, without their messages.
+fn exit_reason_label(exit_reason: Option<&ExitReason>) -> String {
+	match exit_reason {
+		None | Some(ExitReason::Succeed(_)) => "succeed".into(),
+		Some(ExitReason::Revert(_)) => "revert".into(),
+		Some(ExitReason::Error(ExitError::Other(_))) => "error_other".into(),
+		Some(ExitReason::Error(err)) => format!("error_{:?}", err),
+		Some(ExitReason::Fatal(_)) => "fatal".into(),
+	}
+}
+
+/// Record the EVM metrics of every new best block.
+pub async fn metrics_task<B, C, Balance>(client: Arc<C>, metrics: EvmMetrics)
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + BlockBackend<B> + BlockchainEvents<B> + AuxStore,
+	C::Api: EVMRuntimeRPCApi<B, Balance>,
+	Balance: Codec + MaybeDisplay + MaybeFromStr,
+{
+	let receipts = ReceiptIndexer::<B, C, Balance>::new(client.clone());
+	let mut notifications = client.import_notification_stream();
+
+	while let Some(notification) = notifications.next().await {
+		// blocks of forks would be counted twice
+		if !notification.is_new_best {
+			continue;
+		}
+		match receipts.block_receipts(notification.hash) {
+			Ok(block_receipts) => metrics.record_block(block_receipts.iter().map(|(_, receipt)| receipt)),
+			Err(err) => log::warn!(target: "evm", "metrics of block {:?} failed: {}", notification.hash, err),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use module_evm::ExitRevert;
+
+	fn receipt(contract: u64, used_gas: u64, used_storage: i32, exit_reason: Option<ExitReason>) -> Receipt {
+		Receipt {
+			extrinsic_index: 0,
+			from: H160::zero(),
+			to: Some(H160::from_low_u64_be(contract)),
+			contract_address: None,
+			succeeded: exit_reason.is_none(),
+			exit_reason,
+			used_gas,
+			used_storage,
+			logs: vec![],
+			transaction_hash: None,
+		}
+	}
+
+	#[test]
+	fn exit_reason_label_should_work() {
+		assert_eq!(exit_reason_label(None), "succeed");
+		assert_eq!(exit_reason_label(Some(&ExitReason::Revert(ExitRevert::Reverted))), "revert");
+		assert_eq!(exit_reason_label(Some(&ExitReason::Error(ExitError::OutOfGas))), "error_OutOfGas");
+		assert_eq!(
+			exit_reason_label(Some(&ExitReason::Error(ExitError::Other("message".into())))),
+			"error_other"
+		);
+	}
+
+	#[test]
+	fn record_block_should_work() {
+		let metrics = EvmMetrics::register(&Registry::new()).unwrap();
+		let label = |contract: u64| format!("{:?}", H160::from_low_u64_be(contract));
+
+		metrics.record_block(&[
+			receipt(1, 100, 64, None),
+			receipt(2, 300, -32, Some(ExitReason::Revert(ExitRevert::Reverted))),
+			receipt(1, 50, 0, None),
+		]);
+		assert_eq!(metrics.block_gas_used.get_sample_count(), 1);
+		assert_eq!(metrics.block_gas_used.get_sample_sum(), 450.0);
+		assert_eq!(metrics.storage_charged.get(), 64);
+		assert_eq!(metrics.executions.with_label_values(&["succeed"]).get(), 2);
+		assert_eq!(metrics.executions.with_label_values(&["revert"]).get(), 1);
+		assert_eq!(metrics.top_contracts_gas_used.with_label_values(&[&label(1)]).get(), 150);
+		assert_eq!(metrics.top_contracts_gas_used.with_label_values(&[&label(2)]).get(), 300);
+
+		// only the top contracts are exported
+		let receipts: Vec<_> = (3..3 + TOP_CONTRACTS as u64)
+			.map(|contract| receipt(contract, 1_000, 0, None))
+			.collect();
+		metrics.record_block(&receipts);
+		assert_eq!(metrics.top_contracts_gas_used.with_label_values(&[&label(2)]).get(), 0);
+		assert_eq!(metrics.block_gas_used.get_sample_count(), 2);
+	}
+
+	#[test]
+	fn contracts_gas_used_should_cover_recent_blocks() {
+		let contract = |contract: u64| H160::from_low_u64_be(contract);
+		let mut contracts_gas_used = ContractsGasUsed::default();
+
+		contracts_gas_used.push_block(vec![(contract(1), 100), (contract(2), 300), (contract(1), 50)]);
+		assert_eq!(contracts_gas_used.top(1), vec![(contract(2), 300)]);
+		assert_eq!(contracts_gas_used.top(5), vec![(contract(2), 300), (contract(1), 150)]);
+
+		// the first block leaves the window
+		for _ in 0..GAS_USED_WINDOW - 1 {
+			contracts_gas_used.push_block(vec![(contract(1), 1)]);
+		}
+		assert_eq!(
+			contracts_gas_used.top(5),
+			vec![(contract(1), 150 + GAS_USED_WINDOW as u64 - 1), (contract(2), 300)]
+		);
+		contracts_gas_used.push_block(vec![]);
+		assert_eq!(contracts_gas_used.top(5), vec![(contract(1), GAS_USED_WINDOW as u64 - 1)]);
+		assert_eq!(contracts_gas_used.totals.len(), 1);
+		assert_eq!(contracts_gas_used.blocks.len(), GAS_USED_WINDOW);
+	}
+}
@@ -0,0 +1,268 @@
+//! Ethereum style receipts of EVM extrinsics.
+
+use ethereum_types::{Bloom, H160, H256, U256, U64};
+use serde::Serialize;
+use sc_client_api::{backend::AuxStore, BlockBackend};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{hashing::blake2_256, Bytes};
+use sp_runtime::{
+	codec::{Codec, Decode, Encode},
+	generic::BlockId,
+	traits::{Block as BlockT, MaybeDisplay, MaybeFromStr, NumberFor, One, UniqueSaturatedInto, Zero},
+};
+use std::{marker::PhantomData, sync::Arc};
+
+use module_evm::{accrue_log_bloom, Log, Receipt};
+
+use crate::{logs::EthLog, EVMRuntimeRPCApi};
+
+/// Prefix of the aux storage key holding the receipts of a block.
+const RECEIPTS_KEY_PREFIX: &[u8] = b"evm_receipts:";
+
+/// Prefix of the aux storage key mapping a transaction hash to its block.
+const TRANSACTION_KEY_PREFIX: &[u8] = b"evm_transaction:";
+
+/// Aux storage key holding the number of the canonical block up to which
+/// all blocks are indexed.
+const INDEXED_TO_KEY: &[u8] = b"evm_receipts_indexed_to";
+
+/// Ethereum compatible transaction receipt.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EthReceipt {
+	/// Hash of the raw Ethereum transaction, or of the extrinsic.
+	pub transaction_hash: H256,
+	/// Index of the extrinsic in the block.
+	pub transaction_index: U256,
+	pub block_hash: H256,
+	pub block_number: U256,
+	pub from: H160,
+	pub to: Option<H160>,
+	/// Gas used by the EVM extrinsics of the block up to this one.
+	pub cumulative_gas_used: U256,
+	pub gas_used: U256,
+	pub contract_address: Option<H160>,
+	pub logs: Vec<EthLog>,
+	pub logs_bloom: Bloom,
+	/// `1` if the execution succeeded, `0` otherwise.
+	pub status: U64,
+}
+
+/// Receipts of a block, as stored in the index: extrinsic hash and receipt.
+pub type BlockReceipts = Vec<(H256, Receipt)>;
+
+/// Bloom filter of the addresses and topics of the logs.
+pub fn logs_bloom<'a>(logs: impl IntoIterator<Item = &'a Log>) -> Bloom {
+	let mut bloom = Bloom::default();
+	for log in logs {
+		accrue_log_bloom(&mut bloom, log);
+	}
+	bloom
+}
+
+/// Indexes the receipts of blocks into the client aux storage, along with
+/// the block of each transaction.
+pub struct ReceiptIndexer<B, C, Balance> {
+	client: Arc<C>,
+	_marker: PhantomData<(B, Balance)>,
+}
+
+impl<B, C, Balance> ReceiptIndexer<B, C, Balance> {
+	pub fn new(client: Arc<C>) -> Self {
+		Self {
+			client,
+			_marker: Default::default(),
+		}
+	}
+}
+
+impl<B, C, Balance> ReceiptIndexer<B, C, Balance>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + BlockBackend<B> + AuxStore,
+	C::Api: EVMRuntimeRPCApi<B, Balance>,
+	Balance: Codec + MaybeDisplay + MaybeFromStr,
+{
+	fn key<K: Encode>(prefix: &[u8], hash: &K) -> Vec<u8> {
+		let mut key = prefix.to_vec();
+		key.extend(hash.encode());
+		key
+	}
+
+	/// Receipts of the given block, indexing the block first if required.
+	pub fn block_receipts(&self, hash: B::Hash) -> Result<BlockReceipts, String> {
+		let key = Self::key(RECEIPTS_KEY_PREFIX, &hash);
+		if let Some(encoded) = self.client.get_aux(&key).map_err(|err| format!("{:?}", err))? {
+			return BlockReceipts::decode(&mut &encoded[..]).map_err(|err| format!("{:?}", err));
+		}
+
+		let id = BlockId::Hash(hash);
+		let api = self.client.runtime_api();
+		// runtimes before version 2 of the api do not expose their receipts
+		let receipts = if api
+			.has_api_with::<dyn EVMRuntimeRPCApi<B, Balance>, _>(&id, |version| version >= 2)
+			.map_err(|err| format!("{:?}", err))?
+		{
+			api.block_receipts(&id).map_err(|err| format!("{:?}", err))?
+		} else {
+			Vec::new()
+		};
+
+		let receipts: BlockReceipts = if receipts.is_empty() {
+			Vec::new()
+		} else {
+			let extrinsics = self
+				.client
+				.block_body(&id)
+				.map_err(|err| format!("{:?}", err))?
+				.ok_or_else(|| format!("missing body of block {:?}", hash))?;
+
+			receipts
+				.into_iter()
+				.map(|receipt| {
+					let extrinsic_hash = extrinsics
+						.get(receipt.extrinsic_index as usize)
+						.map(|xt| H256::from(xt.using_encoded(blake2_256)))
+						.unwrap_or_default();
+					(extrinsic_hash, receipt)
+				})
+				.collect()
+		};
+
+		// both the extrinsic hash and the Ethereum transaction hash map to the block
+		let mut entries = vec![(key, receipts.encode())];
+		for (extrinsic_hash, receipt) in &receipts {
+			entries.push((Self::key(TRANSACTION_KEY_PREFIX, extrinsic_hash), hash.encode()));
+			if let Some(transaction_hash) = receipt.transaction_hash {
+				entries.push((Self::key(TRANSACTION_KEY_PREFIX, &transaction_hash), hash.encode()));
+			}
+		}
+		let entries: Vec<(&[u8], &[u8])> = entries.iter().map(|(key, value)| (&key[..], &value[..])).collect();
+		self.client
+			.insert_aux(&entries, &[])
+			.map_err(|err| format!("{:?}", err))?;
+
+		Ok(receipts)
+	}
+
+	/// Index the canonical blocks after the last indexed one up to `to`,
+	/// covering the blocks imported without an import notification, like
+	/// the blocks of the initial sync. Blocks whose state is pruned are
+	/// skipped. Returns the number of the last indexed block.
+	pub fn index_canonical_blocks(&self, to: NumberFor<B>) -> Result<NumberFor<B>, String> {
+		let mut indexed_to = match self.client.get_aux(INDEXED_TO_KEY).map_err(|err| format!("{:?}", err))? {
+			Some(encoded) => Some(NumberFor::<B>::decode(&mut &encoded[..]).map_err(|err| format!("{:?}", err))?),
+			None => None,
+		};
+
+		let mut number = indexed_to.map_or_else(Zero::zero, |indexed_to| indexed_to + One::one());
+		while number <= to {
+			let hash = match self.client.hash(number).map_err(|err| format!("{:?}", err))? {
+				Some(hash) => hash,
+				None => break,
+			};
+			if let Err(err) = self.block_receipts(hash) {
+				log::warn!(target: "evm", "index receipts of block {:?} failed: {}", hash, err);
+			}
+			self.client
+				.insert_aux(&[(INDEXED_TO_KEY, &number.encode()[..])], &[])
+				.map_err(|err| format!("{:?}", err))?;
+			indexed_to = Some(number);
+			number += One::one();
+		}
+
+		Ok(indexed_to.unwrap_or_default())
+	}
+
+	/// Receipt of the transaction with the given Ethereum transaction hash or
+	/// extrinsic hash, `None` unless its block is canonical and indexed.
+	///
+	/// Blocks are indexed when imported and by `index_canonical_blocks`,
+	/// the receipts of blocks not indexed yet are not found by hash.
+	pub fn receipt(&self, transaction_hash: H256) -> Result<Option<EthReceipt>, String> {
+		let key = Self::key(TRANSACTION_KEY_PREFIX, &transaction_hash);
+		let hash = match self.client.get_aux(&key).map_err(|err| format!("{:?}", err))? {
+			Some(encoded) => B::Hash::decode(&mut &encoded[..]).map_err(|err| format!("{:?}", err))?,
+			None => return Ok(None),
+		};
+
+		// the transaction may have been indexed in a retracted block
+		let number = match self.client.number(hash).map_err(|err| format!("{:?}", err))? {
+			Some(number) => number,
+			None => return Ok(None),
+		};
+		if self.client.hash(number).map_err(|err| format!("{:?}", err))? != Some(hash) {
+			return Ok(None);
+		}
+
+		let block_hash = H256::from_slice(hash.as_ref());
+		let block_number = U256::from(UniqueSaturatedInto::<u64>::unique_saturated_into(number));
+		let mut cumulative_gas_used = 0u64;
+		let mut log_index = 0usize;
+		for (extrinsic_hash, receipt) in self.block_receipts(hash)? {
+			cumulative_gas_used = cumulative_gas_used.saturating_add(receipt.used_gas);
+			let receipt_hash = receipt.transaction_hash.unwrap_or(extrinsic_hash);
+			if receipt_hash != transaction_hash && extrinsic_hash != transaction_hash {
+				log_index += receipt.logs.len();
+				continue;
+			}
+
+			let logs = receipt
+				.logs
+				.iter()
+				.enumerate()
+				.map(|(transaction_log_index, log)| EthLog {
+					address: log.address,
+					topics: log.topics.clone(),
+					data: Bytes(log.data.clone()),
+					block_hash,
+					block_number,
+					transaction_hash: receipt_hash,
+					transaction_index: U256::from(receipt.extrinsic_index),
+					log_index: U256::from(log_index + transaction_log_index),
+					transaction_log_index: U256::from(transaction_log_index),
+					removed: false,
+				})
+				.collect();
+
+			return Ok(Some(EthReceipt {
+				transaction_hash: receipt_hash,
+				transaction_index: U256::from(receipt.extrinsic_index),
+				block_hash,
+				block_number,
+				from: receipt.from,
+				to: receipt.to,
+				cumulative_gas_used: U256::from(cumulative_gas_used),
+				gas_used: U256::from(receipt.used_gas),
+				contract_address: receipt.contract_address,
+				logs,
+				logs_bloom: logs_bloom(&receipt.logs),
+				status: U64::from(receipt.succeeded as u64),
+			}));
+		}
+
+		Ok(None)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ethereum_types::BloomInput;
+
+	#[test]
+	fn logs_bloom_should_work() {
+		let log = Log {
+			address: H160::from_low_u64_be(1),
+			topics: vec![H256::from_low_u64_be(2)],
+			data: vec![3],
+		};
+		let bloom = logs_bloom(&[log]);
+
+		assert!(bloom.contains_input(BloomInput::Raw(H160::from_low_u64_be(1).as_bytes())));
+		assert!(bloom.contains_input(BloomInput::Raw(H256::from_low_u64_be(2).as_bytes())));
+		assert!(!bloom.contains_input(BloomInput::Raw(H160::from_low_u64_be(4).as_bytes())));
+		assert_eq!(logs_bloom(&[]), Bloom::default());
+	}
+}
@@ -16,8 +16,11 @@ use frame_support::{
 		EnsureOrigin,
 		ExistenceRequirement,
 		Get,
+		IsSubType,
 		OnKilledAccount,
+		OnTimestampSet,
 		ReservableCurrency,
+		StorageVersion,
 		WithdrawReasons,
 	},
 	transactional,
@@ -30,21 +33,37 @@ use primitive_types::{H256, U256};
 use serde::{Deserialize, Serialize};
 use sha3::{Digest, Keccak256};
 use sp_runtime::{
-	traits::{Convert, DispatchInfoOf, Zero, One, PostDispatchInfoOf, SignedExtension, UniqueSaturatedInto},
+	traits::{
+		BlockNumberProvider, Convert, DispatchInfoOf, One, PostDispatchInfoOf, Saturating, SignedExtension,
+		UniqueSaturatedInto, Zero,
+	},
 	transaction_validity::TransactionValidityError,
 	Either, TransactionOutcome,
 };
+use sp_core::ecdsa;
 use sp_std::{marker::PhantomData, vec::Vec};
-use support::{EVMStateRentTrait, ExecutionMode, InvokeContext, TransactionPayment, EVM as EVMTrait};
+use support::{
+	EVMStateRentTrait, ExecutionMode, InvokeContext, TransactionFeeSponsor, TransactionPayment, EVM as EVMTrait,
+};
+
+use crate::meta_call::MetaCall;
+use crate::runner::handler::{Handler, STORAGE_SIZE};
+use crate::transaction::{EthereumTransaction, TransactionAction};
 
 pub use crate::precompiles::{Precompile, Precompiles};
 pub use crate::runner::Runner;
 pub use evm::{Context, ExitError, ExitFatal, ExitReason, ExitRevert, ExitSucceed};
 pub use orml_traits::currency::TransferAll;
-pub use primitives::evm::{Account, AddressMapping, CallInfo, CreateInfo, EvmAddress, Log, Vicinity};
+pub use primitives::evm::{
+	accrue_log_bloom, AccessListInfo, AccessListItem, Account, AddressMapping, BatchCall, Bloom, CallInfo, CodeInfo,
+	CreateInfo, DevOperation, EthereumHeader, EvmAddress, Log, Receipt, SourceInfo, StateOverride, StorageRange,
+	TraceConfig, TraceInfo, Vicinity,
+};
 
+pub mod meta_call;
 pub mod precompiles;
 pub mod runner;
+pub mod transaction;
 
 mod default_weight;
 mod mock;
@@ -65,6 +84,23 @@ pub trait WeightInfo {
 	fn disable_contract_development() -> Weight;
 	fn set_code() -> Weight;
 	fn selfdestruct() -> Weight;
+	fn set_hard_fork() -> Weight;
+	fn check_storage_rent() -> Weight;
+	fn top_up_storage_deposit() -> Weight;
+	fn archive_contract(s: u32) -> Weight;
+	fn restore_contract(s: u32) -> Weight;
+	fn propose_upgrade() -> Weight;
+	fn enact_upgrade() -> Weight;
+	fn approve_upgrade() -> Weight;
+	fn cancel_upgrade() -> Weight;
+	fn rollback_contract() -> Weight;
+	fn set_deployment_policy() -> Weight;
+	fn set_deployer_allowed() -> Weight;
+	fn set_sponsor_cap() -> Weight;
+	fn fund_gas_tank() -> Weight;
+	fn withdraw_gas_tank() -> Weight;
+	fn set_gas_tank_limit() -> Weight;
+	fn dev_operation() -> Weight;
 }
 
 // Initially based on Istanbul hard fork configuration.
@@ -106,6 +142,89 @@ static EVM_CONFIG: EvmConfig = EvmConfig {
 	estimate: false,
 };
 
+// Berlin hard fork configuration. The state access costs are the EIP-2929 warm
+// access costs, the runner charges the cold access surcharge on first access.
+static EVM_BERLIN_CONFIG: EvmConfig = EvmConfig {
+	gas_ext_code: 100,
+	gas_ext_code_hash: 100,
+	gas_balance: 100,
+	gas_sload: 100,
+	gas_sstore_set: 20000,
+	gas_sstore_reset: 2900,
+	refund_sstore_clears: 0, // no gas refund
+	gas_suicide: 5000,
+	gas_suicide_new_account: 25000,
+	gas_call: 100,
+	gas_expbyte: 50,
+	gas_transaction_create: 53000,
+	gas_transaction_call: 21000,
+	gas_transaction_zero_data: 4,
+	gas_transaction_non_zero_data: 16,
+	sstore_gas_metering: false,         // no gas refund
+	sstore_revert_under_stipend: false, // ignored
+	err_on_call_with_more_gas: false,
+	empty_considered_exists: false,
+	create_increase_nonce: true,
+	call_l64_after_gas: true,
+	stack_limit: 1024,
+	memory_limit: usize::max_value(),
+	call_stack_limit: 1024,
+	create_contract_limit: None, // ignored
+	call_stipend: 2300,
+	has_delegate_call: true,
+	has_create2: true,
+	has_revert: true,
+	has_return_data: true,
+	has_bitwise_shifting: true,
+	has_chain_id: true,
+	has_self_balance: true,
+	has_ext_code_hash: true,
+	estimate: false,
+};
+
+/// EVM hard fork level.
+#[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord, RuntimeDebug, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum HardFork {
+	Istanbul,
+	/// EIP-2929 state access gas costs.
+	Berlin,
+	/// BASEFEE opcode and EIP-3541 rejection of new code starting with `0xEF`.
+	London,
+}
+
+impl Default for HardFork {
+	fn default() -> Self {
+		HardFork::Istanbul
+	}
+}
+
+/// Progress of the backfill of the storage sizes and deposits of the
+/// contracts created before they were tracked, run over several blocks.
+#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+pub enum StorageBackfillCursor {
+	/// Backfill the accounts after the given one, from the first if `None`.
+	NextAccount(Option<EvmAddress>),
+	/// Count the storage slots of a contract from the `next` key, `slots`
+	/// were counted before it.
+	CountSlots {
+		contract: EvmAddress,
+		next: Option<H256>,
+		slots: u32,
+	},
+}
+
+impl HardFork {
+	/// EVM config of the hard fork.
+	pub fn config(&self) -> &'static EvmConfig {
+		match self {
+			HardFork::Istanbul => &EVM_CONFIG,
+			// London only adds opcodes and checks which are handled by the runner
+			HardFork::Berlin | HardFork::London => &EVM_BERLIN_CONFIG,
+		}
+	}
+}
+
 #[frame_support::pallet]
 pub mod module {
 	use super::*;
@@ -150,9 +269,12 @@ pub mod module {
 		/// ChargeTransactionPayment convert weight to fee.
 		type ChargeTransactionPayment: TransactionPayment<Self::AccountId, BalanceOf<Self>, NegativeImbalanceOf<Self>>;
 
-		/// EVM config used in the module.
+		/// Base fee per gas, the gas price of EVM transactions.
+		type BaseFee: Get<U256>;
+
+		/// EVM config used in the module, depends on the current hard fork.
 		fn config() -> &'static EvmConfig {
-			&EVM_CONFIG
+			Pallet::<Self>::hard_fork().config()
 		}
 
 		/// Required origin for creating system contract.
@@ -172,6 +294,27 @@ pub mod module {
 
 		type FreeDeploymentOrigin: EnsureOrigin<Self::Origin>;
 
+		/// Required origin for scheduling a hard fork.
+		type HardForkOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Required origin for setting the deployment policy and the deployer
+		/// allow-list.
+		type DeploymentPolicyOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Blocks a contract may stay under-collateralised before it can be
+		/// archived.
+		#[pallet::constant]
+		type RentGracePeriod: Get<Self::BlockNumber>;
+
+		/// Blocks a proposed contract upgrade waits before anyone can enact it,
+		/// unless `NetworkContractOrigin` approves it earlier.
+		#[pallet::constant]
+		type UpgradeDelay: Get<Self::BlockNumber>;
+
+		/// Previous code versions kept for each upgraded contract.
+		#[pallet::constant]
+		type MaxCodeHistory: Get<u32>;
+
 		/// Weight information for the extrinsics in this module.
 		type WeightInfo: WeightInfo;
 	}
@@ -200,10 +343,60 @@ pub mod module {
 		}
 	}
 
-	#[derive(Clone, Copy, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
-	pub struct CodeInfo {
-		pub code_size: u32,
-		pub ref_count: u32,
+	/// Tombstone of an archived contract.
+	#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+	pub struct ArchivedContractInfo {
+		pub contract_info: ContractInfo,
+		/// Hash of the sorted storage entries of the contract.
+		pub storage_root: H256,
+		/// Storage bytes of the contract, including code.
+		pub storage_size: u32,
+	}
+
+	/// Code upgrade proposed for a deployed contract.
+	#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+	pub struct PendingUpgrade<BlockNumber, AccountId, Balance> {
+		pub code_hash: H256,
+		/// Block from which anyone can enact the upgrade.
+		pub enact_at: BlockNumber,
+		/// Maintainer who proposed the upgrade and the deposit reserved for
+		/// the code, moved into the storage deposit of the contract once the
+		/// upgrade is enacted.
+		pub depositor: AccountId,
+		pub deposit: Balance,
+	}
+
+	/// Previous code version of an upgraded contract and the deposit covering
+	/// it, held until the code version leaves the code history.
+	#[derive(Clone, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+	pub struct HistoricCode<AccountId, Balance> {
+		pub code_hash: H256,
+		pub depositor: AccountId,
+		pub deposit: Balance,
+	}
+
+	/// Policy for creating contracts, the default one does not restrict
+	/// deployments.
+	#[derive(Clone, Default, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+	pub struct DeploymentPolicy<BlockNumber, Balance> {
+		/// Only allow-listed deployers can create contracts.
+		pub allow_list_enabled: bool,
+		/// Contracts a deployer can create within `quota_period` blocks, zero
+		/// for unlimited.
+		pub quota: u32,
+		pub quota_period: BlockNumber,
+		/// Fee per byte of contract code, charged on top of `DeploymentFee`.
+		pub fee_per_byte: Balance,
+	}
+
+	/// Fees prepaid by the maintainer for calls to a contract.
+	#[derive(Clone, Default, Eq, PartialEq, RuntimeDebug, Encode, Decode)]
+	pub struct GasTank<Balance, BlockNumber> {
+		/// Amount reserved on the contract account for paying fees.
+		pub balance: Balance,
+		/// Calls of a caller paid within `period` blocks, zero for unlimited.
+		pub calls_per_period: u32,
+		pub period: BlockNumber,
 	}
 
 	#[cfg(feature = "std")]
@@ -218,6 +411,17 @@ pub mod module {
 		pub storage: std::collections::BTreeMap<H256, H256>,
 		/// Account code.
 		pub code: Vec<u8>,
+		/// Maintainer of the contract, the zero address by default.
+		#[serde(default)]
+		pub maintainer: EvmAddress,
+		/// Whether the contract is deployed, `true` by default.
+		#[serde(default = "genesis_account_deployed")]
+		pub deployed: bool,
+	}
+
+	#[cfg(feature = "std")]
+	fn genesis_account_deployed() -> bool {
+		true
 	}
 
 	/// Accounts info.
@@ -238,6 +442,78 @@ pub mod module {
 	#[pallet::getter(fn code_infos)]
 	pub type CodeInfos<T: Config> = StorageMap<_, Identity, H256, CodeInfo>;
 
+	/// Storage bytes paid by each contract, including code and extra bytes.
+	#[pallet::storage]
+	#[pallet::getter(fn contract_storage_size)]
+	pub type ContractStorageSizes<T: Config> = StorageMap<_, Twox64Concat, EvmAddress, u32, ValueQuery>;
+
+	/// Storage deposit reserved on the account of each contract, excluding
+	/// its gas tank.
+	#[pallet::storage]
+	#[pallet::getter(fn storage_deposit)]
+	pub type StorageDeposits<T: Config> = StorageMap<_, Twox64Concat, EvmAddress, BalanceOf<T>, ValueQuery>;
+
+	/// Progress of the storage sizes and deposits backfill, `None` once done.
+	#[pallet::storage]
+	#[pallet::getter(fn storage_backfill)]
+	pub type StorageBackfill<T: Config> = StorageValue<_, StorageBackfillCursor>;
+
+	/// Block since which a contract has been reported under-collateralised.
+	#[pallet::storage]
+	#[pallet::getter(fn under_collateralised_since)]
+	pub type UnderCollateralisedSince<T: Config> = StorageMap<_, Twox64Concat, EvmAddress, T::BlockNumber>;
+
+	/// Archived contracts.
+	#[pallet::storage]
+	#[pallet::getter(fn archived_contracts)]
+	pub type ArchivedContracts<T: Config> = StorageMap<_, Twox64Concat, EvmAddress, ArchivedContractInfo>;
+
+	/// Code upgrades proposed for deployed contracts.
+	#[pallet::storage]
+	#[pallet::getter(fn pending_upgrades)]
+	pub type PendingUpgrades<T: Config> =
+		StorageMap<_, Twox64Concat, EvmAddress, PendingUpgrade<T::BlockNumber, T::AccountId, BalanceOf<T>>>;
+
+	/// Previous code hashes of upgraded contracts, oldest first.
+	#[pallet::storage]
+	#[pallet::getter(fn code_history)]
+	pub type CodeHistory<T: Config> =
+		StorageMap<_, Twox64Concat, EvmAddress, Vec<HistoricCode<T::AccountId, BalanceOf<T>>>, ValueQuery>;
+
+	/// The deployment policy.
+	#[pallet::storage]
+	#[pallet::getter(fn deployment_policy)]
+	pub type CurrentDeploymentPolicy<T: Config> =
+		StorageValue<_, DeploymentPolicy<T::BlockNumber, BalanceOf<T>>, ValueQuery>;
+
+	/// Deployers allowed to create contracts while the allow-list is enabled.
+	#[pallet::storage]
+	#[pallet::getter(fn is_allowed_deployer)]
+	pub type AllowedDeployers<T: Config> = StorageMap<_, Twox64Concat, EvmAddress, bool, ValueQuery>;
+
+	/// Start of the current quota period of a deployer and the contracts
+	/// created in it.
+	#[pallet::storage]
+	#[pallet::getter(fn deployment_quotas)]
+	pub type DeploymentQuotas<T: Config> =
+		StorageMap<_, Twox64Concat, EvmAddress, (T::BlockNumber, u32), ValueQuery>;
+
+	/// Logs bloom of the current block.
+	#[pallet::storage]
+	#[pallet::getter(fn block_logs_bloom)]
+	pub type BlockLogsBloom<T: Config> = StorageValue<_, Bloom, ValueQuery>;
+
+	/// Gas used by the EVM executions of the current block.
+	#[pallet::storage]
+	#[pallet::getter(fn block_gas_used)]
+	pub type BlockGasUsed<T: Config> = StorageValue<_, u64, ValueQuery>;
+
+	/// Ethereum header projections of the last `ETHEREUM_HEADERS_KEPT`
+	/// finalized blocks, by block number.
+	#[pallet::storage]
+	#[pallet::getter(fn ethereum_headers)]
+	pub type EthereumHeaders<T: Config> = StorageMap<_, Twox64Concat, T::BlockNumber, EthereumHeader>;
+
 	/// Next available system contract address.
 	#[pallet::storage]
 	#[pallet::getter(fn network_contract_index)]
@@ -248,6 +524,66 @@ pub mod module {
 	#[pallet::getter(fn extrinsic_origin)]
 	pub type ExtrinsicOrigin<T: Config> = StorageValue<_, T::AccountId>;
 
+	/// Account paying the storage deposit of the current sponsored call,
+	/// instead of its origin.
+	#[pallet::storage]
+	#[pallet::getter(fn storage_deposit_payer)]
+	pub type StorageDepositPayer<T: Config> = StorageValue<_, T::AccountId>;
+
+	/// Remaining amount a sponsor pays for calls to a contract.
+	#[pallet::storage]
+	#[pallet::getter(fn sponsor_caps)]
+	pub type SponsorCaps<T: Config> =
+		StorageDoubleMap<_, Twox64Concat, T::AccountId, Twox64Concat, EvmAddress, BalanceOf<T>>;
+
+	/// Gas tanks of contracts.
+	#[pallet::storage]
+	#[pallet::getter(fn gas_tanks)]
+	pub type GasTanks<T: Config> = StorageMap<_, Twox64Concat, EvmAddress, GasTank<BalanceOf<T>, T::BlockNumber>>;
+
+	/// Start of the current period of a caller of a contract and the calls
+	/// paid by the gas tank in it.
+	#[pallet::storage]
+	#[pallet::getter(fn gas_tank_usage)]
+	pub type GasTankUsage<T: Config> =
+		StorageDoubleMap<_, Twox64Concat, EvmAddress, Twox64Concat, EvmAddress, (T::BlockNumber, u32), ValueQuery>;
+
+	/// The active EVM hard fork.
+	#[pallet::storage]
+	#[pallet::getter(fn hard_fork)]
+	pub type CurrentHardFork<T: Config> = StorageValue<_, HardFork, ValueQuery>;
+
+	/// Hard fork to activate at the given block.
+	#[pallet::storage]
+	#[pallet::getter(fn scheduled_hard_fork)]
+	pub type ScheduledHardFork<T: Config> = StorageValue<_, (T::BlockNumber, HardFork)>;
+
+	/// Whether the `dev_operation` extrinsic is accepted, on development
+	/// chains only.
+	#[pallet::storage]
+	#[pallet::getter(fn dev_mode)]
+	pub type DevMode<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	/// Milliseconds added to the timestamp of blocks in dev mode.
+	#[pallet::storage]
+	#[pallet::getter(fn time_offset)]
+	pub type TimeOffset<T: Config> = StorageValue<_, u64, ValueQuery>;
+
+	/// Timestamp of the next block in dev mode, in milliseconds.
+	#[pallet::storage]
+	#[pallet::getter(fn next_block_timestamp)]
+	pub type NextBlockTimestamp<T: Config> = StorageValue<_, u64>;
+
+	/// Offset added to `pallet_timestamp::Now` in the current block, removed
+	/// before the next timestamp is set.
+	#[pallet::storage]
+	pub type AppliedTimeOffset<T: Config> = StorageValue<_, u64, ValueQuery>;
+
+	/// Blocks added to the block number of `DevBlockNumber` in dev mode.
+	#[pallet::storage]
+	#[pallet::getter(fn block_number_offset)]
+	pub type BlockNumberOffset<T: Config> = StorageValue<_, T::BlockNumber, ValueQuery>;
+
 	#[pallet::type_value]
 	pub fn EmptyEventVec<T: Config>() -> Vec<Event<T>> { Vec::new() }
 
@@ -259,6 +595,9 @@ pub mod module {
 	#[pallet::genesis_config]
 	pub struct GenesisConfig<T: Config> {
 		pub accounts: std::collections::BTreeMap<EvmAddress, GenesisAccount<BalanceOf<T>, T::Index>>,
+		/// Accept the `dev_operation` extrinsic, for development chains.
+		#[serde(default)]
+		pub dev_mode: bool,
 	}
 
 	#[cfg(feature = "std")]
@@ -266,6 +605,7 @@ pub mod module {
 		fn default() -> Self {
 			GenesisConfig {
 				accounts: Default::default(),
+				dev_mode: false,
 			}
 		}
 	}
@@ -284,18 +624,25 @@ pub mod module {
 				if !account.code.is_empty() {
 					// if code len > 0 then it's a contract
 					let used_gas_and_storage: (u64, i32) = (Zero::zero(), Zero::zero());
-					<Pallet<T>>::on_contract_initialization(address, &EvmAddress::default(), account.code.clone(), used_gas_and_storage)
+					<Pallet<T>>::on_contract_initialization(address, &account.maintainer, account.code.clone(), used_gas_and_storage)
 						.expect("Genesis contract shouldn't fail");
 
 					#[cfg(not(feature = "with-ethereum-compatibility"))]
-					<Pallet<T>>::mark_deployed(*address, None).expect("Genesis contract shouldn't fail");
+					if account.deployed {
+						<Pallet<T>>::mark_deployed(*address, None).expect("Genesis contract shouldn't fail");
+					}
 
 					for (index, value) in &account.storage {
 						AccountStorages::<T>::insert(address, index, value);
 					}
+					ContractStorageSizes::<T>::insert(
+						address,
+						<Pallet<T>>::storage_size_of(account.code.len() as u32, account.storage.len() as u32),
+					);
 				}
 			});
 			NetworkContractIndex::<T>::put(primitives::NETWORK_CONTRACT_START);
+			DevMode::<T>::put(self.dev_mode);
 		}
 	}
 
@@ -343,6 +690,54 @@ pub mod module {
 		ContractSetCode(EvmAddress),
 		/// Selfdestructed contract code. \[caller, contract\]
 		ContractSelfdestructed(EvmAddress, EvmAddress),
+		/// Scheduled a hard fork. \[hard_fork, block_number\]
+		HardForkScheduled(HardFork, T::BlockNumber),
+		/// Activated a hard fork. \[hard_fork\]
+		HardForkActivated(HardFork),
+		/// Contract deposit does not cover its storage. \[contract\]
+		ContractUnderCollateralised(EvmAddress),
+		/// Contract deposit covers its storage again. \[contract\]
+		ContractCollateralised(EvmAddress),
+		/// Topped up the storage deposit of a contract. \[who, contract, amount\]
+		StorageDepositToppedUp(T::AccountId, EvmAddress, BalanceOf<T>),
+		/// Archived an under-collateralised contract. \[contract\]
+		ContractArchived(EvmAddress),
+		/// Restored an archived contract. \[who, contract\]
+		ContractRestored(T::AccountId, EvmAddress),
+		/// Reserved storage deposit for a contract. \[who, contract, amount\]
+		StorageDepositCharged(EvmAddress, EvmAddress, BalanceOf<T>),
+		/// Refunded storage deposit of a contract. \[who, contract, amount\]
+		StorageDepositRefunded(EvmAddress, EvmAddress, BalanceOf<T>),
+		/// Proposed a contract upgrade. \[contract, code_hash, enact_at\]
+		UpgradeProposed(EvmAddress, H256, T::BlockNumber),
+		/// Canceled a contract upgrade. \[contract, code_hash\]
+		UpgradeCanceled(EvmAddress, H256),
+		/// Upgraded the code of a contract. \[contract, code_hash\]
+		ContractUpgraded(EvmAddress, H256),
+		/// Rolled back the code of a contract. \[contract, code_hash\]
+		ContractRolledBack(EvmAddress, H256),
+		/// Set the deployment policy.
+		DeploymentPolicySet,
+		/// Allowed a deployer to create contracts. \[deployer\]
+		DeployerAllowed(EvmAddress),
+		/// Disallowed a deployer to create contracts. \[deployer\]
+		DeployerDisallowed(EvmAddress),
+		/// Set the amount a sponsor pays for calls to a contract. \[sponsor, contract, cap\]
+		SponsorCapSet(T::AccountId, EvmAddress, BalanceOf<T>),
+		/// Sponsored a call signed by another account. \[sponsor, from, contract, amount\]
+		CallSponsored(T::AccountId, EvmAddress, EvmAddress, BalanceOf<T>),
+		/// Funded the gas tank of a contract. \[who, contract, amount\]
+		GasTankFunded(T::AccountId, EvmAddress, BalanceOf<T>),
+		/// Withdrew from the gas tank of a contract. \[who, contract, amount\]
+		GasTankWithdrawn(T::AccountId, EvmAddress, BalanceOf<T>),
+		/// Set the rate limit of the gas tank of a contract. \[contract, calls_per_period, period\]
+		GasTankLimitSet(EvmAddress, u32, T::BlockNumber),
+		/// Paid the fee of a call from the gas tank of the contract. \[contract, caller, fee\]
+		GasTankCharged(EvmAddress, EvmAddress, BalanceOf<T>),
+		/// Executed a raw Ethereum transaction. \[from, transaction_hash\]
+		Transacted(EvmAddress, H256),
+		/// Applied a dev operation.
+		DevOperationApplied,
 	}
 
 	#[pallet::error]
@@ -371,18 +766,150 @@ pub mod module {
 		ChargeFeeFailed,
 		/// Contract address conflicts with the system contract
 		ConflictContractAddress,
+		/// New contract code starts with the reserved 0xEF byte
+		ReservedCodePrefix,
+		/// Contract deposit covers its storage
+		ContractCollateralised,
+		/// Contract has not been reported under-collateralised
+		ContractNotUnderCollateralised,
+		/// Rent grace period has not elapsed yet
+		RentGracePeriodNotElapsed,
+		/// Contract is not archived
+		ContractNotArchived,
+		/// Code does not match the archived contract
+		ArchivedCodeMismatch,
+		/// Storage does not match the archived contract
+		ArchivedStorageMismatch,
+		/// Access lists require the Berlin hard fork
+		AccessListNotSupported,
+		/// Contract is not deployed
+		ContractNotDeployed,
+		/// Contract already has a pending upgrade
+		UpgradeAlreadyPending,
+		/// Contract has no pending upgrade
+		UpgradeNotFound,
+		/// Upgrade delay has not elapsed yet
+		UpgradeTimelocked,
+		/// Code hash is not a previous version of the contract
+		CodeNotInHistory,
+		/// Deployer is not on the deployment allow-list
+		DeployerNotAllowed,
+		/// Deployer created its quota of contracts in the current period
+		DeploymentQuotaExceeded,
+		/// Signed call is past its last valid block
+		MetaCallExpired,
+		/// Signature does not match the signed call
+		InvalidSignature,
+		/// Call may cost more than the sponsor cap left for the contract
+		SponsorCapExceeded,
+		/// Contract has no gas tank
+		GasTankNotFound,
+		/// Gas tank balance is too low
+		InsufficientGasTank,
+		/// Raw Ethereum transaction does not decode
+		InvalidRawTransaction,
+		/// Ethereum transaction is signed for another chain
+		InvalidChainId,
+		/// Ethereum transaction nonce is not the account nonce
+		InvalidNonce,
+		/// Ethereum transaction gas price is below the base fee
+		GasPriceTooLow,
+		/// Dev operations are only accepted on development chains
+		DevModeDisabled,
+		/// Timestamp of the next block is not after the current time
+		InvalidTimestamp,
+		/// Hard fork is not after the active hard fork
+		HardForkDowngrade,
+		/// Storage rent is not checked before the storage deposits are backfilled
+		StorageBackfillPending,
+		/// Deployment quota is set without a quota period
+		InvalidDeploymentPolicy,
 	}
 
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
+	/// Ethereum headers kept in the state, as many blocks as `BLOCKHASH`
+	/// reaches.
+	pub const ETHEREUM_HEADERS_KEPT: u32 = 256;
+
+	/// Accounts and storage slots visited by the storage backfill per block.
+	pub const STORAGE_BACKFILL_ITEMS: u32 = 500;
+
+	/// Calls of each caller a new gas tank pays within `GAS_TANK_PERIOD`
+	/// blocks, until the maintainer sets another limit.
+	pub const GAS_TANK_CALLS_PER_PERIOD: u32 = 10;
+	pub const GAS_TANK_PERIOD: u32 = 100;
+
 	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(PhantomData<T>);
 
 	#[pallet::hooks]
-	impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {}
+	impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			// reads and writes of `on_finalize`
+			let mut finalize_weight = T::DbWeight::get().reads_writes(2, 4);
+
+			// the timestamp inherent must follow the timestamp without offset
+			let applied_offset = AppliedTimeOffset::<T>::take();
+			if applied_offset > 0 {
+				pallet_timestamp::Now::<T>::mutate(|moment| {
+					*moment = moment.saturating_sub(applied_offset.unique_saturated_into())
+				});
+				finalize_weight = finalize_weight.saturating_add(T::DbWeight::get().reads_writes(1, 2));
+			} else {
+				finalize_weight = finalize_weight.saturating_add(T::DbWeight::get().reads(1));
+			}
+
+			finalize_weight = finalize_weight.saturating_add(T::DbWeight::get().reads(1));
+			if let Some(cursor) = Self::storage_backfill() {
+				finalize_weight =
+					finalize_weight.saturating_add(Self::backfill_storage_deposits(cursor, STORAGE_BACKFILL_ITEMS));
+			}
+
+			match Self::scheduled_hard_fork() {
+				Some((at, hard_fork)) if at <= now => {
+					Self::activate_hard_fork(hard_fork);
+					T::DbWeight::get().reads_writes(1, 2) + finalize_weight
+				}
+				_ => T::DbWeight::get().reads(1) + finalize_weight,
+			}
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			if StorageVersion::get::<Pallet<T>>() >= STORAGE_VERSION {
+				return T::DbWeight::get().reads(1);
+			}
+
+			// the backfill runs from `on_initialize`, a few contracts per block
+			StorageBackfill::<T>::put(StorageBackfillCursor::NextAccount(None));
+			STORAGE_VERSION.put::<Pallet<T>>();
+			T::DbWeight::get().reads_writes(1, 2)
+		}
+
+		fn on_finalize(now: T::BlockNumber) {
+			let moment: u64 = pallet_timestamp::Pallet::<T>::get().unique_saturated_into();
+			if now >= ETHEREUM_HEADERS_KEPT.into() {
+				EthereumHeaders::<T>::remove(now - ETHEREUM_HEADERS_KEPT.into());
+			}
+			EthereumHeaders::<T>::insert(now, EthereumHeader {
+				parent_hash: H256::from_slice(frame_system::Pallet::<T>::parent_hash().as_ref()),
+				state_root: H256::zero(),
+				number: now.unique_saturated_into(),
+				gas_used: BlockGasUsed::<T>::take(),
+				timestamp: moment / 1000,
+				logs_bloom: BlockLogsBloom::<T>::take(),
+			});
+		}
+	}
 
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
 		/// Issue an EVM call operation. This is similar to a message call
 		/// transaction in Ethereum.
+		///
+		/// The gas tank of the target pays the fee instead of the caller if
+		/// it covers the fee and the caller is within its rate limit.
 		#[pallet::weight(T::GasToWeight::convert(*gas_limit))]
 		#[transactional]
 		pub fn call(
@@ -394,42 +921,7 @@ pub mod module {
 			storage_limit: u32,
 		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
-			let source = T::AddressMapping::get_or_create_evm_address(&who);
-
-			let info = Runner::<T>::call(
-				source,
-				source,
-				target,
-				input,
-				value,
-				gas_limit,
-				storage_limit,
-				T::config(),
-			)?;
-			let used_gas: u64 = info.used_gas.unique_saturated_into();
-
-			if info.exit_reason.is_succeed() {
-				Pallet::<T>::deposit_event(Event::<T>::Executed(
-					source,
-					target,
-					(used_gas, info.used_storage.unique_saturated_into())
-				));
-			} else {
-				Pallet::<T>::deposit_event(Event::<T>::ExecutedFailed(
-					source,
-					target,
-					info.exit_reason,
-					info.output,
-					(gas_limit, Zero::zero())
-				));
-			}
-
-			Self::process_queued_events()?;
-
-			Ok(PostDispatchInfo {
-				actual_weight: Some(T::GasToWeight::convert(used_gas)),
-				pays_fee: Pays::Yes,
-			})
+			Self::do_call(&who, target, input, value, gas_limit, storage_limit, Vec::new())
 		}
 
 		#[pallet::weight(T::GasToWeight::convert(*gas_limit))]
@@ -471,7 +963,7 @@ pub mod module {
 					target,
 					info.exit_reason,
 					info.output,
-					(gas_limit, Zero::zero())
+					(info.used_gas.unique_saturated_into(), Zero::zero())
 				));
 			}
 
@@ -511,28 +1003,7 @@ pub mod module {
 			storage_limit: u32,
 		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
-			let source = T::AddressMapping::get_or_create_evm_address(&who);
-
-			let info = Runner::<T>::create(source, init, value, gas_limit, storage_limit, T::config())?;
-
-			if !info.exit_reason.is_succeed() {
-				Pallet::<T>::deposit_event(Event::<T>::CreatedFailed(
-					source,
-					info.address,
-					info.exit_reason,
-					info.output,
-					(gas_limit, Zero::zero())
-				));
-			}
-
-			Self::process_queued_events()?;
-
-			let used_gas: u64 = info.used_gas.unique_saturated_into();
-
-			Ok(PostDispatchInfo {
-				actual_weight: Some(T::GasToWeight::convert(used_gas)),
-				pays_fee: Pays::Yes,
-			})
+			Self::do_create(&who, init, None, value, gas_limit, storage_limit, Vec::new())
 		}
 
 		/// Issue an EVM create2 operation.
@@ -547,28 +1018,7 @@ pub mod module {
 			storage_limit: u32,
 		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
-			let source = T::AddressMapping::get_or_create_evm_address(&who);
-
-			let info = Runner::<T>::create2(source, init, salt, value, gas_limit, storage_limit, T::config())?;
-
-			if !info.exit_reason.is_succeed() {
-				Pallet::<T>::deposit_event(Event::<T>::CreatedFailed(
-					source,
-					info.address,
-					info.exit_reason,
-					info.output,
-					(gas_limit, Zero::zero())
-				));
-			}
-
-			Self::process_queued_events()?;
-
-			let used_gas: u64 = info.used_gas.unique_saturated_into();
-
-			Ok(PostDispatchInfo {
-				actual_weight: Some(T::GasToWeight::convert(used_gas)),
-				pays_fee: Pays::Yes,
-			})
+			Self::do_create(&who, init, Some(salt), value, gas_limit, storage_limit, Vec::new())
 		}
 
 		/// Issue an EVM create operation. The next available system contract
@@ -597,7 +1047,7 @@ pub mod module {
 					info.address,
 					info.exit_reason,
 					info.output,
-					(gas_limit, Zero::zero())
+					(info.used_gas.unique_saturated_into(), Zero::zero())
 				));
 			}
 
@@ -633,7 +1083,7 @@ pub mod module {
 			let address = T::AddressMapping::get_evm_address(&who).ok_or(Error::<T>::AddressNotMapped)?;
 			T::Currency::withdraw(
 				&who,
-				T::DeploymentFee::get(),
+				Self::deployment_fee(&contract),
 				WithdrawReasons::FEE,
 				ExistenceRequirement::KeepAlive)?;
 			Self::mark_deployed(contract, Some(address))?;
@@ -715,48 +1165,1434 @@ pub mod module {
 
 			Ok(().into())
 		}
-	}
-}
-
-impl<T: Config> Pallet<T> {
-	/// Process queued events.
-	/// The event queue must be purged at the end of each extrinsic call.
-	pub fn process_queued_events() -> DispatchResult {
-		for event in Self::queued_events() {
-			Pallet::<T>::deposit_event(event);
-		}
-		QueuedEvents::<T>::set(Vec::new());
-		Ok(())
-	}
-
-	/// Remove an account.
-	pub fn remove_account(address: &EvmAddress) -> Result<u32, ExitError> {
-		let mut size = 0u32;
 
-		// Deref code, and remove it if ref count is zero.
-		if let Some(EvmAccountInfo {
-			contract_info: Some(contract_info),
-			..
-		}) = Self::accounts(address)
-		{
-			CodeInfos::<T>::mutate_exists(&contract_info.code_hash, |maybe_code_info| {
-				if let Some(code_info) = maybe_code_info.as_mut() {
-					size = code_info.code_size;
-					code_info.ref_count = code_info.ref_count.saturating_sub(1);
-					if code_info.ref_count == 0 {
-						Codes::<T>::remove(&contract_info.code_hash);
-						*maybe_code_info = None;
-					}
+		/// Report a contract whose deposit does not cover its storage, or clear
+		/// the report once it does again.
+		#[pallet::weight(<T as Config>::WeightInfo::check_storage_rent())]
+		#[transactional]
+		pub fn check_storage_rent(origin: OriginFor<T>, contract: EvmAddress) -> DispatchResultWithPostInfo {
+			ensure_signed(origin)?;
+			Self::contract_info(&contract).ok_or(Error::<T>::ContractNotFound)?;
+			ensure!(Self::storage_backfill().is_none(), Error::<T>::StorageBackfillPending);
+
+			let reported = UnderCollateralisedSince::<T>::contains_key(&contract);
+			if Self::is_under_collateralised(&contract) {
+				if !reported {
+					UnderCollateralisedSince::<T>::insert(&contract, frame_system::Pallet::<T>::block_number());
+					Pallet::<T>::deposit_event(Event::<T>::ContractUnderCollateralised(contract));
 				}
-			});
+			} else {
+				ensure!(reported, Error::<T>::ContractCollateralised);
+				UnderCollateralisedSince::<T>::remove(&contract);
+				Pallet::<T>::deposit_event(Event::<T>::ContractCollateralised(contract));
+			}
+
+			Ok(().into())
 		}
 
-		Accounts::<T>::remove(address);
+		/// Add `amount` to the storage deposit of a contract.
+		#[pallet::weight(<T as Config>::WeightInfo::top_up_storage_deposit())]
+		#[transactional]
+		pub fn top_up_storage_deposit(
+			origin: OriginFor<T>,
+			contract: EvmAddress,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::contract_info(&contract).ok_or(Error::<T>::ContractNotFound)?;
+
+			let contract_account_id = T::AddressMapping::get_account_id(&contract);
+			T::Currency::transfer(&who, &contract_account_id, amount, ExistenceRequirement::AllowDeath)?;
+			T::Currency::reserve(&contract_account_id, amount)?;
+			StorageDeposits::<T>::mutate(&contract, |deposit| *deposit = deposit.saturating_add(amount));
+
+			if !Self::is_under_collateralised(&contract) && UnderCollateralisedSince::<T>::take(&contract).is_some() {
+				Pallet::<T>::deposit_event(Event::<T>::ContractCollateralised(contract));
+			}
+
+			Pallet::<T>::deposit_event(Event::<T>::StorageDepositToppedUp(who, contract, amount));
+			Ok(().into())
+		}
+
+		/// Archive a contract which stayed under-collateralised past the rent
+		/// grace period. The storage is replaced by a tombstone, the deposit
+		/// is released to the contract account and the gas tank is refunded to
+		/// the maintainer.
+		#[pallet::weight(<T as Config>::WeightInfo::archive_contract(
+			Pallet::<T>::contract_storage_size(contract) / STORAGE_SIZE
+		))]
+		#[transactional]
+		pub fn archive_contract(origin: OriginFor<T>, contract: EvmAddress) -> DispatchResultWithPostInfo {
+			ensure_signed(origin)?;
+
+			let since = Self::under_collateralised_since(&contract).ok_or(Error::<T>::ContractNotUnderCollateralised)?;
+			ensure!(
+				since.saturating_add(T::RentGracePeriod::get()) <= frame_system::Pallet::<T>::block_number(),
+				Error::<T>::RentGracePeriodNotElapsed
+			);
+			ensure!(
+				Self::is_under_collateralised(&contract),
+				Error::<T>::ContractCollateralised
+			);
+
+			Self::do_archive(&contract)?;
+
+			Pallet::<T>::deposit_event(Event::<T>::ContractArchived(contract));
+			Ok(().into())
+		}
+
+		/// Restore an archived contract from its code and storage, the caller
+		/// pays the full storage deposit.
+		#[pallet::weight(<T as Config>::WeightInfo::restore_contract(storage.len() as u32))]
+		#[transactional]
+		pub fn restore_contract(
+			origin: OriginFor<T>,
+			contract: EvmAddress,
+			code: Vec<u8>,
+			storage: Vec<(H256, H256)>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			Self::do_restore(&who, &contract, code, storage)?;
+
+			Pallet::<T>::deposit_event(Event::<T>::ContractRestored(who, contract));
+			Ok(().into())
+		}
+
+		/// Schedule the hard fork to activate at the given block, or immediately
+		/// if the block has been reached already. Only later hard forks than
+		/// the active one are accepted.
+		#[pallet::weight(<T as Config>::WeightInfo::set_hard_fork())]
+		#[transactional]
+		pub fn set_hard_fork(
+			origin: OriginFor<T>,
+			hard_fork: HardFork,
+			at: T::BlockNumber,
+		) -> DispatchResultWithPostInfo {
+			T::HardForkOrigin::ensure_origin(origin)?;
+			ensure!(hard_fork > Self::hard_fork(), Error::<T>::HardForkDowngrade);
+
+			if at <= frame_system::Pallet::<T>::block_number() {
+				Self::activate_hard_fork(hard_fork);
+			} else {
+				ScheduledHardFork::<T>::put((at, hard_fork));
+				Pallet::<T>::deposit_event(Event::<T>::HardForkScheduled(hard_fork, at));
+			}
+
+			Ok(().into())
+		}
+
+		/// Issue an EVM call operation with the addresses and storage keys of
+		/// the access list pre-warmed (EIP-2930).
+		#[pallet::weight(T::GasToWeight::convert(*gas_limit))]
+		#[transactional]
+		pub fn call_with_access_list(
+			origin: OriginFor<T>,
+			target: EvmAddress,
+			input: Vec<u8>,
+			value: BalanceOf<T>,
+			gas_limit: u64,
+			storage_limit: u32,
+			access_list: Vec<AccessListItem>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::do_call(&who, target, input, value, gas_limit, storage_limit, access_list)
+		}
+
+		/// Issue an EVM create operation with the addresses and storage keys
+		/// of the access list pre-warmed (EIP-2930).
+		#[pallet::weight(T::GasToWeight::convert(*gas_limit))]
+		#[transactional]
+		pub fn create_with_access_list(
+			origin: OriginFor<T>,
+			init: Vec<u8>,
+			value: BalanceOf<T>,
+			gas_limit: u64,
+			storage_limit: u32,
+			access_list: Vec<AccessListItem>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::do_create(&who, init, None, value, gas_limit, storage_limit, access_list)
+		}
+
+		/// Issue an EVM create2 operation with the addresses and storage keys
+		/// of the access list pre-warmed (EIP-2930).
+		#[pallet::weight(T::GasToWeight::convert(*gas_limit))]
+		#[transactional]
+		pub fn create2_with_access_list(
+			origin: OriginFor<T>,
+			init: Vec<u8>,
+			salt: H256,
+			value: BalanceOf<T>,
+			gas_limit: u64,
+			storage_limit: u32,
+			access_list: Vec<AccessListItem>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::do_create(&who, init, Some(salt), value, gas_limit, storage_limit, access_list)
+		}
+
+		/// Propose new code for a deployed contract. Anyone can enact the
+		/// upgrade once `UpgradeDelay` has passed, `NetworkContractOrigin`
+		/// can approve it earlier.
+		///
+		/// The storage deposit of the code is reserved from the maintainer
+		/// until the upgrade is canceled, or moved into the storage deposit of
+		/// the contract once it is enacted.
+		#[pallet::weight(<T as Config>::WeightInfo::propose_upgrade())]
+		#[transactional]
+		pub fn propose_upgrade(origin: OriginFor<T>, contract: EvmAddress, code: Vec<u8>) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			let maintainer = T::AddressMapping::get_evm_address(&who).ok_or(Error::<T>::AddressNotMapped)?;
+			let contract_info = Self::contract_info(&contract).ok_or(Error::<T>::ContractNotFound)?;
+			ensure!(contract_info.maintainer == maintainer, Error::<T>::NoPermission);
+			ensure!(contract_info.deployed, Error::<T>::ContractNotDeployed);
+			ensure!(
+				!PendingUpgrades::<T>::contains_key(&contract),
+				Error::<T>::UpgradeAlreadyPending
+			);
+			ensure!(
+				code.len() as u32 <= T::MaxCodeSize::get(),
+				Error::<T>::ContractExceedsMaxCodeSize
+			);
+			// EIP-3541
+			ensure!(
+				Self::hard_fork() < HardFork::London || code.first() != Some(&0xEF),
+				Error::<T>::ReservedCodePrefix
+			);
+
+			let deposit = T::StorageDepositPerByte::get().saturating_mul((code.len() as u32).into());
+			T::Currency::reserve(&who, deposit)?;
+
+			let code_hash = Self::retain_code(code);
+			let enact_at = frame_system::Pallet::<T>::block_number().saturating_add(T::UpgradeDelay::get());
+			PendingUpgrades::<T>::insert(
+				&contract,
+				PendingUpgrade {
+					code_hash,
+					enact_at,
+					depositor: who,
+					deposit,
+				},
+			);
+
+			Pallet::<T>::deposit_event(Event::<T>::UpgradeProposed(contract, code_hash, enact_at));
+			Ok(().into())
+		}
+
+		/// Enact the pending upgrade of a contract once its delay has passed.
+		#[pallet::weight(<T as Config>::WeightInfo::enact_upgrade())]
+		#[transactional]
+		pub fn enact_upgrade(origin: OriginFor<T>, contract: EvmAddress) -> DispatchResultWithPostInfo {
+			ensure_signed(origin)?;
+			let upgrade = Self::pending_upgrades(&contract).ok_or(Error::<T>::UpgradeNotFound)?;
+			ensure!(
+				frame_system::Pallet::<T>::block_number() >= upgrade.enact_at,
+				Error::<T>::UpgradeTimelocked
+			);
+
+			PendingUpgrades::<T>::remove(&contract);
+			Self::do_upgrade(&contract, upgrade.code_hash, upgrade.depositor, upgrade.deposit)?;
+
+			Pallet::<T>::deposit_event(Event::<T>::ContractUpgraded(contract, upgrade.code_hash));
+			Ok(().into())
+		}
+
+		/// Enact the pending upgrade of a contract without waiting for its delay.
+		#[pallet::weight(<T as Config>::WeightInfo::approve_upgrade())]
+		#[transactional]
+		pub fn approve_upgrade(origin: OriginFor<T>, contract: EvmAddress) -> DispatchResultWithPostInfo {
+			T::NetworkContractOrigin::ensure_origin(origin)?;
+			let upgrade = PendingUpgrades::<T>::take(&contract).ok_or(Error::<T>::UpgradeNotFound)?;
+			Self::do_upgrade(&contract, upgrade.code_hash, upgrade.depositor, upgrade.deposit)?;
+
+			Pallet::<T>::deposit_event(Event::<T>::ContractUpgraded(contract, upgrade.code_hash));
+			Ok(().into())
+		}
+
+		/// Cancel the pending upgrade of a contract, either by its maintainer
+		/// or `NetworkContractOrigin`.
+		#[pallet::weight(<T as Config>::WeightInfo::cancel_upgrade())]
+		#[transactional]
+		pub fn cancel_upgrade(origin: OriginFor<T>, contract: EvmAddress) -> DispatchResultWithPostInfo {
+			if let Err(origin) = T::NetworkContractOrigin::try_origin(origin) {
+				let who = ensure_signed(origin)?;
+				let maintainer = T::AddressMapping::get_evm_address(&who).ok_or(Error::<T>::AddressNotMapped)?;
+				let contract_info = Self::contract_info(&contract).ok_or(Error::<T>::ContractNotFound)?;
+				ensure!(contract_info.maintainer == maintainer, Error::<T>::NoPermission);
+			}
+
+			let upgrade = PendingUpgrades::<T>::take(&contract).ok_or(Error::<T>::UpgradeNotFound)?;
+			Self::release_code(&upgrade.code_hash);
+			T::Currency::unreserve(&upgrade.depositor, upgrade.deposit);
+
+			Pallet::<T>::deposit_event(Event::<T>::UpgradeCanceled(contract, upgrade.code_hash));
+			Ok(().into())
+		}
+
+		/// Restore a previous code version of an upgraded contract.
+		#[pallet::weight(<T as Config>::WeightInfo::rollback_contract())]
+		#[transactional]
+		pub fn rollback_contract(
+			origin: OriginFor<T>,
+			contract: EvmAddress,
+			code_hash: H256,
+		) -> DispatchResultWithPostInfo {
+			T::NetworkContractOrigin::ensure_origin(origin)?;
+
+			// the history entry holds the code reference and the deposit
+			// handed over to the contract
+			let entry = CodeHistory::<T>::try_mutate(&contract, |history| -> Result<_, DispatchError> {
+				let index = history
+					.iter()
+					.rposition(|entry| entry.code_hash == code_hash)
+					.ok_or(Error::<T>::CodeNotInHistory)?;
+				Ok(history.remove(index))
+			})?;
+			Self::do_upgrade(&contract, code_hash, entry.depositor, entry.deposit)?;
+
+			Pallet::<T>::deposit_event(Event::<T>::ContractRolledBack(contract, code_hash));
+			Ok(().into())
+		}
+
+		/// Set the deployment policy, applied to contracts created from now on.
+		#[pallet::weight(<T as Config>::WeightInfo::set_deployment_policy())]
+		#[transactional]
+		pub fn set_deployment_policy(
+			origin: OriginFor<T>,
+			policy: DeploymentPolicy<T::BlockNumber, BalanceOf<T>>,
+		) -> DispatchResultWithPostInfo {
+			T::DeploymentPolicyOrigin::ensure_origin(origin)?;
+			ensure!(
+				policy.quota == 0 || !policy.quota_period.is_zero(),
+				Error::<T>::InvalidDeploymentPolicy
+			);
+			CurrentDeploymentPolicy::<T>::put(policy);
+			Pallet::<T>::deposit_event(Event::<T>::DeploymentPolicySet);
+			Ok(().into())
+		}
+
+		/// Add the deployer to or remove it from the deployment allow-list.
+		#[pallet::weight(<T as Config>::WeightInfo::set_deployer_allowed())]
+		#[transactional]
+		pub fn set_deployer_allowed(
+			origin: OriginFor<T>,
+			deployer: EvmAddress,
+			allowed: bool,
+		) -> DispatchResultWithPostInfo {
+			T::DeploymentPolicyOrigin::ensure_origin(origin)?;
+			if allowed {
+				AllowedDeployers::<T>::insert(deployer, true);
+				Pallet::<T>::deposit_event(Event::<T>::DeployerAllowed(deployer));
+			} else {
+				AllowedDeployers::<T>::remove(deployer);
+				Pallet::<T>::deposit_event(Event::<T>::DeployerDisallowed(deployer));
+			}
+			Ok(().into())
+		}
+
+		/// Issue an EVM call signed by `from` following EIP-712, with the
+		/// transaction fee and storage deposit paid by the sender, the sponsor.
+		///
+		/// The signature covers the EVM nonce of `from`, which the call
+		/// increments, and the last block `valid_until` the call is valid in.
+		#[pallet::weight(T::GasToWeight::convert(*gas_limit))]
+		#[transactional]
+		pub fn sponsored_call(
+			origin: OriginFor<T>,
+			from: EvmAddress,
+			target: EvmAddress,
+			input: Vec<u8>,
+			value: BalanceOf<T>,
+			gas_limit: u64,
+			storage_limit: u32,
+			valid_until: T::BlockNumber,
+			signature: ecdsa::Signature,
+		) -> DispatchResultWithPostInfo {
+			let sponsor = ensure_signed(origin)?;
+			ensure!(
+				frame_system::Pallet::<T>::block_number() <= valid_until,
+				Error::<T>::MetaCallExpired
+			);
+
+			let meta_call = MetaCall {
+				from,
+				to: target,
+				input: input.clone(),
+				value: U256::from(UniqueSaturatedInto::<u128>::unique_saturated_into(value)),
+				gas_limit,
+				storage_limit,
+				nonce: Self::account_basic(&from).nonce,
+				valid_until: valid_until.unique_saturated_into(),
+			};
+			ensure!(
+				meta_call.verify(T::ChainId::get(), &signature.0),
+				Error::<T>::InvalidSignature
+			);
+
+			let cap = Self::sponsor_caps(&sponsor, &target).unwrap_or_else(Zero::zero);
+			ensure!(
+				Self::sponsored_cost(gas_limit, storage_limit) <= cap,
+				Error::<T>::SponsorCapExceeded
+			);
+
+			// msg.sender and the extrinsic origin stay the signer of the call
+			let previous_origin = ExtrinsicOrigin::<T>::get();
+			ExtrinsicOrigin::<T>::put(T::AddressMapping::get_account_id(&from));
+			StorageDepositPayer::<T>::put(&sponsor);
+			let result = Runner::<T>::call(from, from, target, input, value, gas_limit, storage_limit, T::config());
+			StorageDepositPayer::<T>::kill();
+			ExtrinsicOrigin::<T>::set(previous_origin);
+			let info = result?;
+			let used_gas: u64 = info.used_gas.unique_saturated_into();
+
+			let cost = Self::sponsored_cost(used_gas, info.used_storage.max(0) as u32);
+			SponsorCaps::<T>::insert(&sponsor, &target, cap.saturating_sub(cost));
+
+			if info.exit_reason.is_succeed() {
+				Pallet::<T>::deposit_event(Event::<T>::Executed(
+					from,
+					target,
+					(used_gas, info.used_storage.unique_saturated_into())
+				));
+			} else {
+				Pallet::<T>::deposit_event(Event::<T>::ExecutedFailed(
+					from,
+					target,
+					info.exit_reason,
+					info.output,
+					(info.used_gas.unique_saturated_into(), Zero::zero())
+				));
+			}
+			Pallet::<T>::deposit_event(Event::<T>::CallSponsored(sponsor, from, target, cost));
+
+			Self::process_queued_events()?;
+
+			Ok(PostDispatchInfo {
+				actual_weight: Some(T::GasToWeight::convert(used_gas)),
+				pays_fee: Pays::Yes,
+			})
+		}
+
+		/// Set the amount the sender pays for sponsored calls to the contract,
+		/// zero stops sponsoring them.
+		#[pallet::weight(<T as Config>::WeightInfo::set_sponsor_cap())]
+		#[transactional]
+		pub fn set_sponsor_cap(
+			origin: OriginFor<T>,
+			contract: EvmAddress,
+			cap: BalanceOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let sponsor = ensure_signed(origin)?;
+			if cap.is_zero() {
+				SponsorCaps::<T>::remove(&sponsor, &contract);
+			} else {
+				SponsorCaps::<T>::insert(&sponsor, &contract, cap);
+			}
+			Pallet::<T>::deposit_event(Event::<T>::SponsorCapSet(sponsor, contract, cap));
+			Ok(().into())
+		}
+
+		/// Reserve funds of the maintainer on the contract account, paying the
+		/// fees of `call` extrinsics to the contract.
+		///
+		/// A new tank pays `GAS_TANK_CALLS_PER_PERIOD` calls of each caller
+		/// per `GAS_TANK_PERIOD` blocks, see `set_gas_tank_limit`.
+		#[pallet::weight(<T as Config>::WeightInfo::fund_gas_tank())]
+		#[transactional]
+		pub fn fund_gas_tank(
+			origin: OriginFor<T>,
+			contract: EvmAddress,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::ensure_maintainer(&who, &contract)?;
+
+			let contract_account_id = T::AddressMapping::get_account_id(&contract);
+			T::Currency::transfer(&who, &contract_account_id, amount, ExistenceRequirement::KeepAlive)?;
+			T::Currency::reserve(&contract_account_id, amount)?;
+			GasTanks::<T>::mutate(&contract, |maybe_tank| {
+				let tank = maybe_tank.get_or_insert_with(|| GasTank {
+					balance: Zero::zero(),
+					calls_per_period: GAS_TANK_CALLS_PER_PERIOD,
+					period: GAS_TANK_PERIOD.into(),
+				});
+				tank.balance = tank.balance.saturating_add(amount);
+			});
+
+			Pallet::<T>::deposit_event(Event::<T>::GasTankFunded(who, contract, amount));
+			Ok(().into())
+		}
+
+		/// Return funds of the gas tank of the contract to the maintainer.
+		#[pallet::weight(<T as Config>::WeightInfo::withdraw_gas_tank())]
+		#[transactional]
+		pub fn withdraw_gas_tank(
+			origin: OriginFor<T>,
+			contract: EvmAddress,
+			#[pallet::compact] amount: BalanceOf<T>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::ensure_maintainer(&who, &contract)?;
+
+			GasTanks::<T>::try_mutate(&contract, |maybe_tank| -> DispatchResult {
+				let tank = maybe_tank.as_mut().ok_or(Error::<T>::GasTankNotFound)?;
+				ensure!(tank.balance >= amount, Error::<T>::InsufficientGasTank);
+				tank.balance -= amount;
+				Ok(())
+			})?;
+
+			let contract_account_id = T::AddressMapping::get_account_id(&contract);
+			T::Currency::unreserve(&contract_account_id, amount);
+			T::Currency::transfer(&contract_account_id, &who, amount, ExistenceRequirement::AllowDeath)?;
+
+			Pallet::<T>::deposit_event(Event::<T>::GasTankWithdrawn(who, contract, amount));
+			Ok(().into())
+		}
+
+		/// Limit the calls of each caller the gas tank of the contract pays
+		/// within `period` blocks, zero calls for unlimited.
+		#[pallet::weight(<T as Config>::WeightInfo::set_gas_tank_limit())]
+		#[transactional]
+		pub fn set_gas_tank_limit(
+			origin: OriginFor<T>,
+			contract: EvmAddress,
+			calls_per_period: u32,
+			period: T::BlockNumber,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			Self::ensure_maintainer(&who, &contract)?;
+
+			GasTanks::<T>::try_mutate(&contract, |maybe_tank| -> DispatchResult {
+				let tank = maybe_tank.as_mut().ok_or(Error::<T>::GasTankNotFound)?;
+				tank.calls_per_period = calls_per_period;
+				tank.period = period;
+				Ok(())
+			})?;
+
+			Pallet::<T>::deposit_event(Event::<T>::GasTankLimitSet(contract, calls_per_period, period));
+			Ok(().into())
+		}
+
+		/// Execute a raw Ethereum transaction signed by an Ethereum wallet,
+		/// as sent through `eth_sendRawTransaction`.
+		///
+		/// The extrinsic is unsigned, the sender recovered from the signature
+		/// pays the fee of the gas limit and the storage deposit, up to what
+		/// its balance covers. The fee and the nonce are kept when the
+		/// execution fails.
+		#[pallet::weight(T::GasToWeight::convert(EthereumTransaction::gas_limit_of(transaction)))]
+		pub fn transact(origin: OriginFor<T>, transaction: Vec<u8>) -> DispatchResultWithPostInfo {
+			ensure_none(origin)?;
+			let transaction_hash = crate::transaction::transaction_hash(&transaction);
+			let (transaction, from) = Self::verify_raw_transaction(&transaction)?;
+			ensure!(
+				transaction.nonce == Self::account_basic(&from).nonce,
+				Error::<T>::InvalidNonce
+			);
+
+			let who = T::AddressMapping::get_account_id(&from);
+			let gas_limit: u64 = transaction.gas_limit.unique_saturated_into();
+			let weight = T::GasToWeight::convert(gas_limit);
+			T::ChargeTransactionPayment::reserve_fee(&who, weight)?;
+			let (_, payed) = T::ChargeTransactionPayment::unreserve_and_charge_fee(&who, weight)
+				.map_err(|_| Error::<T>::ChargeFeeFailed)?;
+
+			let value: BalanceOf<T> = UniqueSaturatedInto::<u128>::unique_saturated_into(transaction.value).unique_saturated_into();
+			let storage_limit = Self::raw_transaction_storage_limit(&who, value);
+
+			ExtrinsicOrigin::<T>::put(&who);
+			let result = frame_support::storage::with_transaction(|| {
+				match Self::execute_raw_transaction(from, transaction, value, gas_limit, storage_limit) {
+					Ok(used_gas) => TransactionOutcome::Commit(Ok(used_gas)),
+					Err(err) => TransactionOutcome::Rollback(Err(err)),
+				}
+			});
+			ExtrinsicOrigin::<T>::kill();
+
+			let used_gas = match result {
+				Ok(used_gas) => used_gas,
+				Err(err) => {
+					// the transaction can not be replayed, and pays its whole gas limit
+					Handler::<T>::inc_nonce(from);
+					T::ChargeTransactionPayment::refund_fee(&who, Zero::zero(), payed)
+						.map_err(|_| Error::<T>::ChargeFeeFailed)?;
+					return Err(err.into());
+				}
+			};
+
+			Pallet::<T>::deposit_event(Event::<T>::Transacted(from, transaction_hash));
+
+			T::ChargeTransactionPayment::refund_fee(&who, T::GasToWeight::convert(gas_limit.saturating_sub(used_gas)), payed)
+				.map_err(|_| Error::<T>::ChargeFeeFailed)?;
+
+			Ok(PostDispatchInfo {
+				actual_weight: Some(T::GasToWeight::convert(used_gas)),
+				pays_fee: Pays::No,
+			})
+		}
+
+		/// Manipulate the time or the state of accounts on development
+		/// chains, as the `evm_increaseTime` or `evm_setCode` RPCs do.
+		///
+		/// The extrinsic is unsigned and only valid in dev mode. `salt` keeps
+		/// repeated operations apart in the transaction pool.
+		#[pallet::weight(<T as Config>::WeightInfo::dev_operation())]
+		pub fn dev_operation(origin: OriginFor<T>, operation: DevOperation, _salt: u64) -> DispatchResultWithPostInfo {
+			ensure_none(origin)?;
+			Self::ensure_dev_operation(&operation)?;
+
+			match operation {
+				DevOperation::IncreaseTime(millis) => {
+					TimeOffset::<T>::mutate(|offset| *offset = offset.saturating_add(millis));
+					// the rest of the block already sees the new time
+					AppliedTimeOffset::<T>::mutate(|offset| *offset = offset.saturating_add(millis));
+					pallet_timestamp::Now::<T>::mutate(|moment| {
+						*moment = moment.saturating_add(millis.unique_saturated_into())
+					});
+				}
+				DevOperation::SetNextBlockTimestamp(timestamp) => NextBlockTimestamp::<T>::put(timestamp),
+				DevOperation::IncreaseBlockNumber(blocks) => BlockNumberOffset::<T>::mutate(|offset| {
+					*offset = offset.saturating_add(blocks.into());
+				}),
+				DevOperation::SetState(state_override) => {
+					// the code of the account is replaced, not shared
+					if state_override.code.is_some() {
+						if let Some(contract_info) = Self::contract_info(&state_override.address) {
+							Self::release_code(&contract_info.code_hash);
+						}
+					}
+					Self::apply_state_override(state_override);
+				}
+			}
+
+			Pallet::<T>::deposit_event(Event::<T>::DevOperationApplied);
+			Ok(Pays::No.into())
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			if let Call::dev_operation(operation, salt) = call {
+				if Self::ensure_dev_operation(operation).is_err() {
+					return InvalidTransaction::Call.into();
+				}
+				return ValidTransaction::with_tag_prefix("EvmDevOperation")
+					.and_provides((operation, salt))
+					.propagate(false)
+					.build();
+			}
+
+			let (transaction, from, nonce, fee) = Self::validate_transact_call(call)?;
+			if transaction.nonce < nonce {
+				return InvalidTransaction::Stale.into();
+			}
+
+			// the fee charged for the gas limit, as for signed extrinsics
+			let mut builder = ValidTransaction::with_tag_prefix("EvmTransaction")
+				.priority(fee.unique_saturated_into())
+				.and_provides((from, transaction.nonce))
+				.propagate(true);
+			// transactions ahead of the account nonce wait for the previous ones
+			if transaction.nonce > nonce {
+				builder = builder.and_requires((from, transaction.nonce - 1));
+			}
+			builder.build()
+		}
+
+		fn pre_dispatch(call: &Self::Call) -> Result<(), TransactionValidityError> {
+			if let Call::dev_operation(..) = call {
+				return if Self::dev_mode() {
+					Ok(())
+				} else {
+					Err(InvalidTransaction::Call.into())
+				};
+			}
+
+			let (transaction, _, nonce, _) = Self::validate_transact_call(call)?;
+			if transaction.nonce < nonce {
+				Err(InvalidTransaction::Stale.into())
+			} else if transaction.nonce > nonce {
+				Err(InvalidTransaction::Future.into())
+			} else {
+				Ok(())
+			}
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	fn ensure_maintainer(who: &T::AccountId, contract: &EvmAddress) -> DispatchResult {
+		let maintainer = T::AddressMapping::get_evm_address(who).ok_or(Error::<T>::AddressNotMapped)?;
+		let contract_info = Self::contract_info(contract).ok_or(Error::<T>::ContractNotFound)?;
+		ensure!(contract_info.maintainer == maintainer, Error::<T>::NoPermission);
+		Ok(())
+	}
+
+	/// Execute a call of `who`, with the access list pre-warmed.
+	fn do_call(
+		who: &T::AccountId,
+		target: EvmAddress,
+		input: Vec<u8>,
+		value: BalanceOf<T>,
+		gas_limit: u64,
+		storage_limit: u32,
+		access_list: Vec<AccessListItem>,
+	) -> DispatchResultWithPostInfo {
+		let source = T::AddressMapping::get_or_create_evm_address(who);
+
+		let info = Runner::<T>::call_with_access_list(
+			source,
+			source,
+			target,
+			input,
+			value,
+			gas_limit,
+			storage_limit,
+			access_list,
+			T::config(),
+		)?;
+		let used_gas: u64 = info.used_gas.unique_saturated_into();
+
+		if info.exit_reason.is_succeed() {
+			Pallet::<T>::deposit_event(Event::<T>::Executed(
+				source,
+				target,
+				(used_gas, info.used_storage.unique_saturated_into())
+			));
+		} else {
+			Pallet::<T>::deposit_event(Event::<T>::ExecutedFailed(
+				source,
+				target,
+				info.exit_reason,
+				info.output,
+				(info.used_gas.unique_saturated_into(), Zero::zero())
+			));
+		}
+
+		Self::process_queued_events()?;
+
+		Ok(PostDispatchInfo {
+			actual_weight: Some(T::GasToWeight::convert(used_gas)),
+			pays_fee: Pays::Yes,
+		})
+	}
+
+	/// Execute a create, or create2 if `salt` is given, of `who` with the
+	/// access list pre-warmed.
+	fn do_create(
+		who: &T::AccountId,
+		init: Vec<u8>,
+		salt: Option<H256>,
+		value: BalanceOf<T>,
+		gas_limit: u64,
+		storage_limit: u32,
+		access_list: Vec<AccessListItem>,
+	) -> DispatchResultWithPostInfo {
+		let source = T::AddressMapping::get_or_create_evm_address(who);
+		Self::check_deployment_policy(&source)?;
+
+		let info = Runner::<T>::create_with_access_list(
+			source,
+			init,
+			salt,
+			value,
+			gas_limit,
+			storage_limit,
+			access_list,
+			T::config(),
+		)?;
+
+		if !info.exit_reason.is_succeed() {
+			Pallet::<T>::deposit_event(Event::<T>::CreatedFailed(
+				source,
+				info.address,
+				info.exit_reason,
+				info.output,
+				(info.used_gas.unique_saturated_into(), Zero::zero())
+			));
+		}
+
+		Self::process_queued_events()?;
+
+		let used_gas: u64 = info.used_gas.unique_saturated_into();
+
+		Ok(PostDispatchInfo {
+			actual_weight: Some(T::GasToWeight::convert(used_gas)),
+			pays_fee: Pays::Yes,
+		})
+	}
+
+	/// Free `fee` from the gas tank of the contract for the transaction
+	/// payment, if the tank covers it and the caller is within the rate limit
+	/// of the tank. Returns the contract account paying the fee.
+	fn charge_gas_tank(contract: &EvmAddress, caller: &EvmAddress, fee: BalanceOf<T>) -> Option<T::AccountId> {
+		let tank = Self::gas_tanks(contract)?;
+		let now = frame_system::Pallet::<T>::block_number();
+		let (mut since, mut calls) = Self::gas_tank_usage(contract, caller);
+		if tank.calls_per_period > 0 {
+			if now >= since.saturating_add(tank.period) {
+				since = now;
+				calls = 0;
+			}
+			if calls >= tank.calls_per_period {
+				return None;
+			}
+		}
+
+		let contract_account_id = T::AddressMapping::get_account_id(contract);
+		if fee > tank.balance || T::Currency::reserved_balance(&contract_account_id) < fee {
+			return None;
+		}
+		T::Currency::unreserve(&contract_account_id, fee);
+		GasTanks::<T>::insert(
+			contract,
+			GasTank {
+				balance: tank.balance - fee,
+				..tank
+			},
+		);
+		GasTankUsage::<T>::insert(contract, caller, (since, calls.saturating_add(1)));
+		Pallet::<T>::deposit_event(Event::<T>::GasTankCharged(*contract, *caller, fee));
+		Some(contract_account_id)
+	}
+
+	/// Return the unused part of a fee paid by the gas tank of the contract.
+	fn refund_gas_tank(contract: &EvmAddress, refund: BalanceOf<T>) {
+		let contract_account_id = T::AddressMapping::get_account_id(contract);
+		GasTanks::<T>::mutate(contract, |maybe_tank| {
+			// the tank just keeps less funds if the refund can not be reserved
+			if let Some(tank) = maybe_tank.as_mut() {
+				if T::Currency::reserve(&contract_account_id, refund).is_ok() {
+					tank.balance = tank.balance.saturating_add(refund);
+				}
+			}
+		});
+	}
+
+	/// Decode a raw Ethereum transaction and recover its sender, checking
+	/// it is signed for this chain and pays at least the base fee.
+	///
+	/// Legacy transactions without EIP-155 replay protection are rejected.
+	pub fn verify_raw_transaction(raw: &[u8]) -> Result<(EthereumTransaction, EvmAddress), DispatchError> {
+		let transaction = EthereumTransaction::decode(raw).map_err(|_| Error::<T>::InvalidRawTransaction)?;
+		ensure!(
+			transaction.chain_id == Some(T::ChainId::get()),
+			Error::<T>::InvalidChainId
+		);
+		ensure!(transaction.gas_price >= T::BaseFee::get(), Error::<T>::GasPriceTooLow);
+		let from = transaction.sender().ok_or(Error::<T>::InvalidSignature)?;
+		Ok((transaction, from))
+	}
+
+	/// Check a `transact` call can be included, returning the transaction,
+	/// its sender, the nonce of the sender and the fee of the gas limit.
+	fn validate_transact_call(
+		call: &Call<T>,
+	) -> Result<(EthereumTransaction, EvmAddress, U256, BalanceOf<T>), TransactionValidityError> {
+		let raw = match call {
+			Call::transact(raw) => raw,
+			_ => return Err(InvalidTransaction::Call.into()),
+		};
+		let (transaction, from) = Self::verify_raw_transaction(raw).map_err(|_| InvalidTransaction::BadProof)?;
+
+		// the changes are discarded
+		let fee = frame_support::storage::with_transaction(|| {
+			TransactionOutcome::Rollback(Self::check_raw_transaction(&transaction, &from))
+		})?;
+
+		Ok((transaction, from, Self::account_basic(&from).nonce, fee))
+	}
+
+	/// Reject the raw transactions the execution refuses, which would still
+	/// pay their fee, and check the sender affords the fee. Returns the fee.
+	fn check_raw_transaction(
+		transaction: &EthereumTransaction,
+		from: &EvmAddress,
+	) -> Result<BalanceOf<T>, TransactionValidityError> {
+		if !transaction.access_list.is_empty() && Self::hard_fork() < HardFork::Berlin {
+			return Err(InvalidTransaction::Call.into());
+		}
+		match transaction.action {
+			TransactionAction::Call(target) => {
+				if Handler::<T>::is_undeployed_contract(&target) && !Handler::<T>::has_permission_to_call(from) {
+					return Err(InvalidTransaction::Call.into());
+				}
+			}
+			TransactionAction::Create => Self::check_deployment_policy(from).map_err(|_| InvalidTransaction::Call)?,
+		}
+
+		let who = T::AddressMapping::get_account_id(from);
+		let weight = T::GasToWeight::convert(transaction.gas_limit.unique_saturated_into());
+		T::ChargeTransactionPayment::reserve_fee(&who, weight).map_err(|_| InvalidTransaction::Payment.into())
+	}
+
+	/// Execute a verified raw transaction, returning the used gas.
+	fn execute_raw_transaction(
+		from: EvmAddress,
+		transaction: EthereumTransaction,
+		value: BalanceOf<T>,
+		gas_limit: u64,
+		storage_limit: u32,
+	) -> Result<u64, DispatchError> {
+		let used_gas = match transaction.action {
+			TransactionAction::Call(target) => {
+				let info = Runner::<T>::call_with_access_list(
+					from,
+					from,
+					target,
+					transaction.input,
+					value,
+					gas_limit,
+					storage_limit,
+					transaction.access_list,
+					T::config(),
+				)?;
+				let used_gas: u64 = info.used_gas.unique_saturated_into();
+				if info.exit_reason.is_succeed() {
+					Pallet::<T>::deposit_event(Event::<T>::Executed(
+						from,
+						target,
+						(used_gas, info.used_storage.unique_saturated_into())
+					));
+				} else {
+					Pallet::<T>::deposit_event(Event::<T>::ExecutedFailed(
+						from,
+						target,
+						info.exit_reason,
+						info.output,
+						(info.used_gas.unique_saturated_into(), Zero::zero())
+					));
+				}
+				used_gas
+			}
+			TransactionAction::Create => {
+				Self::check_deployment_policy(&from)?;
+				let info = Runner::<T>::create_with_access_list(
+					from,
+					transaction.input,
+					None,
+					value,
+					gas_limit,
+					storage_limit,
+					transaction.access_list,
+					T::config(),
+				)?;
+				if !info.exit_reason.is_succeed() {
+					Pallet::<T>::deposit_event(Event::<T>::CreatedFailed(
+						from,
+						info.address,
+						info.exit_reason,
+						info.output,
+						(info.used_gas.unique_saturated_into(), Zero::zero())
+					));
+				}
+				info.used_gas.unique_saturated_into()
+			}
+		};
+
+		Self::process_queued_events()?;
+		Ok(used_gas)
+	}
+
+	/// Storage limit of raw Ethereum transactions, which do not carry one:
+	/// the storage the sender can pay the deposit of, up to the size of a
+	/// new contract.
+	fn raw_transaction_storage_limit(who: &T::AccountId, value: BalanceOf<T>) -> u32 {
+		let affordable = T::Currency::free_balance(who).saturating_sub(value) / T::StorageDepositPerByte::get().max(One::one());
+		let max_limit = T::MaxCodeSize::get().saturating_add(T::NewContractExtraBytes::get());
+		affordable.min(max_limit.into()).unique_saturated_into()
+	}
+
+	/// Fee charged for the weight of the gas plus the storage deposit of a
+	/// sponsored call.
+	fn sponsored_cost(gas: u64, storage: u32) -> BalanceOf<T> {
+		let gas_fee = T::ChargeTransactionPayment::adjusted_weight_fee(T::GasToWeight::convert(gas));
+		let storage_deposit = T::StorageDepositPerByte::get().saturating_mul(storage.into());
+		gas_fee.saturating_add(storage_deposit)
+	}
+
+	fn activate_hard_fork(hard_fork: HardFork) {
+		ScheduledHardFork::<T>::kill();
+		CurrentHardFork::<T>::put(hard_fork);
+		Pallet::<T>::deposit_event(Event::<T>::HardForkActivated(hard_fork));
+	}
+
+	/// Check the deployment policy allows `deployer` to create a contract,
+	/// counting the contract against its quota.
+	pub(crate) fn check_deployment_policy(deployer: &EvmAddress) -> DispatchResult {
+		let policy = Self::deployment_policy();
+		if policy.allow_list_enabled {
+			ensure!(Self::is_allowed_deployer(deployer), Error::<T>::DeployerNotAllowed);
+		}
+
+		if policy.quota > 0 {
+			let now = frame_system::Pallet::<T>::block_number();
+			DeploymentQuotas::<T>::try_mutate(deployer, |(since, count)| -> DispatchResult {
+				if now >= since.saturating_add(policy.quota_period) {
+					*since = now;
+					*count = 0;
+				}
+				ensure!(*count < policy.quota, Error::<T>::DeploymentQuotaExceeded);
+				*count += 1;
+				Ok(())
+			})?;
+		}
+
+		Ok(())
+	}
+
+	/// Fee for deploying the contract, `DeploymentFee` plus the per byte fee
+	/// of its code.
+	pub fn deployment_fee(contract: &EvmAddress) -> BalanceOf<T> {
+		let code_size = Self::contract_info(contract)
+			.and_then(|contract_info| Self::code_infos(&contract_info.code_hash))
+			.map_or(0, |code_info| code_info.code_size);
+		T::DeploymentFee::get().saturating_add(
+			Self::deployment_policy()
+				.fee_per_byte
+				.saturating_mul(code_size.into()),
+		)
+	}
+
+	/// Add the gas used by an EVM execution to the gas used by the block.
+	pub(crate) fn note_used_gas(used_gas: U256) {
+		let used_gas: u64 = used_gas.unique_saturated_into();
+		BlockGasUsed::<T>::mutate(|block_gas_used| *block_gas_used = block_gas_used.saturating_add(used_gas));
+	}
+
+	/// Process queued events.
+	/// The event queue must be purged at the end of each extrinsic call.
+	pub fn process_queued_events() -> DispatchResult {
+		for event in Self::queued_events() {
+			Pallet::<T>::deposit_event(event);
+		}
+		QueuedEvents::<T>::set(Vec::new());
+		Ok(())
+	}
+
+	/// Contract info of the given address, if it is a contract.
+	pub fn contract_info(address: &EvmAddress) -> Option<ContractInfo> {
+		Self::accounts(address).and_then(|account_info| account_info.contract_info)
+	}
+
+	/// Deposit required to cover the storage of the contract.
+	pub fn required_storage_deposit(contract: &EvmAddress) -> BalanceOf<T> {
+		T::StorageDepositPerByte::get().saturating_mul(Self::contract_storage_size(contract).into())
+	}
+
+	/// Whether the storage deposit of the contract does not cover its storage.
+	/// System contracts do not pay a storage deposit.
+	pub fn is_under_collateralised(contract: &EvmAddress) -> bool {
+		if contract.as_bytes().starts_with(&primitives::SYSTEM_CONTRACT_ADDRESS_PREFIX) {
+			return false;
+		}
+		Self::storage_deposit(contract) < Self::required_storage_deposit(contract)
+	}
+
+	/// Storage bytes paid for a contract of `code_size` bytes with `slots`
+	/// storage entries.
+	pub(crate) fn storage_size_of(code_size: u32, slots: u32) -> u32 {
+		code_size
+			.saturating_add(T::NewContractExtraBytes::get())
+			.saturating_add(slots.saturating_mul(STORAGE_SIZE))
+	}
+
+	/// Backfill the storage sizes of the contracts created before they were
+	/// tracked, and the storage deposits reserved on their accounts, visiting
+	/// at most `items` accounts and storage slots from `cursor`.
+	pub(crate) fn backfill_storage_deposits(mut cursor: StorageBackfillCursor, items: u32) -> Weight {
+		let (mut reads, mut writes) = (1u64, 1u64);
+		let mut visited = 0u32;
+
+		while visited < items {
+			cursor = match cursor {
+				StorageBackfillCursor::NextAccount(last) => {
+					let address = match Self::next_account(last.as_ref()) {
+						Some(address) => address,
+						None => {
+							StorageBackfill::<T>::kill();
+							return T::DbWeight::get().reads_writes(reads, writes);
+						}
+					};
+					visited += 1;
+					reads += 1;
+					if Self::contract_info(&address).is_some() {
+						StorageBackfillCursor::CountSlots {
+							contract: address,
+							next: None,
+							slots: 0,
+						}
+					} else {
+						StorageBackfillCursor::NextAccount(Some(address))
+					}
+				}
+				StorageBackfillCursor::CountSlots { contract, next, slots } => {
+					let (counted, next) = Self::count_storage_slots(&contract, next, items - visited);
+					visited = visited.saturating_add(counted);
+					reads += u64::from(counted);
+					let slots = slots.saturating_add(counted);
+					if next.is_some() {
+						StorageBackfillCursor::CountSlots { contract, next, slots }
+					} else {
+						Self::backfill_contract(&contract, slots);
+						reads += 4;
+						writes += 2;
+						StorageBackfillCursor::NextAccount(Some(contract))
+					}
+				}
+			};
+		}
+
+		StorageBackfill::<T>::put(cursor);
+		T::DbWeight::get().reads_writes(reads, writes)
+	}
+
+	/// Set the storage size of a contract with `slots` storage entries, and
+	/// its storage deposit to the balance reserved on its account.
+	fn backfill_contract(contract: &EvmAddress, slots: u32) {
+		// archived while its slots were counted
+		let contract_info = match Self::contract_info(contract) {
+			Some(contract_info) => contract_info,
+			None => return,
+		};
+		let code_size = Self::code_infos(&contract_info.code_hash).map_or(0, |code_info| code_info.code_size);
+		ContractStorageSizes::<T>::insert(contract, Self::storage_size_of(code_size, slots));
+
+		let contract_account_id = T::AddressMapping::get_account_id(contract);
+		let gas_tank = Self::gas_tanks(contract).map_or_else(Zero::zero, |tank| tank.balance);
+		StorageDeposits::<T>::insert(
+			contract,
+			T::Currency::reserved_balance(&contract_account_id).saturating_sub(gas_tank),
+		);
+	}
+
+	/// Account of `Accounts` after `last`, the first one if `None`.
+	fn next_account(last: Option<&EvmAddress>) -> Option<EvmAddress> {
+		use frame_support::storage::StoragePrefixedMap;
+
+		let prefix = Accounts::<T>::final_prefix().to_vec();
+		let key = match last {
+			Some(address) => Accounts::<T>::hashed_key_for(address),
+			None => prefix.clone(),
+		};
+		sp_io::storage::next_key(&key)
+			.filter(|key| key.starts_with(&prefix))
+			// the Twox64Concat hashed key ends with the raw address
+			.map(|key| EvmAddress::from_slice(&key[key.len() - 20..]))
+	}
+
+	/// Raw key prefix of the storage of a contract, and the raw key of its
+	/// first storage slot from the `start` key.
+	fn first_storage_key(address: &EvmAddress, start: Option<H256>) -> (Vec<u8>, Option<Vec<u8>>) {
+		use frame_support::{storage::StoragePrefixedMap, StorageHasher};
+
+		let mut prefix = AccountStorages::<T>::final_prefix().to_vec();
+		prefix.extend(Twox64Concat::hash(&address.encode()));
+
+		let first = match start {
+			Some(start) => {
+				let key = AccountStorages::<T>::hashed_key_for(address, start);
+				if sp_io::storage::exists(&key) {
+					Some(key)
+				} else {
+					sp_io::storage::next_key(&key)
+				}
+			}
+			None => sp_io::storage::next_key(&prefix),
+		};
+		(prefix, first)
+	}
+
+	/// Count at most `limit` storage slots of a contract from the `start`
+	/// key, with the key to continue from if slots are left.
+	fn count_storage_slots(address: &EvmAddress, start: Option<H256>, limit: u32) -> (u32, Option<H256>) {
+		let (prefix, mut next) = Self::first_storage_key(address, start);
+
+		let mut counted = 0u32;
+		while let Some(key) = next.filter(|key| key.starts_with(&prefix)) {
+			if counted >= limit {
+				// the Blake2_128Concat hashed key ends with the raw storage index
+				return (counted, Some(H256::from_slice(&key[key.len() - 32..])));
+			}
+			counted += 1;
+			next = sp_io::storage::next_key(&key);
+		}
+		(counted, None)
+	}
+
+	/// Page through the storage of a contract, at most `limit` slots starting
+	/// from the `start` key. Slots are ordered by their hashed storage key.
+	///
+	/// A `limit` of zero is raised to one, every page must make progress.
+	pub fn storage_range(address: &EvmAddress, start: Option<H256>, limit: u32) -> StorageRange {
+		let limit = limit.max(1);
+		let (prefix, mut next) = Self::first_storage_key(address, start);
+
+		let mut storage = Vec::new();
+		let mut next_key = None;
+		while let Some(key) = next.filter(|key| key.starts_with(&prefix)) {
+			// the Blake2_128Concat hashed key ends with the raw storage index
+			let index = H256::from_slice(&key[key.len() - 32..]);
+			if storage.len() as u32 >= limit {
+				next_key = Some(index);
+				break;
+			}
+			storage.push((index, AccountStorages::<T>::get(address, index)));
+			next = sp_io::storage::next_key(&key);
+		}
+
+		let code_hash = Self::contract_info(address).map(|contract_info| contract_info.code_hash);
+		StorageRange {
+			code_hash,
+			code_info: code_hash.and_then(|code_hash| Self::code_infos(code_hash)),
+			storage_size: Self::contract_storage_size(address),
+			storage,
+			next_key,
+		}
+	}
+
+	/// Hash of the storage entries, independent of their order.
+	pub fn storage_root(mut storage: Vec<(H256, H256)>) -> H256 {
+		storage.sort();
+		H256::from_slice(Keccak256::digest(&storage.encode()).as_slice())
+	}
+
+	fn do_archive(contract: &EvmAddress) -> DispatchResult {
+		let contract_info = Accounts::<T>::try_mutate(contract, |maybe_account_info| -> Result<ContractInfo, DispatchError> {
+			maybe_account_info
+				.as_mut()
+				.and_then(|account_info| account_info.contract_info.take())
+				.ok_or_else(|| Error::<T>::ContractNotFound.into())
+		})?;
+
+		let storage = AccountStorages::<T>::iter_prefix(contract).collect::<Vec<_>>();
+		AccountStorages::<T>::remove_prefix(contract, None);
+
+		CodeInfos::<T>::mutate_exists(&contract_info.code_hash, |maybe_code_info| {
+			if let Some(code_info) = maybe_code_info.as_mut() {
+				code_info.ref_count = code_info.ref_count.saturating_sub(1);
+				if code_info.ref_count == 0 {
+					Codes::<T>::remove(&contract_info.code_hash);
+					*maybe_code_info = None;
+				}
+			}
+		});
+
+		let contract_account_id = T::AddressMapping::get_account_id(contract);
+		T::Currency::unreserve(&contract_account_id, StorageDeposits::<T>::take(contract));
+		// the tank funds go back to the maintainer, who prepaid them
+		if let Some(tank) = GasTanks::<T>::take(contract) {
+			T::Currency::unreserve(&contract_account_id, tank.balance);
+			T::Currency::transfer(
+				&contract_account_id,
+				&T::AddressMapping::get_account_id(&contract_info.maintainer),
+				tank.balance,
+				ExistenceRequirement::AllowDeath,
+			)?;
+		}
+		GasTankUsage::<T>::remove_prefix(contract, None);
+
+		UnderCollateralisedSince::<T>::remove(contract);
+		ArchivedContracts::<T>::insert(
+			contract,
+			ArchivedContractInfo {
+				contract_info,
+				storage_root: Self::storage_root(storage),
+				storage_size: ContractStorageSizes::<T>::take(contract),
+			},
+		);
+
+		Ok(())
+	}
+
+	fn do_restore(who: &T::AccountId, contract: &EvmAddress, code: Vec<u8>, storage: Vec<(H256, H256)>) -> DispatchResult {
+		let archived = Self::archived_contracts(contract).ok_or(Error::<T>::ContractNotArchived)?;
+		ensure!(Self::contract_info(contract).is_none(), Error::<T>::ConflictContractAddress);
+		ensure!(
+			code_hash(&code) == archived.contract_info.code_hash,
+			Error::<T>::ArchivedCodeMismatch
+		);
+		ensure!(
+			Self::storage_root(storage.clone()) == archived.storage_root,
+			Error::<T>::ArchivedStorageMismatch
+		);
+
+		CodeInfos::<T>::mutate_exists(&archived.contract_info.code_hash, |maybe_code_info| {
+			if let Some(code_info) = maybe_code_info.as_mut() {
+				code_info.ref_count = code_info.ref_count.saturating_add(1);
+			} else {
+				*maybe_code_info = Some(CodeInfo {
+					code_size: code.len() as u32,
+					ref_count: 1,
+				});
+				Codes::<T>::insert(&archived.contract_info.code_hash, code);
+			}
+		});
+
+		for (index, value) in storage {
+			AccountStorages::<T>::insert(contract, index, value);
+		}
+
+		Accounts::<T>::mutate(contract, |maybe_account_info| {
+			if let Some(account_info) = maybe_account_info.as_mut() {
+				account_info.contract_info = Some(archived.contract_info.clone());
+			} else {
+				*maybe_account_info = Some(EvmAccountInfo::<T>::new(
+					Default::default(),
+					Some(archived.contract_info.clone()),
+				));
+			}
+		});
+		ContractStorageSizes::<T>::insert(contract, archived.storage_size);
+		ArchivedContracts::<T>::remove(contract);
+
+		let contract_account_id = T::AddressMapping::get_account_id(contract);
+		let deposit = Self::required_storage_deposit(contract);
+		T::Currency::transfer(who, &contract_account_id, deposit, ExistenceRequirement::AllowDeath)?;
+		T::Currency::reserve(&contract_account_id, deposit)?;
+		StorageDeposits::<T>::insert(contract, deposit);
+
+		Ok(())
+	}
+
+	/// Remove an account.
+	pub fn remove_account(address: &EvmAddress) -> Result<u32, ExitError> {
+		let mut size = 0u32;
+
+		// Deref code, and remove it if ref count is zero.
+		if let Some(EvmAccountInfo {
+			contract_info: Some(contract_info),
+			..
+		}) = Self::accounts(address)
+		{
+			CodeInfos::<T>::mutate_exists(&contract_info.code_hash, |maybe_code_info| {
+				if let Some(code_info) = maybe_code_info.as_mut() {
+					size = code_info.code_size;
+					code_info.ref_count = code_info.ref_count.saturating_sub(1);
+					if code_info.ref_count == 0 {
+						Codes::<T>::remove(&contract_info.code_hash);
+						*maybe_code_info = None;
+					}
+				}
+			});
+		}
+
+		for entry in CodeHistory::<T>::take(address) {
+			Self::release_code(&entry.code_hash);
+			T::Currency::unreserve(&entry.depositor, entry.deposit);
+		}
+		if let Some(upgrade) = PendingUpgrades::<T>::take(address) {
+			Self::release_code(&upgrade.code_hash);
+			T::Currency::unreserve(&upgrade.depositor, upgrade.deposit);
+		}
+
+		Accounts::<T>::remove(address);
 		AccountStorages::<T>::remove_prefix(address, None);
+		ContractStorageSizes::<T>::remove(address);
+		StorageDeposits::<T>::remove(address);
+		UnderCollateralisedSince::<T>::remove(address);
+		GasTanks::<T>::remove(address);
+		GasTankUsage::<T>::remove_prefix(address, None);
 
 		Ok(size)
 	}
 
+	/// Check that a dev operation is accepted: dev mode is enabled and the
+	/// timestamp of the next block is after the current time of the chain.
+	fn ensure_dev_operation(operation: &DevOperation) -> Result<(), Error<T>> {
+		ensure!(Self::dev_mode(), Error::<T>::DevModeDisabled);
+		if let DevOperation::SetNextBlockTimestamp(timestamp) = operation {
+			ensure!(*timestamp > Self::dev_now(), Error::<T>::InvalidTimestamp);
+		}
+		Ok(())
+	}
+
+	/// Time of the chain in dev mode: the timestamp without the applied
+	/// offset, moved forward by the time offset.
+	fn dev_now() -> u64 {
+		let now: u64 = pallet_timestamp::Now::<T>::get().unique_saturated_into();
+		now.saturating_sub(AppliedTimeOffset::<T>::get())
+			.saturating_add(Self::time_offset())
+	}
+
+	/// Override the state of an account, to simulate calls against it inside
+	/// a storage transaction which is rolled back, or to set it on development
+	/// chains.
+	///
+	/// A new code is retained for the account, releasing the replaced code is
+	/// up to the caller.
+	pub fn apply_state_override(state_override: StateOverride) {
+		let StateOverride {
+			address,
+			balance,
+			nonce,
+			code,
+			state,
+			state_diff,
+		} = state_override;
+
+		if let Some(balance) = balance {
+			let account_id = T::AddressMapping::get_account_id(&address);
+			T::Currency::make_free_balance_be(
+				&account_id,
+				UniqueSaturatedInto::<BalanceOf<T>>::unique_saturated_into(balance),
+			);
+		}
+
+		if let Some(nonce) = nonce {
+			Accounts::<T>::mutate(&address, |maybe_account_info| {
+				let nonce = UniqueSaturatedInto::<T::Index>::unique_saturated_into(nonce);
+				if let Some(account_info) = maybe_account_info.as_mut() {
+					account_info.nonce = nonce;
+				} else {
+					*maybe_account_info = Some(EvmAccountInfo::<T>::new(nonce, None));
+				}
+			});
+		}
+
+		if let Some(code) = code {
+			let code_hash = Self::retain_code(code);
+
+			Accounts::<T>::mutate(&address, |maybe_account_info| {
+				let maintainer = maybe_account_info
+					.as_ref()
+					.and_then(|account_info| account_info.contract_info.as_ref())
+					.map_or(address, |contract_info| contract_info.maintainer);
+				let contract_info = ContractInfo {
+					code_hash,
+					maintainer,
+					deployed: true,
+				};
+				if let Some(account_info) = maybe_account_info.as_mut() {
+					account_info.contract_info = Some(contract_info);
+				} else {
+					*maybe_account_info = Some(EvmAccountInfo::<T>::new(Default::default(), Some(contract_info)));
+				}
+			});
+		}
+
+		if let Some(state) = state {
+			AccountStorages::<T>::remove_prefix(&address, None);
+			for (index, value) in state {
+				if !value.is_zero() {
+					AccountStorages::<T>::insert(&address, index, value);
+				}
+			}
+		}
+
+		for (index, value) in state_diff {
+			if value.is_zero() {
+				AccountStorages::<T>::remove(&address, index);
+			} else {
+				AccountStorages::<T>::insert(&address, index, value);
+			}
+		}
+	}
+
 	/// Get the account basic in EVM format.
 	pub fn account_basic(address: &EvmAddress) -> Account {
 		let account_id = T::AddressMapping::get_account_id(address);
@@ -813,6 +2649,12 @@ impl<T: Config> Pallet<T> {
 		if code_size > T::MaxCodeSize::get() {
 			return Err(ExitError::OutOfGas);
 		}
+		// EIP-3541
+		if Self::hard_fork() >= HardFork::London && code.first() == Some(&0xEF) {
+			return Err(ExitError::Other(
+				Into::<&str>::into(Error::<T>::ReservedCodePrefix).into(),
+			));
+		}
 		CodeInfos::<T>::mutate_exists(&code_hash, |maybe_code_info| {
 			if let Some(code_info) = maybe_code_info.as_mut() {
 				code_info.ref_count = code_info.ref_count.saturating_add(1);
@@ -865,6 +2707,90 @@ impl<T: Config> Pallet<T> {
 		Ok(())
 	}
 
+	/// Store the code, or add a reference to it if it is stored already.
+	fn retain_code(code: Vec<u8>) -> H256 {
+		let code_hash = code_hash(code.as_slice());
+		CodeInfos::<T>::mutate_exists(&code_hash, |maybe_code_info| {
+			if let Some(code_info) = maybe_code_info.as_mut() {
+				code_info.ref_count = code_info.ref_count.saturating_add(1);
+			} else {
+				*maybe_code_info = Some(CodeInfo {
+					code_size: code.len() as u32,
+					ref_count: 1,
+				});
+				Codes::<T>::insert(&code_hash, code);
+			}
+		});
+		code_hash
+	}
+
+	/// Drop a reference to the code, removing it once unreferenced.
+	fn release_code(code_hash: &H256) {
+		CodeInfos::<T>::mutate_exists(code_hash, |maybe_code_info| {
+			if let Some(code_info) = maybe_code_info.as_mut() {
+				code_info.ref_count = code_info.ref_count.saturating_sub(1);
+				if code_info.ref_count == 0 {
+					Codes::<T>::remove(code_hash);
+					*maybe_code_info = None;
+				}
+			}
+		});
+	}
+
+	/// Replace the code of a contract with the already retained `code_hash`,
+	/// keeping the replaced code in its history.
+	///
+	/// The storage size is updated to the new code size. The `deposit` of the
+	/// new code moves from `depositor` into the storage deposit of the
+	/// contract, and the part of it covering the replaced code stays reserved
+	/// with that code in the history.
+	fn do_upgrade(
+		contract: &EvmAddress,
+		code_hash: H256,
+		depositor: T::AccountId,
+		deposit: BalanceOf<T>,
+	) -> DispatchResult {
+		let old_code_hash = Accounts::<T>::try_mutate(contract, |maybe_account_info| -> Result<H256, DispatchError> {
+			let contract_info = maybe_account_info
+				.as_mut()
+				.and_then(|account_info| account_info.contract_info.as_mut())
+				.ok_or(Error::<T>::ContractNotFound)?;
+			Ok(sp_std::mem::replace(&mut contract_info.code_hash, code_hash))
+		})?;
+
+		let code_size = |code_hash: &H256| Self::code_infos(code_hash).map_or(0, |code_info| code_info.code_size);
+		let (old_size, new_size) = (code_size(&old_code_hash), code_size(&code_hash));
+		ContractStorageSizes::<T>::mutate(contract, |size| {
+			*size = size.saturating_sub(old_size).saturating_add(new_size);
+		});
+
+		let contract_account_id = T::AddressMapping::get_account_id(contract);
+		T::Currency::unreserve(&depositor, deposit);
+		T::Currency::transfer(&depositor, &contract_account_id, deposit, ExistenceRequirement::AllowDeath)?;
+		T::Currency::reserve(&contract_account_id, deposit)?;
+		let old_deposit = StorageDeposits::<T>::mutate(contract, |storage_deposit| {
+			let old_deposit =
+				(*storage_deposit).min(T::StorageDepositPerByte::get().saturating_mul(old_size.into()));
+			*storage_deposit = storage_deposit.saturating_sub(old_deposit).saturating_add(deposit);
+			old_deposit
+		});
+
+		CodeHistory::<T>::mutate(contract, |history| {
+			history.push(HistoricCode {
+				code_hash: old_code_hash,
+				depositor: contract_account_id,
+				deposit: old_deposit,
+			});
+			while history.len() > T::MaxCodeHistory::get() as usize {
+				let entry = history.remove(0);
+				Self::release_code(&entry.code_hash);
+				T::Currency::unreserve(&entry.depositor, entry.deposit);
+			}
+		});
+
+		Ok(())
+	}
+
 	/// Mark contract as deployed
 	///
 	/// If maintainer is provider then it will check maintainer
@@ -952,6 +2878,11 @@ impl<T: Config> Pallet<T> {
 			let contract_info = account_info.contract_info.take().ok_or(Error::<T>::ContractNotFound)?;
 
 			AccountStorages::<T>::remove_prefix(contract, None);
+			ContractStorageSizes::<T>::remove(contract);
+			StorageDeposits::<T>::remove(contract);
+			UnderCollateralisedSince::<T>::remove(contract);
+			GasTanks::<T>::remove(contract);
+			GasTankUsage::<T>::remove_prefix(contract, None);
 
 			CodeInfos::<T>::mutate_exists(&contract_info.code_hash, |maybe_code_info| {
 				if let Some(code_info) = maybe_code_info.as_mut() {
@@ -983,6 +2914,64 @@ impl<T: Config> Pallet<T> {
 	}
 }
 
+/// The gas tank of the target contract pays the fees of `call` and
+/// `call_with_access_list`, within its rate limit.
+impl<T: Config> TransactionFeeSponsor<T::AccountId, <T as frame_system::Config>::Call, BalanceOf<T>> for Pallet<T>
+where
+	<T as frame_system::Config>::Call: IsSubType<Call<T>>,
+{
+	fn sponsor_fee(who: &T::AccountId, call: &<T as frame_system::Config>::Call, fee: BalanceOf<T>) -> Option<T::AccountId> {
+		let target = match call.is_sub_type()? {
+			Call::call(target, ..) | Call::call_with_access_list(target, ..) => *target,
+			_ => return None,
+		};
+		let caller = T::AddressMapping::get_or_create_evm_address(who);
+		Self::charge_gas_tank(&target, &caller, fee)
+	}
+
+	fn refund_sponsor(sponsor: &T::AccountId, refund: BalanceOf<T>) {
+		if let Some(contract) = T::AddressMapping::get_evm_address(sponsor) {
+			Self::refund_gas_tank(&contract, refund);
+		}
+	}
+}
+
+/// Block number moved forward by the dev mode block number offset, for the
+/// block number based periods of other pallets.
+pub struct DevBlockNumber<T>(PhantomData<T>);
+
+impl<T: Config> BlockNumberProvider for DevBlockNumber<T> {
+	type BlockNumber = T::BlockNumber;
+
+	fn current_block_number() -> T::BlockNumber {
+		frame_system::Pallet::<T>::block_number().saturating_add(Pallet::<T>::block_number_offset())
+	}
+}
+
+impl<T: Config> OnTimestampSet<T::Moment> for Pallet<T> {
+	/// Add the dev mode time offset to the timestamp of the block.
+	fn on_timestamp_set(moment: T::Moment) {
+		if !Self::dev_mode() {
+			return;
+		}
+
+		let now: u64 = moment.unique_saturated_into();
+		let offset = match NextBlockTimestamp::<T>::take() {
+			Some(timestamp) => {
+				// the time of the chain never goes back
+				let offset = timestamp.saturating_sub(now).max(Self::time_offset());
+				TimeOffset::<T>::put(offset);
+				offset
+			}
+			None => Self::time_offset(),
+		};
+		if offset > 0 {
+			pallet_timestamp::Now::<T>::put(moment.saturating_add(offset.unique_saturated_into()));
+			AppliedTimeOffset::<T>::put(offset);
+		}
+	}
+}
+
 impl<T: Config> EVMTrait<T::AccountId> for Pallet<T> {
 	type Balance = BalanceOf<T>;
 	fn execute(
@@ -1026,7 +3015,7 @@ impl<T: Config> EVMTrait<T::AccountId> for Pallet<T> {
 								context.contract,
 								info.exit_reason.clone(),
 								info.output.clone(),
-								(gas_limit, Zero::zero())
+								(info.used_gas.unique_saturated_into(), Zero::zero())
 							));
 							TransactionOutcome::Rollback(Ok(info))
 						}
@@ -0,0 +1,86 @@
+//! EIP-712 typed data of EVM calls signed by a user and dispatched by a
+//! sponsor.
+
+use primitive_types::{H160, H256, U256};
+use sp_io::{crypto::secp256k1_ecdsa_recover, hashing::keccak_256};
+use sp_std::vec::Vec;
+
+/// Name of the EIP-712 signing domain.
+pub const DOMAIN_NAME: &[u8] = b"Reef EVM";
+/// Version of the EIP-712 signing domain.
+pub const DOMAIN_VERSION: &[u8] = b"1";
+
+const DOMAIN_TYPE: &[u8] = b"EIP712Domain(string name,string version,uint256 chainId)";
+const META_CALL_TYPE: &[u8] = b"MetaCall(address from,address to,bytes input,uint256 value,uint256 gasLimit,uint256 storageLimit,uint256 nonce,uint256 validUntil)";
+
+/// EVM call signed by `from`.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct MetaCall {
+	pub from: H160,
+	pub to: H160,
+	pub input: Vec<u8>,
+	pub value: U256,
+	pub gas_limit: u64,
+	pub storage_limit: u32,
+	/// EVM nonce of `from`, protects against replays.
+	pub nonce: U256,
+	/// Last block the call can be dispatched in.
+	pub valid_until: u64,
+}
+
+fn encode_address(buf: &mut Vec<u8>, address: &H160) {
+	buf.extend_from_slice(H256::from(*address).as_bytes());
+}
+
+fn encode_uint(buf: &mut Vec<u8>, value: U256) {
+	let mut word = [0u8; 32];
+	value.to_big_endian(&mut word);
+	buf.extend_from_slice(&word);
+}
+
+/// EIP-712 domain separator of the chain.
+pub fn domain_separator(chain_id: u64) -> [u8; 32] {
+	let mut buf = Vec::with_capacity(4 * 32);
+	buf.extend_from_slice(&keccak_256(DOMAIN_TYPE));
+	buf.extend_from_slice(&keccak_256(DOMAIN_NAME));
+	buf.extend_from_slice(&keccak_256(DOMAIN_VERSION));
+	encode_uint(&mut buf, U256::from(chain_id));
+	keccak_256(&buf)
+}
+
+impl MetaCall {
+	/// EIP-712 struct hash of the call.
+	pub fn struct_hash(&self) -> [u8; 32] {
+		let mut buf = Vec::with_capacity(9 * 32);
+		buf.extend_from_slice(&keccak_256(META_CALL_TYPE));
+		encode_address(&mut buf, &self.from);
+		encode_address(&mut buf, &self.to);
+		buf.extend_from_slice(&keccak_256(&self.input));
+		encode_uint(&mut buf, self.value);
+		encode_uint(&mut buf, U256::from(self.gas_limit));
+		encode_uint(&mut buf, U256::from(self.storage_limit));
+		encode_uint(&mut buf, self.nonce);
+		encode_uint(&mut buf, U256::from(self.valid_until));
+		keccak_256(&buf)
+	}
+
+	/// The digest signed by `from`.
+	pub fn signing_hash(&self, chain_id: u64) -> [u8; 32] {
+		let mut buf = Vec::with_capacity(2 + 2 * 32);
+		buf.extend_from_slice(b"\x19\x01");
+		buf.extend_from_slice(&domain_separator(chain_id));
+		buf.extend_from_slice(&self.struct_hash());
+		keccak_256(&buf)
+	}
+
+	/// Whether `signature` is a signature of the call by `from`.
+	pub fn verify(&self, chain_id: u64, signature: &[u8; 65]) -> bool {
+		recover_signer(signature, &self.signing_hash(chain_id)) == Some(self.from)
+	}
+}
+
+/// Address of the signer of the digest.
+pub fn recover_signer(signature: &[u8; 65], hash: &[u8; 32]) -> Option<H160> {
+	let public = secp256k1_ecdsa_recover(signature, hash).ok()?;
+	Some(H160::from_slice(&keccak_256(&public)[12..]))
+}
@@ -13,7 +13,7 @@ use sp_runtime::{
 	traits::{BlakeTwo256, IdentityLookup},
 	AccountId32,
 };
-use std::{collections::BTreeMap, str::FromStr};
+use std::{cell::RefCell, collections::BTreeMap, str::FromStr};
 
 mod evm_mod {
 	pub use super::super::*;
@@ -71,7 +71,7 @@ parameter_types! {
 }
 impl pallet_timestamp::Config for Test {
 	type Moment = u64;
-	type OnTimestampSet = ();
+	type OnTimestampSet = EVM;
 	type MinimumPeriod = MinimumPeriod;
 	type WeightInfo = ();
 }
@@ -117,6 +117,24 @@ impl Convert<u64, u64> for GasToWeight {
 
 parameter_types! {
 	pub NetworkContractSource: H160 = alice();
+	pub const RentGracePeriod: u64 = 10;
+	pub const UpgradeDelay: u64 = 10;
+	pub const MaxCodeHistory: u32 = 2;
+}
+
+thread_local! {
+	static BASE_FEE: RefCell<U256> = RefCell::new(U256::one());
+}
+
+pub struct BaseFee;
+impl Get<U256> for BaseFee {
+	fn get() -> U256 {
+		BASE_FEE.with(|v| *v.borrow())
+	}
+}
+
+pub fn set_base_fee(base_fee: U256) {
+	BASE_FEE.with(|v| *v.borrow_mut() = base_fee);
 }
 
 ord_parameter_types! {
@@ -143,12 +161,18 @@ impl Config for Test {
 	type ChainId = ChainId;
 	type GasToWeight = GasToWeight;
 	type ChargeTransactionPayment = ();
+	type BaseFee = BaseFee;
 
 	type NetworkContractOrigin = EnsureSignedBy<NetworkContractAccount, AccountId32>;
 	type NetworkContractSource = NetworkContractSource;
 	type DeveloperDeposit = DeveloperDeposit;
 	type DeploymentFee = DeploymentFee;
 	type FreeDeploymentOrigin = EnsureSignedBy<CouncilAccount, AccountId32>;
+	type HardForkOrigin = EnsureSignedBy<CouncilAccount, AccountId32>;
+	type DeploymentPolicyOrigin = EnsureSignedBy<CouncilAccount, AccountId32>;
+	type RentGracePeriod = RentGracePeriod;
+	type UpgradeDelay = UpgradeDelay;
+	type MaxCodeHistory = MaxCodeHistory;
 
 	type WeightInfo = ();
 }
@@ -163,6 +187,7 @@ construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
 		System: frame_system::{Pallet, Call, Storage, Config, Event<T>},
+		Timestamp: pallet_timestamp::{Pallet, Call, Storage, Inherent},
 		EVM: evm_mod::{Pallet, Config<T>, Call, Storage, Event<T>},
 		Tokens: orml_tokens::{Pallet, Storage, Event<T>},
 		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
@@ -207,6 +232,8 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 			code: vec![
 				0x00, // STOP
 			],
+			maintainer: Default::default(),
+			deployed: true,
 		},
 	);
 	accounts.insert(
@@ -218,6 +245,8 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 			code: vec![
 				0xff, // INVALID
 			],
+			maintainer: Default::default(),
+			deployed: true,
 		},
 	);
 
@@ -228,6 +257,8 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 			balance: INITIAL_BALANCE,
 			storage: Default::default(),
 			code: Default::default(),
+			maintainer: Default::default(),
+			deployed: true,
 		},
 	);
 	accounts.insert(
@@ -237,6 +268,8 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 			balance: INITIAL_BALANCE,
 			storage: Default::default(),
 			code: Default::default(),
+			maintainer: Default::default(),
+			deployed: true,
 		},
 	);
 
@@ -245,6 +278,7 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 		.unwrap();
 	evm_mod::GenesisConfig::<Test> {
 		accounts,
+		dev_mode: false,
 	}
 	.assimilate_storage(&mut t)
 	.unwrap();
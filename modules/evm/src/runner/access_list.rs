@@ -0,0 +1,172 @@
+use evm::{ExitError, Opcode, Stack};
+use primitive_types::{H160, H256};
+use primitives::{evm::AccessListItem, PREDEPLOY_ADDRESS_START};
+use sp_std::{
+	cell::RefCell,
+	collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+	prelude::*,
+	rc::Rc,
+};
+
+/// EIP-2929 cold storage read cost.
+pub const COLD_SLOAD_COST: u64 = 2100;
+/// EIP-2929 cold account access cost.
+pub const COLD_ACCOUNT_ACCESS_COST: u64 = 2600;
+/// EIP-2929 warm storage read cost, charged as the base cost of the opcode.
+pub const WARM_STORAGE_READ_COST: u64 = 100;
+/// EIP-2930 cost of an address in the access list of a transaction.
+pub const ACCESS_LIST_ADDRESS_COST: u64 = 2400;
+/// EIP-2930 cost of a storage key in the access list of a transaction.
+pub const ACCESS_LIST_STORAGE_KEY_COST: u64 = 1900;
+
+/// Intrinsic cost of the access list of a transaction.
+pub fn access_list_cost(access_list: &[AccessListItem]) -> u64 {
+	access_list.iter().fold(0u64, |cost, item| {
+		cost.saturating_add(ACCESS_LIST_ADDRESS_COST)
+			.saturating_add(ACCESS_LIST_STORAGE_KEY_COST.saturating_mul(item.storage_keys.len() as u64))
+	})
+}
+
+/// Shared accessed set handle, cloned into every sub call handler.
+pub type AccessedSetRef = Rc<RefCell<AccessedSet>>;
+
+enum Accessed {
+	Address(H160),
+	StorageKey(H160, H256),
+}
+
+/// Addresses and storage keys accessed by a transaction (EIP-2929).
+///
+/// Accesses are journaled so the ones made by a reverted sub call can be
+/// forgotten again.
+#[derive(Default)]
+pub struct AccessedSet {
+	addresses: BTreeSet<H160>,
+	storage_keys: BTreeSet<(H160, H256)>,
+	journal: Vec<Accessed>,
+}
+
+impl AccessedSet {
+	/// New accessed set with the transaction origin and target warm.
+	pub fn new_ref(origin: H160, target: H160) -> AccessedSetRef {
+		let mut accessed = Self::default();
+		accessed.access_address(origin);
+		accessed.access_address(target);
+		accessed.journal.clear();
+		Rc::new(RefCell::new(accessed))
+	}
+
+	/// Mark the address as accessed, returns whether it was cold.
+	pub fn access_address(&mut self, address: H160) -> bool {
+		// precompiles are always warm
+		if address < H160::from_low_u64_be(PREDEPLOY_ADDRESS_START) {
+			return false;
+		}
+
+		let cold = self.addresses.insert(address);
+		if cold {
+			self.journal.push(Accessed::Address(address));
+		}
+		cold
+	}
+
+	/// Mark the storage key as accessed, returns whether it was cold.
+	pub fn access_storage_key(&mut self, address: H160, index: H256) -> bool {
+		let cold = self.storage_keys.insert((address, index));
+		if cold {
+			self.journal.push(Accessed::StorageKey(address, index));
+		}
+		cold
+	}
+
+	/// Mark the addresses and storage keys of the access list as accessed.
+	pub fn access_list_items(&mut self, access_list: &[AccessListItem]) {
+		for item in access_list {
+			self.access_address(item.address);
+			for index in item.storage_keys.iter() {
+				self.access_storage_key(item.address, *index);
+			}
+		}
+	}
+
+	/// The accessed addresses and storage keys as an access list. Addresses in
+	/// `exclude` are left out unless storage keys of theirs were accessed.
+	pub fn access_list(&self, exclude: &[H160]) -> Vec<AccessListItem> {
+		let mut items = BTreeMap::<H160, Vec<H256>>::new();
+		for address in self.addresses.iter().filter(|address| !exclude.contains(address)) {
+			items.entry(*address).or_default();
+		}
+		for (address, index) in self.storage_keys.iter() {
+			items.entry(*address).or_default().push(*index);
+		}
+
+		items
+			.into_iter()
+			.map(|(address, storage_keys)| AccessListItem { address, storage_keys })
+			.collect()
+	}
+
+	pub fn checkpoint(&self) -> usize {
+		self.journal.len()
+	}
+
+	/// Forget the accesses made after the checkpoint.
+	pub fn revert_to(&mut self, checkpoint: usize) {
+		while self.journal.len() > checkpoint {
+			match self.journal.pop() {
+				Some(Accessed::Address(address)) => {
+					self.addresses.remove(&address);
+				}
+				Some(Accessed::StorageKey(address, index)) => {
+					self.storage_keys.remove(&(address, index));
+				}
+				None => break,
+			}
+		}
+	}
+
+	/// Record the state access of the opcode executed by `address`, returning
+	/// the cold access cost on top of the warm cost included in the base cost.
+	pub fn access_cost(&mut self, address: H160, opcode: Opcode, stack: &Stack) -> Result<u64, ExitError> {
+		let cost = match opcode {
+			Opcode::SLOAD => {
+				if self.access_storage_key(address, stack.peek(0)?) {
+					COLD_SLOAD_COST - WARM_STORAGE_READ_COST
+				} else {
+					0
+				}
+			}
+			Opcode::SSTORE => {
+				if self.access_storage_key(address, stack.peek(0)?) {
+					COLD_SLOAD_COST
+				} else {
+					0
+				}
+			}
+			Opcode::BALANCE | Opcode::EXTCODESIZE | Opcode::EXTCODECOPY | Opcode::EXTCODEHASH => {
+				if self.access_address(stack.peek(0)?.into()) {
+					COLD_ACCOUNT_ACCESS_COST - WARM_STORAGE_READ_COST
+				} else {
+					0
+				}
+			}
+			Opcode::CALL | Opcode::CALLCODE | Opcode::DELEGATECALL | Opcode::STATICCALL => {
+				if self.access_address(stack.peek(1)?.into()) {
+					COLD_ACCOUNT_ACCESS_COST - WARM_STORAGE_READ_COST
+				} else {
+					0
+				}
+			}
+			Opcode::SUICIDE => {
+				if self.access_address(stack.peek(0)?.into()) {
+					COLD_ACCOUNT_ACCESS_COST
+				} else {
+					0
+				}
+			}
+			_ => 0,
+		};
+
+		Ok(cost)
+	}
+}
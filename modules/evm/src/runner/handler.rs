@@ -2,11 +2,13 @@
 
 use crate::{
 	precompiles::Precompiles,
+	runner::access_list::{AccessedSet, AccessedSetRef},
 	runner::storage_meter::{StorageMeter, StorageMeterHandler},
-	EvmAccountInfo, AccountStorages, Accounts, AddressMapping, Codes, Config, ContractInfo, Error, Event, Log,
-	Pallet, Vicinity, QueuedEvents, TransferAll
+	runner::tracer::TracerRef,
+	accrue_log_bloom, EvmAccountInfo, AccountStorages, Accounts, AddressMapping, BlockLogsBloom, Codes, Config, ContractInfo,
+	ContractStorageSizes, Error, Event, HardFork, Log, Pallet, StorageDeposits, Vicinity, QueuedEvents, TransferAll
 };
-use evm::{Capture, Context, CreateScheme, ExitError, ExitReason, Opcode, Runtime, Stack, Transfer};
+use evm::{Capture, Context, CreateScheme, ExitError, ExitReason, Machine, Opcode, Runtime, Stack, Transfer};
 use evm_gasometer::{self as gasometer, Gasometer};
 use evm_runtime::{Config as EvmRuntimeConfig, Handler as HandlerT};
 use frame_support::{
@@ -21,17 +23,29 @@ use sp_runtime::{
 };
 use sp_std::{cmp::min, convert::Infallible, marker::PhantomData, prelude::*, rc::Rc};
 
-use primitives::SYSTEM_CONTRACT_ADDRESS_PREFIX;
+use primitives::{
+	evm::{AccessListItem, CallType},
+	SYSTEM_CONTRACT_ADDRESS_PREFIX,
+};
 
 /// Storage key size and storage value size.
 pub const STORAGE_SIZE: u32 = 64;
 
+/// London BASEFEE opcode, unknown to the interpreter.
+const BASEFEE: Opcode = Opcode(0x48);
+/// BASEFEE gas cost.
+const GAS_BASE: u64 = 2;
+
 pub struct Handler<'vicinity, 'config, 'meter, T: Config> {
 	pub vicinity: &'vicinity Vicinity,
 	pub config: &'config EvmRuntimeConfig,
 	pub gasometer: Gasometer<'config>,
 	pub storage_meter: StorageMeter<'meter>,
 	pub is_static: bool,
+	pub hard_fork: HardFork,
+	/// Accessed addresses and storage keys, since Berlin.
+	pub accessed: Option<AccessedSetRef>,
+	pub tracer: Option<TracerRef>,
 	_marker: PhantomData<T>,
 }
 
@@ -47,11 +61,26 @@ impl<'vicinity, 'config, T: Config> Handler<'vicinity, 'config, '_, T> {
 		contract: H160,
 		is_static: bool,
 		config: &'config EvmRuntimeConfig,
+		access_list: &[AccessListItem],
+		accessed: Option<AccessedSetRef>,
+		tracer: Option<TracerRef>,
 		f: F,
 	) -> Result<R, DispatchError> {
+		let hard_fork = Pallet::<T>::hard_fork();
+		let accessed = match accessed {
+			Some(accessed) => Some(accessed),
+			None if hard_fork >= HardFork::Berlin => Some(AccessedSet::new_ref(vicinity.origin, contract)),
+			None => None,
+		};
+		if let Some(accessed) = accessed.as_ref() {
+			accessed.borrow_mut().access_list_items(access_list);
+		}
+
 		frame_support::storage::with_transaction(|| {
 			let mut storage_meter_handler = StorageMeterHandlerImpl::<T> {
 				origin: vicinity.origin,
+				payer: Pallet::<T>::storage_deposit_payer()
+					.unwrap_or_else(|| T::AddressMapping::get_account_id(&vicinity.origin)),
 				_marker: PhantomData,
 			};
 			let storage_meter = match StorageMeter::new(&mut storage_meter_handler, contract, storage_limit) {
@@ -65,6 +94,9 @@ impl<'vicinity, 'config, T: Config> Handler<'vicinity, 'config, '_, T> {
 				is_static,
 				gasometer: Gasometer::new(gas_limit, config),
 				storage_meter,
+				hard_fork,
+				accessed,
+				tracer,
 				_marker: PhantomData,
 			};
 
@@ -91,6 +123,10 @@ impl<'vicinity, 'config, T: Config> Handler<'vicinity, 'config, '_, T> {
 		config: &'config EvmRuntimeConfig,
 		f: F,
 	) -> Result<R, DispatchError> {
+		let tracer = self.tracer.clone();
+		let accessed = self.accessed.clone();
+		let checkpoint = accessed.as_ref().map(|accessed| accessed.borrow().checkpoint());
+		let hard_fork = self.hard_fork;
 		frame_support::storage::with_transaction(|| {
 			let storage_meter = match self.storage_meter.child_meter(contract) {
 				Ok(x) => x,
@@ -103,16 +139,27 @@ impl<'vicinity, 'config, T: Config> Handler<'vicinity, 'config, '_, T> {
 				is_static,
 				gasometer: Gasometer::new(gas_limit, config),
 				storage_meter,
+				hard_fork,
+				accessed: accessed.clone(),
+				tracer,
 				_marker: PhantomData,
 			};
 
-			match f(&mut substate, &mut self.gasometer) {
+			let outcome = match f(&mut substate, &mut self.gasometer) {
 				TransactionOutcome::Commit(r) => match substate.storage_meter.finish() {
 					Ok(_) => TransactionOutcome::Commit(Ok(r)),
 					Err(e) => TransactionOutcome::Rollback(Err(e)),
 				},
 				TransactionOutcome::Rollback(e) => TransactionOutcome::Rollback(Ok(e)),
+			};
+
+			// accesses of a reverted sub call are forgotten
+			if let (TransactionOutcome::Rollback(_), Some(accessed), Some(checkpoint)) = (&outcome, &accessed, checkpoint)
+			{
+				accessed.borrow_mut().revert_to(checkpoint);
 			}
+
+			outcome
 		})
 	}
 
@@ -145,9 +192,36 @@ impl<'vicinity, 'config, T: Config> Handler<'vicinity, 'config, '_, T> {
 
 		let mut runtime = Runtime::new(Rc::new(code), Rc::new(input), context, self.config);
 
-		let reason = match runtime.run(self) {
-			Capture::Exit(s) => s,
-			Capture::Trap(_) => unreachable!("Trap is Infallible"),
+		let reason = match self.tracer.clone() {
+			Some(tracer) => loop {
+				let gas_before = self.gasometer.gas();
+				let step = match (runtime.machine().position(), runtime.machine().inspect()) {
+					(Ok(pc), Some((opcode, stack))) => {
+						tracer
+							.borrow_mut()
+							.step(*pc, opcode, gas_before, stack, runtime.machine().memory())
+					}
+					_ => None,
+				};
+
+				let result = runtime.step(self);
+
+				if let Some(index) = step {
+					tracer
+						.borrow_mut()
+						.step_cost(index, gas_before.saturating_sub(self.gasometer.gas()));
+				}
+
+				match result {
+					Ok(()) => continue,
+					Err(Capture::Exit(s)) => break s,
+					Err(Capture::Trap(_)) => unreachable!("Trap is Infallible"),
+				}
+			},
+			None => match runtime.run(self) {
+				Capture::Exit(s) => s,
+				Capture::Trap(_) => unreachable!("Trap is Infallible"),
+			},
 		};
 
 		match reason {
@@ -379,6 +453,10 @@ impl<'vicinity, 'config, 'meter, T: Config> HandlerT for Handler<'vicinity, 'con
 
 		let mut storage_change = StorageChange::None;
 
+		if let Some(tracer) = self.tracer.as_ref() {
+			tracer.borrow_mut().storage_change(index, value);
+		}
+
 		let default_value = H256::default();
 		let is_prev_value_default = Pallet::<T>::account_storages(address, index) == default_value;
 
@@ -405,7 +483,9 @@ impl<'vicinity, 'config, 'meter, T: Config> HandlerT for Handler<'vicinity, 'con
 	}
 
 	fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError> {
-		Pallet::<T>::deposit_event(Event::<T>::Log(Log { address, topics, data }));
+		let log = Log { address, topics, data };
+		BlockLogsBloom::<T>::mutate(|bloom| accrue_log_bloom(bloom, &log));
+		Pallet::<T>::deposit_event(Event::<T>::Log(log));
 
 		Ok(())
 	}
@@ -452,8 +532,14 @@ impl<'vicinity, 'config, 'meter, T: Config> HandlerT for Handler<'vicinity, 'con
 		}
 		let mut target_gas = target_gas.unwrap_or(after_gas);
 		target_gas = min(target_gas, after_gas);
+		let gas_before = self.gasometer.gas();
 		try_or_fail!(self.gasometer.record_cost(target_gas));
 
+		let call_type = if matches!(scheme, CreateScheme::Create2 { .. }) {
+			CallType::Create2
+		} else {
+			CallType::Create
+		};
 		let maybe_address = Self::create_address(scheme);
 		let address = if let Err(e) = maybe_address {
 			return Capture::Exit((ExitReason::Error(e), None, Vec::new()));
@@ -462,15 +548,31 @@ impl<'vicinity, 'config, 'meter, T: Config> HandlerT for Handler<'vicinity, 'con
 		};
 		Self::inc_nonce(caller);
 
+		if let Some(accessed) = self.accessed.as_ref() {
+			accessed.borrow_mut().access_address(address);
+		}
+
+		if let Some(tracer) = self.tracer.as_ref() {
+			tracer
+				.borrow_mut()
+				.enter(call_type, caller, address, init_code.clone(), value, target_gas);
+		}
+
 		let origin = &self.vicinity.origin;
 
-		self.run_sub_transaction(
+		let result = self.run_sub_transaction(
 			self.vicinity,
 			target_gas,
 			address,
 			self.is_static,
 			self.config,
 			|substate, gasometer| {
+				// contracts created by contracts count against the deployment
+				// policy of the origin, the top level create is checked before
+				// running
+				try_or_rollback!(<Pallet<T>>::check_deployment_policy(origin)
+					.map_err(|e| ExitError::Other(Into::<&'static str>::into(e).into())));
+
 				try_or_rollback!(Self::transfer(Transfer {
 					source: caller,
 					target: address,
@@ -518,7 +620,14 @@ impl<'vicinity, 'config, 'meter, T: Config> HandlerT for Handler<'vicinity, 'con
 				None,
 				Vec::new(),
 			))
-		})
+		});
+
+		if let (Some(tracer), Capture::Exit((reason, _, out))) = (self.tracer.as_ref(), &result) {
+			let gas_used = gas_before.saturating_sub(self.gasometer.gas());
+			tracer.borrow_mut().exit(reason, out, gas_used);
+		}
+
+		result
 	}
 
 	fn call(
@@ -561,7 +670,26 @@ impl<'vicinity, 'config, 'meter, T: Config> HandlerT for Handler<'vicinity, 'con
 
 		let code = self.code(code_address);
 
-		self.run_sub_transaction(
+		let gas_before = self.gasometer.gas();
+		if let Some(tracer) = self.tracer.as_ref() {
+			let call_type = if is_static {
+				CallType::StaticCall
+			} else if code_address != context.address {
+				if transfer.is_some() {
+					CallType::CallCode
+				} else {
+					CallType::DelegateCall
+				}
+			} else {
+				CallType::Call
+			};
+			let value = transfer.as_ref().map_or_else(U256::zero, |transfer| transfer.value);
+			tracer
+				.borrow_mut()
+				.enter(call_type, context.caller, code_address, input.clone(), value, target_gas);
+		}
+
+		let result = self.run_sub_transaction(
 			self.vicinity,
 			target_gas,
 			context.address,
@@ -629,10 +757,26 @@ impl<'vicinity, 'config, 'meter, T: Config> HandlerT for Handler<'vicinity, 'con
 				ExitReason::Error(ExitError::Other(Into::<&'static str>::into(x).into())),
 				Vec::new(),
 			))
-		})
+		});
+
+		if let (Some(tracer), Capture::Exit((reason, out))) = (self.tracer.as_ref(), &result) {
+			let gas_used = gas_before.saturating_sub(self.gasometer.gas());
+			tracer.borrow_mut().exit(reason, out, gas_used);
+		}
+
+		result
 	}
 
 	fn pre_validate(&mut self, context: &Context, opcode: Opcode, stack: &Stack) -> Result<(), ExitError> {
+		if opcode == BASEFEE && self.hard_fork >= HardFork::London {
+			return self.gasometer.record_cost(GAS_BASE);
+		}
+
+		if let Some(accessed) = self.accessed.as_ref() {
+			let cost = accessed.borrow_mut().access_cost(context.address, opcode, stack)?;
+			self.gasometer.record_cost(cost)?;
+		}
+
 		if let Some(cost) = gasometer::static_opcode_cost(opcode) {
 			self.gasometer.record_cost(cost)?;
 		} else {
@@ -643,10 +787,23 @@ impl<'vicinity, 'config, 'meter, T: Config> HandlerT for Handler<'vicinity, 'con
 		}
 		Ok(())
 	}
+
+	fn other(&mut self, opcode: Opcode, machine: &mut Machine) -> Result<(), ExitError> {
+		if opcode == BASEFEE && self.hard_fork >= HardFork::London {
+			// there are no priority fees, the gas price is the base fee
+			let mut base_fee = [0u8; 32];
+			self.vicinity.gas_price.to_big_endian(&mut base_fee);
+			return machine.stack_mut().push(H256::from(base_fee));
+		}
+
+		Err(ExitError::OutOfGas)
+	}
 }
 
 struct StorageMeterHandlerImpl<T: Config> {
 	origin: H160,
+	/// Account the storage deposit is reserved from.
+	payer: T::AccountId,
 	_marker: PhantomData<T>,
 }
 
@@ -662,7 +819,7 @@ impl<T: Config> StorageMeterHandler for StorageMeterHandlerImpl<T> {
 			self.origin, limit,
 		);
 
-		let user = T::AddressMapping::get_account_id(&self.origin);
+		let user = self.payer.clone();
 
 		let amount = T::StorageDepositPerByte::get().saturating_mul(limit.into());
 
@@ -682,7 +839,7 @@ impl<T: Config> StorageMeterHandler for StorageMeterHandlerImpl<T> {
 			self.origin, used, refunded, unused
 		);
 
-		let user = T::AddressMapping::get_account_id(&self.origin);
+		let user = self.payer.clone();
 		let amount = T::StorageDepositPerByte::get().saturating_mul(unused.into());
 
 		// should always be able to unreserve the amount
@@ -704,9 +861,17 @@ impl<T: Config> StorageMeterHandler for StorageMeterHandlerImpl<T> {
 			&self.origin, contract, used, refunded
 		);
 
-		let user = T::AddressMapping::get_account_id(&self.origin);
+		let user = self.payer.clone();
 		let contract_acc = T::AddressMapping::get_account_id(contract);
 
+		ContractStorageSizes::<T>::mutate(contract, |size| {
+			*size = if used > refunded {
+				size.saturating_add(used - refunded)
+			} else {
+				size.saturating_sub(refunded - used)
+			}
+		});
+
 		if used > refunded {
 			let storage = used - refunded;
 			let amount = T::StorageDepositPerByte::get().saturating_mul(storage.into());
@@ -717,12 +882,18 @@ impl<T: Config> StorageMeterHandler for StorageMeterHandlerImpl<T> {
 			T::Currency::unreserve(&user, amount);
 			T::Currency::transfer(&user, &contract_acc, amount, ExistenceRequirement::AllowDeath)?;
 			T::Currency::reserve(&contract_acc, amount)?;
+			StorageDeposits::<T>::mutate(contract, |deposit| *deposit = deposit.saturating_add(amount));
+
+			Pallet::<T>::deposit_event(Event::<T>::StorageDepositCharged(self.origin, *contract, amount));
 		} else {
 			let storage = refunded - used;
 			let amount = T::StorageDepositPerByte::get().saturating_mul(storage.into());
 
 			// user can't be a dead account
 			T::Currency::repatriate_reserved(&contract_acc, &user, amount, BalanceStatus::Reserved)?;
+			StorageDeposits::<T>::mutate(contract, |deposit| *deposit = deposit.saturating_sub(amount));
+
+			Pallet::<T>::deposit_event(Event::<T>::StorageDepositRefunded(self.origin, *contract, amount));
 		};
 
 		Ok(())
@@ -1,16 +1,21 @@
+pub mod access_list;
 pub mod handler;
 pub mod storage_meter;
+pub mod tracer;
 
-use crate::{AddressMapping, BalanceOf, CallInfo, Config, CreateInfo, Error, Pallet, Vicinity};
+use crate::{AddressMapping, BalanceOf, CallInfo, Config, CreateInfo, Error, HardFork, Pallet, Vicinity};
 use evm::{CreateScheme, ExitError, ExitReason};
 use evm_runtime::Handler as HandlerT;
 use evm_gasometer::{self as gasometer};
 use frame_support::traits::{Currency, ExistenceRequirement, Get};
+use access_list::{access_list_cost, AccessedSet, AccessedSetRef};
 use handler::Handler;
 use primitive_types::{H160, H256, U256};
+use primitives::evm::{AccessListInfo, AccessListItem, CallType, TraceConfig, TraceInfo};
 use sha3::{Digest, Keccak256};
 use sp_runtime::{traits::Zero, DispatchError, DispatchResult, SaturatedConversion, TransactionOutcome};
 use sp_std::{marker::PhantomData, vec::Vec};
+use tracer::{Tracer, TracerRef};
 
 #[derive(Default)]
 pub struct Runner<T: Config> {
@@ -27,7 +32,10 @@ impl<T: Config> Runner<T> {
 		assigned_address: Option<H160>,
 		salt: Option<H256>,
 		tag: &'static str,
+		access_list: Vec<AccessListItem>,
 		config: &evm::Config,
+		tracer: Option<TracerRef>,
+		accessed: Option<AccessedSetRef>,
 	) -> Result<CreateInfo, DispatchError> {
 		log::debug!(
 			target: "evm",
@@ -38,8 +46,12 @@ impl<T: Config> Runner<T> {
 			storage_limit,
 		);
 
+		if !access_list.is_empty() && Pallet::<T>::hard_fork() < HardFork::Berlin {
+			return Err(Error::<T>::AccessListNotSupported.into());
+		}
+
 		let vicinity = Vicinity {
-			gas_price: U256::one(),
+			gas_price: T::BaseFee::get(),
 			origin: source,
 		};
 
@@ -61,14 +73,27 @@ impl<T: Config> Runner<T> {
 
 		Handler::<T>::inc_nonce(source);
 
-		Handler::<T>::run_transaction(
+		let call_type = if salt.is_some() {
+			CallType::Create2
+		} else {
+			CallType::Create
+		};
+
+		let create_info = Handler::<T>::run_transaction(
 			&vicinity,
 			gas_limit,
 			storage_limit,
 			address,
 			false,
 			config,
+			&access_list,
+			accessed,
+			tracer,
 			|substate| {
+				if let Some(accessed) = substate.accessed.as_ref() {
+					accessed.borrow_mut().access_address(address);
+				}
+
 				if let Err(e) = Self::transfer(source, address, value) {
 					return TransactionOutcome::Rollback(Err(e));
 				}
@@ -77,14 +102,22 @@ impl<T: Config> Runner<T> {
 				if substate.gasometer.record_transaction(transaction_cost).is_err() {
 					return TransactionOutcome::Rollback(Err(DispatchError::Other("OutOfGas")));
 				}
+				if substate.gasometer.record_cost(access_list_cost(&access_list)).is_err() {
+					return TransactionOutcome::Rollback(Err(DispatchError::Other("OutOfGas")));
+				}
 
-				let (reason, out) = substate.execute(
-					source,
-					address,
-					U256::from(value.saturated_into::<u128>()),
-					init,
-					Vec::new(),
-				);
+				let value = U256::from(value.saturated_into::<u128>());
+				if let Some(tracer) = substate.tracer.as_ref() {
+					tracer
+						.borrow_mut()
+						.enter(call_type, source, address, init.clone(), value, gas_limit);
+				}
+
+				let (reason, out) = substate.execute(source, address, value, init, Vec::new());
+
+				if let Some(tracer) = substate.tracer.as_ref() {
+					tracer.borrow_mut().exit(&reason, &out, substate.used_gas());
+				}
 
 				let mut create_info = CreateInfo {
 					exit_reason: reason.clone(),
@@ -138,7 +171,10 @@ impl<T: Config> Runner<T> {
 
 				TransactionOutcome::Commit(Ok(create_info))
 			},
-		)?
+		)??;
+
+		Pallet::<T>::note_used_gas(create_info.used_gas);
+		Ok(create_info)
 	}
 
 	fn transfer(source: H160, target: H160, value: BalanceOf<T>) -> DispatchResult {
@@ -153,7 +189,7 @@ impl<T: Config> Runner<T> {
 }
 
 impl<T: Config> Runner<T> {
-	pub fn call(
+	fn inner_call(
 		sender: H160,
 		origin: H160,
 		target: H160,
@@ -161,7 +197,10 @@ impl<T: Config> Runner<T> {
 		value: BalanceOf<T>,
 		gas_limit: u64,
 		storage_limit: u32,
+		access_list: Vec<AccessListItem>,
 		config: &evm::Config,
+		tracer: Option<TracerRef>,
+		accessed: Option<AccessedSetRef>,
 	) -> Result<CallInfo, DispatchError> {
 		log::debug!(
 			target: "evm",
@@ -174,8 +213,12 @@ impl<T: Config> Runner<T> {
 			storage_limit,
 		);
 
+		if !access_list.is_empty() && Pallet::<T>::hard_fork() < HardFork::Berlin {
+			return Err(Error::<T>::AccessListNotSupported.into());
+		}
+
 		let vicinity = Vicinity {
-			gas_price: U256::one(),
+			gas_price: T::BaseFee::get(),
 			origin,
 		};
 
@@ -187,39 +230,93 @@ impl<T: Config> Runner<T> {
 
 		Handler::<T>::inc_nonce(sender);
 
-		Handler::<T>::run_transaction(&vicinity, gas_limit, storage_limit, target, false, config, |substate| {
-			if let Err(e) = Self::transfer(sender, target, value) {
-				return TransactionOutcome::Rollback(Err(e));
-			}
-
-			let code = substate.code(target);
-			let transaction_cost = gasometer::call_transaction_cost(&code);
-			if substate.gasometer.record_transaction(transaction_cost).is_err() {
-				return TransactionOutcome::Rollback(Err(DispatchError::Other("OutOfGas")));
-			}
-
-			let (reason, out) =
-				substate.execute(sender, target, U256::from(value.saturated_into::<u128>()), code, input);
-
-			let call_info = CallInfo {
-				exit_reason: reason.clone(),
-				output: out,
-				used_gas: U256::from(substate.used_gas()),
-				used_storage: substate.used_storage(),
-			};
+		let call_info = Handler::<T>::run_transaction(
+			&vicinity,
+			gas_limit,
+			storage_limit,
+			target,
+			false,
+			config,
+			&access_list,
+			accessed,
+			tracer,
+			|substate| {
+				if let Err(e) = Self::transfer(sender, target, value) {
+					return TransactionOutcome::Rollback(Err(e));
+				}
+
+				let code = substate.code(target);
+				let transaction_cost = gasometer::call_transaction_cost(&code);
+				if substate.gasometer.record_transaction(transaction_cost).is_err() {
+					return TransactionOutcome::Rollback(Err(DispatchError::Other("OutOfGas")));
+				}
+				if substate.gasometer.record_cost(access_list_cost(&access_list)).is_err() {
+					return TransactionOutcome::Rollback(Err(DispatchError::Other("OutOfGas")));
+				}
+
+				let value = U256::from(value.saturated_into::<u128>());
+				if let Some(tracer) = substate.tracer.as_ref() {
+					tracer
+						.borrow_mut()
+						.enter(CallType::Call, sender, target, input.clone(), value, gas_limit);
+				}
 
-			log::debug!(
-				target: "evm",
-				"call-result: call_info {:?}",
-				call_info
-			);
+				let (reason, out) = substate.execute(sender, target, value, code, input);
 
-			if !reason.is_succeed() {
-				return TransactionOutcome::Rollback(Ok(call_info));
-			}
+				if let Some(tracer) = substate.tracer.as_ref() {
+					tracer.borrow_mut().exit(&reason, &out, substate.used_gas());
+				}
 
-			TransactionOutcome::Commit(Ok(call_info))
-		})?
+				let call_info = CallInfo {
+					exit_reason: reason.clone(),
+					output: out,
+					used_gas: U256::from(substate.used_gas()),
+					used_storage: substate.used_storage(),
+				};
+
+				log::debug!(
+					target: "evm",
+					"call-result: call_info {:?}",
+					call_info
+				);
+
+				if !reason.is_succeed() {
+					return TransactionOutcome::Rollback(Ok(call_info));
+				}
+
+				TransactionOutcome::Commit(Ok(call_info))
+			},
+		)??;
+
+		Pallet::<T>::note_used_gas(call_info.used_gas);
+		Ok(call_info)
+	}
+}
+
+impl<T: Config> Runner<T> {
+	pub fn call(
+		sender: H160,
+		origin: H160,
+		target: H160,
+		input: Vec<u8>,
+		value: BalanceOf<T>,
+		gas_limit: u64,
+		storage_limit: u32,
+		config: &evm::Config,
+	) -> Result<CallInfo, DispatchError> {
+		Self::inner_call(
+			sender,
+			origin,
+			target,
+			input,
+			value,
+			gas_limit,
+			storage_limit,
+			Vec::new(),
+			config,
+			None,
+			None,
+		)
 	}
 
 	pub fn create(
@@ -239,7 +336,10 @@ impl<T: Config> Runner<T> {
 			None,
 			None,
 			"create",
+			Vec::new(),
 			config,
+			None,
+			None,
 		)
 	}
 
@@ -261,7 +361,10 @@ impl<T: Config> Runner<T> {
 			None,
 			Some(salt),
 			"create2",
+			Vec::new(),
 			config,
+			None,
+			None,
 		)
 	}
 
@@ -283,7 +386,208 @@ impl<T: Config> Runner<T> {
 			Some(assigned_address),
 			None,
 			"create-system-contract",
+			Vec::new(),
 			config,
+			None,
+			None,
 		)
 	}
+
+	/// Execute a call with the addresses and storage keys of the access list
+	/// warm (EIP-2930).
+	pub fn call_with_access_list(
+		sender: H160,
+		origin: H160,
+		target: H160,
+		input: Vec<u8>,
+		value: BalanceOf<T>,
+		gas_limit: u64,
+		storage_limit: u32,
+		access_list: Vec<AccessListItem>,
+		config: &evm::Config,
+	) -> Result<CallInfo, DispatchError> {
+		Self::inner_call(
+			sender,
+			origin,
+			target,
+			input,
+			value,
+			gas_limit,
+			storage_limit,
+			access_list,
+			config,
+			None,
+			None,
+		)
+	}
+
+	/// Execute a create, or create2 if `salt` is given, with the addresses and
+	/// storage keys of the access list warm (EIP-2930).
+	pub fn create_with_access_list(
+		source: H160,
+		init: Vec<u8>,
+		salt: Option<H256>,
+		value: BalanceOf<T>,
+		gas_limit: u64,
+		storage_limit: u32,
+		access_list: Vec<AccessListItem>,
+		config: &evm::Config,
+	) -> Result<CreateInfo, DispatchError> {
+		Self::inner_create(
+			source,
+			init,
+			value,
+			gas_limit,
+			storage_limit,
+			None,
+			salt,
+			if salt.is_some() { "create2" } else { "create" },
+			access_list,
+			config,
+			None,
+			None,
+		)
+	}
+
+	/// Generate the access list of a call, or of a create if `target` is
+	/// `None`. State changes are discarded.
+	pub fn create_access_list(
+		source: H160,
+		target: Option<H160>,
+		input: Vec<u8>,
+		value: BalanceOf<T>,
+		gas_limit: u64,
+		storage_limit: u32,
+		config: &evm::Config,
+	) -> Result<AccessListInfo, DispatchError> {
+		if Pallet::<T>::hard_fork() < HardFork::Berlin {
+			return Err(Error::<T>::AccessListNotSupported.into());
+		}
+
+		let run = |access_list: Vec<AccessListItem>, accessed: Option<AccessedSetRef>| {
+			frame_support::storage::with_transaction(|| {
+				let result = match target {
+					Some(target) => Self::inner_call(
+						source,
+						source,
+						target,
+						input.clone(),
+						value,
+						gas_limit,
+						storage_limit,
+						access_list,
+						config,
+						None,
+						accessed,
+					)
+					.map(|info| (info.exit_reason, info.output, info.used_gas, target)),
+					None => Self::inner_create(
+						source,
+						input.clone(),
+						value,
+						gas_limit,
+						storage_limit,
+						None,
+						None,
+						"create-access-list",
+						access_list,
+						config,
+						None,
+						accessed,
+					)
+					.map(|info| (info.exit_reason, info.output, info.used_gas, info.address)),
+				};
+				TransactionOutcome::Rollback(result)
+			})
+		};
+
+		let accessed = AccessedSet::new_ref(source, target.unwrap_or(source));
+		let (_, _, _, contract) = run(Vec::new(), Some(accessed.clone()))?;
+		let access_list = accessed.borrow().access_list(&[source, contract]);
+
+		// run again with the access list, so the used gas includes its cost
+		let (exit_reason, output, used_gas, _) = run(access_list.clone(), None)?;
+
+		Ok(AccessListInfo {
+			exit_reason,
+			output,
+			used_gas,
+			access_list,
+		})
+	}
+
+	/// Execute a call, recording every step with the given trace config.
+	pub fn trace_call(
+		sender: H160,
+		origin: H160,
+		target: H160,
+		input: Vec<u8>,
+		value: BalanceOf<T>,
+		gas_limit: u64,
+		storage_limit: u32,
+		access_list: Vec<AccessListItem>,
+		config: &evm::Config,
+		trace_config: TraceConfig,
+	) -> Result<TraceInfo, DispatchError> {
+		let tracer = Tracer::new_ref(trace_config);
+		let info = Self::inner_call(
+			sender,
+			origin,
+			target,
+			input,
+			value,
+			gas_limit,
+			storage_limit,
+			access_list,
+			config,
+			Some(tracer.clone()),
+			None,
+		)?;
+
+		let trace = tracer.borrow_mut().finish();
+		Ok(TraceInfo {
+			exit_reason: info.exit_reason,
+			output: info.output,
+			used_gas: info.used_gas,
+			trace,
+		})
+	}
+
+	/// Execute a create or create2 if `salt` is given, recording every step
+	/// with the given trace config.
+	pub fn trace_create(
+		source: H160,
+		init: Vec<u8>,
+		salt: Option<H256>,
+		value: BalanceOf<T>,
+		gas_limit: u64,
+		storage_limit: u32,
+		access_list: Vec<AccessListItem>,
+		config: &evm::Config,
+		trace_config: TraceConfig,
+	) -> Result<TraceInfo, DispatchError> {
+		let tracer = Tracer::new_ref(trace_config);
+		let info = Self::inner_create(
+			source,
+			init,
+			value,
+			gas_limit,
+			storage_limit,
+			None,
+			salt,
+			"trace-create",
+			access_list,
+			config,
+			Some(tracer.clone()),
+			None,
+		)?;
+
+		let trace = tracer.borrow_mut().finish();
+		Ok(TraceInfo {
+			exit_reason: info.exit_reason,
+			output: info.output,
+			used_gas: info.used_gas,
+			trace,
+		})
+	}
 }
@@ -0,0 +1,121 @@
+use evm::{ExitReason, Memory, Opcode, Stack};
+use primitive_types::{H160, H256, U256};
+use primitives::evm::{CallTrace, CallType, StructLog, Trace, TraceConfig, TracerKind};
+use sp_std::{cell::RefCell, prelude::*, rc::Rc};
+
+/// Shared tracer handle, cloned into every sub call handler.
+pub type TracerRef = Rc<RefCell<Tracer>>;
+
+/// Collects the executed opcodes and call frames of a transaction.
+pub struct Tracer {
+	config: TraceConfig,
+	struct_logs: Vec<StructLog>,
+	/// Frames which have been entered but not exited yet.
+	call_stack: Vec<CallTrace>,
+	root: Option<CallTrace>,
+}
+
+impl Tracer {
+	pub fn new(config: TraceConfig) -> Self {
+		Self {
+			config,
+			struct_logs: Vec::new(),
+			call_stack: Vec::new(),
+			root: None,
+		}
+	}
+
+	pub fn new_ref(config: TraceConfig) -> TracerRef {
+		Rc::new(RefCell::new(Self::new(config)))
+	}
+
+	fn is_struct_logger(&self) -> bool {
+		self.config.tracer == TracerKind::StructLogger
+	}
+
+	/// Record an opcode before it is executed, returning the index of the step.
+	pub fn step(&mut self, pc: usize, opcode: Opcode, gas: u64, stack: &Stack, memory: &Memory) -> Option<usize> {
+		if !self.is_struct_logger() {
+			return None;
+		}
+
+		self.struct_logs.push(StructLog {
+			pc: pc as u64,
+			op: opcode.0,
+			gas,
+			gas_cost: 0,
+			depth: self.call_stack.len() as u32,
+			stack: if self.config.disable_stack {
+				Vec::new()
+			} else {
+				stack.data().clone()
+			},
+			memory: if self.config.enable_memory {
+				memory.data().clone()
+			} else {
+				Vec::new()
+			},
+			storage: Vec::new(),
+		});
+
+		Some(self.struct_logs.len() - 1)
+	}
+
+	/// Record the gas used by the step at `index`.
+	pub fn step_cost(&mut self, index: usize, gas_cost: u64) {
+		if let Some(log) = self.struct_logs.get_mut(index) {
+			log.gas_cost = gas_cost;
+		}
+	}
+
+	/// Record a storage write of the step being executed.
+	pub fn storage_change(&mut self, index: H256, value: H256) {
+		if !self.is_struct_logger() || self.config.disable_storage {
+			return;
+		}
+
+		if let Some(log) = self.struct_logs.last_mut() {
+			log.storage.push((index, value));
+		}
+	}
+
+	/// Enter a new call frame.
+	pub fn enter(&mut self, call_type: CallType, from: H160, to: H160, input: Vec<u8>, value: U256, gas: u64) {
+		self.call_stack.push(CallTrace {
+			call_type,
+			from,
+			to,
+			input,
+			value,
+			gas,
+			gas_used: 0,
+			output: Vec::new(),
+			error: None,
+			calls: Vec::new(),
+		});
+	}
+
+	/// Exit the current call frame.
+	pub fn exit(&mut self, reason: &ExitReason, output: &[u8], gas_used: u64) {
+		if let Some(mut frame) = self.call_stack.pop() {
+			frame.gas_used = gas_used;
+			frame.output = output.to_vec();
+			if !reason.is_succeed() {
+				frame.error = Some(reason.clone());
+			}
+
+			match self.call_stack.last_mut() {
+				Some(parent) => parent.calls.push(frame),
+				None => self.root = Some(frame),
+			}
+		}
+	}
+
+	/// Take the collected trace out of the tracer.
+	pub fn finish(&mut self) -> Trace {
+		match self.config.tracer {
+			TracerKind::StructLogger => Trace::StructLogs(sp_std::mem::take(&mut self.struct_logs)),
+			TracerKind::CallTracer => Trace::CallTrace(self.root.take()),
+		}
+	}
+}
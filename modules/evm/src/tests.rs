@@ -5,6 +5,7 @@ use mock::{Event, *};
 
 use crate::runner::handler::Handler;
 use frame_support::{assert_noop, assert_ok};
+use primitives::evm::{AccessListItem, BloomInput, CallType, Trace, TracerKind};
 use sp_core::{
 	bytes::{from_hex, to_hex},
 	H160,
@@ -107,6 +108,73 @@ fn should_create_and_call_contract() {
 	});
 }
 
+#[test]
+fn should_trace_contract_call() {
+	// multiply contract from `should_create_and_call_contract`
+	let contract = from_hex("0x608060405234801561001057600080fd5b5060b88061001f6000396000f3fe6080604052348015600f57600080fd5b506004361060285760003560e01c8063165c4a1614602d575b600080fd5b606060048036036040811015604157600080fd5b8101908080359060200190929190803590602001909291905050506076565b6040518082815260200191505060405180910390f35b600081830290509291505056fea265627a7a723158201f3db7301354b88b310868daf4395a6ab6cd42d16b1d8e68cdf4fdd9d34fffbf64736f6c63430005110032").unwrap();
+	// multiply(2, 3)
+	let multiply = from_hex("0x165c4a1600000000000000000000000000000000000000000000000000000000000000020000000000000000000000000000000000000000000000000000000000000003").unwrap();
+
+	new_test_ext().execute_with(|| {
+		let info = Runner::<Test>::trace_create(
+			alice(),
+			contract,
+			None,
+			0,
+			1000000,
+			1000000,
+			Vec::new(),
+			<Test as Config>::config(),
+			TraceConfig::default(),
+		).unwrap();
+		assert_eq!(info.exit_reason, ExitReason::Succeed(ExitSucceed::Returned));
+		match info.trace {
+			Trace::StructLogs(logs) => {
+				// PUSH1 0x80
+				assert_eq!((logs[0].pc, logs[0].op, logs[0].depth), (0, 0x60, 1));
+				assert_eq!(logs[0].gas_cost, 3);
+				assert!(logs.iter().all(|log| log.depth == 1));
+			}
+			_ => panic!("expected struct logs"),
+		}
+
+		let contract_address = H160::from_str("5f8bd49cd9f0cb2bd5bb9d4320dfe9b61023249d").unwrap();
+
+		#[cfg(not(feature = "with-ethereum-compatibility"))]
+		deploy_free(contract_address);
+
+		let info = Runner::<Test>::trace_call(
+			alice(),
+			alice(),
+			contract_address,
+			multiply.clone(),
+			0,
+			1000000,
+			1000000,
+			Vec::new(),
+			<Test as Config>::config(),
+			TraceConfig {
+				tracer: TracerKind::CallTracer,
+				..Default::default()
+			},
+		).unwrap();
+		assert_eq!(U256::from(info.output.as_slice()), U256::from(6));
+		match info.trace {
+			Trace::CallTrace(Some(frame)) => {
+				assert_eq!(frame.call_type, CallType::Call);
+				assert_eq!(frame.from, alice());
+				assert_eq!(frame.to, contract_address);
+				assert_eq!(frame.input, multiply);
+				assert_eq!(frame.output, info.output);
+				assert_eq!(U256::from(frame.gas_used), info.used_gas);
+				assert!(frame.error.is_none());
+				assert!(frame.calls.is_empty());
+			}
+			_ => panic!("expected call trace"),
+		}
+	});
+}
+
 #[test]
 fn create_reverts_with_message() {
 	// pragma solidity ^0.5.0;
@@ -566,6 +634,39 @@ fn create_network_contract_fails_if_non_network_contract_origin() {
 	});
 }
 
+#[test]
+fn failed_call_should_report_used_gas() {
+	// deploys PUSH1 0x00 PUSH1 0x00 REVERT
+	let contract = from_hex("0x6460006000fd6000526005601bf3").unwrap();
+
+	new_test_ext().execute_with(|| {
+		let address = Runner::<Test>::create(alice(), contract, 0, 1000000, 1000000, <Test as Config>::config())
+			.unwrap()
+			.address;
+		let info =
+			Runner::<Test>::call(alice(), alice(), address, vec![], 0, 1000000, 1000000, <Test as Config>::config())
+				.unwrap();
+		assert_eq!(info.exit_reason, ExitReason::Revert(ExitRevert::Reverted));
+
+		assert_ok!(EVM::call(
+			Origin::signed(<Test as Config>::AddressMapping::get_account_id(&alice())),
+			address,
+			vec![],
+			0,
+			1000000,
+			1000000,
+		));
+		let event = Event::EVM(crate::Event::ExecutedFailed(
+			alice(),
+			address,
+			ExitReason::Revert(ExitRevert::Reverted),
+			vec![],
+			(info.used_gas.as_u64(), 0),
+		));
+		assert!(System::events().iter().any(|record| record.event == event));
+	});
+}
+
 #[test]
 fn should_transfer_maintainer() {
 	// pragma solidity ^0.5.0;
@@ -774,6 +875,56 @@ fn create2_extrinisic_should_deposit_create_event() {
 	});
 }
 
+#[test]
+fn create2_with_access_list_extrinsic_should_work() {
+	// PUSH1 0x00 PUSH1 0x00 MSTORE8 PUSH1 0x01 PUSH1 0x00 RETURN
+	let contract = from_hex("0x600060005360016000f3").unwrap();
+	let salt = H256::from_low_u64_be(1);
+	let access_list = vec![AccessListItem {
+		address: alice(),
+		storage_keys: vec![],
+	}];
+
+	new_test_ext().execute_with(|| {
+		let alice_account_id = <Test as Config>::AddressMapping::get_account_id(&alice());
+		let address = Handler::<Test>::create_address(evm::CreateScheme::Create2 {
+			caller: alice(),
+			code_hash: code_hash(&contract),
+			salt,
+		})
+		.unwrap();
+
+		assert_noop!(
+			EVM::create2_with_access_list(
+				Origin::signed(alice_account_id.clone()),
+				contract.clone(),
+				salt,
+				0,
+				1000000,
+				1000000,
+				access_list.clone()
+			),
+			Error::<Test>::AccessListNotSupported
+		);
+
+		assert_ok!(EVM::set_hard_fork(
+			Origin::signed(CouncilAccount::get()),
+			HardFork::Berlin,
+			0
+		));
+		assert_ok!(EVM::create2_with_access_list(
+			Origin::signed(alice_account_id),
+			contract,
+			salt,
+			0,
+			1000000,
+			1000000,
+			access_list
+		));
+		assert_eq!(EVM::code_at_address(&address), vec![0x00]);
+	});
+}
+
 #[cfg(feature = "with-ethereum-compatibility")]
 #[test]
 fn call_extrinsic_should_deposit_create_event() {
@@ -1442,3 +1593,1360 @@ fn evm_execute_mode_should_work() {
 		assert_eq!(balance(alice()), alice_balance);
 	});
 }
+
+#[test]
+fn should_schedule_hard_fork() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		assert_noop!(
+			EVM::set_hard_fork(Origin::signed(AccountId32::from([2u8; 32])), HardFork::Berlin, 10),
+			BadOrigin
+		);
+
+		assert_ok!(EVM::set_hard_fork(
+			Origin::signed(CouncilAccount::get()),
+			HardFork::Berlin,
+			10
+		));
+		let event = Event::EVM(crate::Event::HardForkScheduled(HardFork::Berlin, 10));
+		assert!(System::events().iter().any(|record| record.event == event));
+		assert_eq!(EVM::scheduled_hard_fork(), Some((10, HardFork::Berlin)));
+
+		EVM::on_initialize(9);
+		assert_eq!(EVM::hard_fork(), HardFork::Istanbul);
+		assert_eq!(<Test as Config>::config().gas_sload, 800);
+
+		EVM::on_initialize(10);
+		assert_eq!(EVM::hard_fork(), HardFork::Berlin);
+		assert_eq!(EVM::scheduled_hard_fork(), None);
+		assert_eq!(<Test as Config>::config().gas_sload, 100);
+		let event = Event::EVM(crate::Event::HardForkActivated(HardFork::Berlin));
+		assert!(System::events().iter().any(|record| record.event == event));
+
+		// activates immediately once the block is reached
+		assert_ok!(EVM::set_hard_fork(
+			Origin::signed(CouncilAccount::get()),
+			HardFork::London,
+			1
+		));
+		assert_eq!(EVM::hard_fork(), HardFork::London);
+	});
+}
+
+#[test]
+fn should_reject_hard_fork_downgrade() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_noop!(
+			EVM::set_hard_fork(Origin::signed(CouncilAccount::get()), HardFork::Istanbul, 10),
+			Error::<Test>::HardForkDowngrade
+		);
+
+		assert_ok!(EVM::set_hard_fork(
+			Origin::signed(CouncilAccount::get()),
+			HardFork::London,
+			1
+		));
+		assert_noop!(
+			EVM::set_hard_fork(Origin::signed(CouncilAccount::get()), HardFork::Berlin, 10),
+			Error::<Test>::HardForkDowngrade
+		);
+		assert_noop!(
+			EVM::set_hard_fork(Origin::signed(CouncilAccount::get()), HardFork::London, 10),
+			Error::<Test>::HardForkDowngrade
+		);
+		assert_eq!(EVM::scheduled_hard_fork(), None);
+		assert_eq!(EVM::hard_fork(), HardFork::London);
+	});
+}
+
+#[test]
+fn berlin_should_charge_cold_storage_access() {
+	// PUSH1 0x00 SLOAD PUSH1 0x00 SLOAD STOP
+	let code = from_hex("0x60005460005400").unwrap();
+	let sload_costs = |code: Vec<u8>| {
+		let info = Runner::<Test>::trace_create(
+			alice(),
+			code,
+			None,
+			0,
+			1000000,
+			1000000,
+			Vec::new(),
+			<Test as Config>::config(),
+			TraceConfig::default(),
+		)
+		.unwrap();
+		match info.trace {
+			Trace::StructLogs(logs) => (logs[1].gas_cost, logs[3].gas_cost),
+			_ => panic!("expected struct logs"),
+		}
+	};
+
+	new_test_ext().execute_with(|| {
+		assert_eq!(sload_costs(code.clone()), (800, 800));
+
+		assert_ok!(EVM::set_hard_fork(
+			Origin::signed(CouncilAccount::get()),
+			HardFork::Berlin,
+			0
+		));
+		assert_eq!(sload_costs(code), (2100, 100));
+	});
+}
+
+#[test]
+fn london_should_support_basefee_and_reject_ef_code() {
+	// BASEFEE PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN
+	let basefee = from_hex("0x4860005260206000f3").unwrap();
+	// PUSH1 0xef PUSH1 0x00 MSTORE8 PUSH1 0x01 PUSH1 0x00 RETURN
+	let ef_code = from_hex("0x60ef60005360016000f3").unwrap();
+	let create = |code: Vec<u8>| {
+		Runner::<Test>::create(alice(), code, 0, 1000000, 1000000, <Test as Config>::config())
+			.unwrap()
+			.exit_reason
+	};
+
+	new_test_ext().execute_with(|| {
+		assert!(!create(basefee.clone()).is_succeed());
+		assert!(create(ef_code.clone()).is_succeed());
+
+		assert_ok!(EVM::set_hard_fork(
+			Origin::signed(CouncilAccount::get()),
+			HardFork::London,
+			0
+		));
+		assert!(create(basefee).is_succeed());
+		assert!(!create(ef_code).is_succeed());
+	});
+}
+
+#[test]
+fn should_archive_and_restore_under_collateralised_contract() {
+	// PUSH1 0x2a PUSH1 0x00 SSTORE PUSH1 0x00 PUSH1 0x00 MSTORE8 PUSH1 0x01 PUSH1 0x00 RETURN
+	let contract = from_hex("0x602a600055600060005360016000f3").unwrap();
+
+	new_test_ext().execute_with(|| {
+		let result = Runner::<Test>::create(alice(), contract, 0, 1000000, 1000000, <Test as Config>::config()).unwrap();
+		assert_eq!(result.exit_reason, ExitReason::Succeed(ExitSucceed::Returned));
+		let address = result.address;
+		let contract_account_id = <Test as Config>::AddressMapping::get_account_id(&address);
+		let signer = <Test as Config>::AddressMapping::get_account_id(&bob());
+
+		// code, extra bytes and one storage entry
+		assert_eq!(EVM::contract_storage_size(&address), 1 + 100 + 64);
+		assert_eq!(reserved_balance(address), 1650);
+		assert_noop!(
+			EVM::check_storage_rent(Origin::signed(signer.clone()), address),
+			Error::<Test>::ContractCollateralised
+		);
+
+		assert_eq!(EVM::storage_deposit(&address), 1650);
+		let alice_account_id = <Test as Config>::AddressMapping::get_account_id(&alice());
+		assert_ok!(EVM::fund_gas_tank(Origin::signed(alice_account_id), address, 300));
+		Balances::unreserve(&contract_account_id, 100);
+		StorageDeposits::<Test>::insert(&address, 1550);
+		assert!(EVM::is_under_collateralised(&address));
+		assert_ok!(EVM::check_storage_rent(Origin::signed(signer.clone()), address));
+		assert_eq!(EVM::under_collateralised_since(&address), Some(1));
+
+		assert_noop!(
+			EVM::archive_contract(Origin::signed(signer.clone()), address),
+			Error::<Test>::RentGracePeriodNotElapsed
+		);
+
+		System::set_block_number(11);
+		let alice_balance = balance(alice());
+		assert_ok!(EVM::archive_contract(Origin::signed(signer.clone()), address));
+		// the gas tank is refunded to the maintainer
+		assert_eq!(balance(alice()), alice_balance + 300);
+		assert_eq!(EVM::gas_tanks(&address), None);
+		let event = Event::EVM(crate::Event::ContractArchived(address));
+		assert!(System::events().iter().any(|record| record.event == event));
+
+		assert_eq!(EVM::contract_info(&address), None);
+		assert_eq!(EVM::account_storages(address, H256::zero()), H256::zero());
+		assert_eq!(EVM::code_infos(code_hash(&[0x00])), None);
+		assert_eq!(EVM::under_collateralised_since(&address), None);
+		assert_eq!(reserved_balance(address), 0);
+
+		let storage = vec![(H256::zero(), H256::from_low_u64_be(0x2a))];
+		assert_noop!(
+			EVM::restore_contract(Origin::signed(signer.clone()), address, vec![0x01], storage.clone()),
+			Error::<Test>::ArchivedCodeMismatch
+		);
+		assert_noop!(
+			EVM::restore_contract(Origin::signed(signer.clone()), address, vec![0x00], vec![]),
+			Error::<Test>::ArchivedStorageMismatch
+		);
+
+		let bob_balance = balance(bob());
+		assert_ok!(EVM::restore_contract(Origin::signed(signer), address, vec![0x00], storage));
+		assert_eq!(EVM::account_storages(address, H256::zero()), H256::from_low_u64_be(0x2a));
+		assert_eq!(EVM::code_at_address(&address), vec![0x00]);
+		assert_eq!(EVM::contract_storage_size(&address), 165);
+		assert_eq!(EVM::archived_contracts(&address), None);
+		assert_eq!(reserved_balance(address), 1650);
+		assert_eq!(EVM::storage_deposit(&address), 1650);
+		assert_eq!(balance(bob()), bob_balance - 1650);
+		assert!(!EVM::is_under_collateralised(&address));
+	});
+}
+
+#[test]
+fn top_up_storage_deposit_should_clear_report() {
+	// PUSH1 0x00 PUSH1 0x00 MSTORE8 PUSH1 0x01 PUSH1 0x00 RETURN
+	let contract = from_hex("0x600060005360016000f3").unwrap();
+
+	new_test_ext().execute_with(|| {
+		let result = Runner::<Test>::create(alice(), contract, 0, 1000000, 1000000, <Test as Config>::config()).unwrap();
+		let address = result.address;
+		let contract_account_id = <Test as Config>::AddressMapping::get_account_id(&address);
+		let signer = <Test as Config>::AddressMapping::get_account_id(&bob());
+
+		Balances::unreserve(&contract_account_id, 10);
+		StorageDeposits::<Test>::mutate(&address, |deposit| *deposit -= 10);
+		assert_ok!(EVM::check_storage_rent(Origin::signed(signer.clone()), address));
+		assert!(EVM::under_collateralised_since(&address).is_some());
+
+		assert_ok!(EVM::top_up_storage_deposit(Origin::signed(signer.clone()), address, 10));
+		assert_eq!(EVM::under_collateralised_since(&address), None);
+		assert_eq!(reserved_balance(address), 1010);
+		let event = Event::EVM(crate::Event::ContractCollateralised(address));
+		assert!(System::events().iter().any(|record| record.event == event));
+
+		assert_noop!(
+			EVM::archive_contract(Origin::signed(signer), address),
+			Error::<Test>::ContractNotUnderCollateralised
+		);
+	});
+}
+
+#[test]
+fn runtime_upgrade_should_backfill_storage_deposits() {
+	// PUSH1 0x2a PUSH1 0x00 SSTORE PUSH1 0x00 PUSH1 0x00 MSTORE8 PUSH1 0x01 PUSH1 0x00 RETURN
+	let contract = from_hex("0x602a600055600060005360016000f3").unwrap();
+
+	new_test_ext().execute_with(|| {
+		use frame_support::traits::{OnRuntimeUpgrade, StorageVersion};
+
+		// genesis contracts: code and extra bytes
+		assert_eq!(EVM::contract_storage_size(&contract_a()), 1 + 100);
+
+		let result = Runner::<Test>::create(alice(), contract, 0, 1000000, 1000000, <Test as Config>::config()).unwrap();
+		let address = result.address;
+		assert_ok!(EVM::fund_gas_tank(
+			Origin::signed(<Test as Config>::AddressMapping::get_account_id(&alice())),
+			address,
+			500
+		));
+
+		ContractStorageSizes::<Test>::remove(&address);
+		StorageDeposits::<Test>::remove(&address);
+		StorageVersion::new(0).put::<EVM>();
+
+		<EVM as OnRuntimeUpgrade>::on_runtime_upgrade();
+		assert_eq!(StorageVersion::get::<EVM>(), StorageVersion::new(1));
+		assert_eq!(EVM::storage_backfill(), Some(StorageBackfillCursor::NextAccount(None)));
+
+		// storage rent waits for the backfill
+		assert_noop!(
+			EVM::check_storage_rent(Origin::signed(<Test as Config>::AddressMapping::get_account_id(&bob())), address),
+			Error::<Test>::StorageBackfillPending
+		);
+
+		// the backfill runs from `on_initialize`
+		EVM::on_initialize(2);
+		assert_eq!(EVM::storage_backfill(), None);
+		assert_eq!(EVM::contract_storage_size(&address), 1 + 100 + 64);
+		assert_eq!(EVM::storage_deposit(&address), 1650);
+
+		// only runs once
+		StorageDeposits::<Test>::remove(&address);
+		<EVM as OnRuntimeUpgrade>::on_runtime_upgrade();
+		assert_eq!(EVM::storage_backfill(), None);
+		EVM::on_initialize(3);
+		assert_eq!(EVM::storage_deposit(&address), 0);
+	});
+}
+
+#[test]
+fn storage_backfill_should_run_over_several_blocks() {
+	// PUSH1 0x2a PUSH1 0x00 SSTORE PUSH1 0x2a PUSH1 0x01 SSTORE PUSH1 0x2a PUSH1 0x02 SSTORE
+	// PUSH1 0x00 PUSH1 0x00 MSTORE8 PUSH1 0x01 PUSH1 0x00 RETURN
+	let contract = from_hex("0x602a600055602a600155602a600255600060005360016000f3").unwrap();
+
+	new_test_ext().execute_with(|| {
+		let mut contracts = vec![contract_a(), contract_b()];
+		for _ in 0..3 {
+			let result =
+				Runner::<Test>::create(alice(), contract.clone(), 0, 1000000, 1000000, <Test as Config>::config())
+					.unwrap();
+			contracts.push(result.address);
+		}
+		let tracked: Vec<_> = contracts
+			.iter()
+			.map(|address| (EVM::contract_storage_size(address), EVM::storage_deposit(address)))
+			.collect();
+		assert_eq!(tracked[2].0, 1 + 100 + 3 * 64);
+
+		for address in &contracts {
+			ContractStorageSizes::<Test>::remove(address);
+			StorageDeposits::<Test>::remove(address);
+		}
+		StorageBackfill::<Test>::put(StorageBackfillCursor::NextAccount(None));
+
+		// two items per block, the slots of a contract are counted over several blocks
+		let mut blocks = 0;
+		let mut split_count = false;
+		while let Some(cursor) = EVM::storage_backfill() {
+			split_count |= matches!(cursor, StorageBackfillCursor::CountSlots { slots, .. } if slots > 0);
+			EVM::backfill_storage_deposits(cursor, 2);
+			blocks += 1;
+		}
+		assert!(split_count);
+		assert!(blocks > contracts.len());
+
+		let backfilled: Vec<_> = contracts
+			.iter()
+			.map(|address| (EVM::contract_storage_size(address), EVM::storage_deposit(address)))
+			.collect();
+		assert_eq!(backfilled, tracked);
+	});
+}
+
+#[test]
+fn should_call_with_state_override() {
+	// PUSH1 0x00 SLOAD PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN
+	let code = from_hex("0x60005460005260206000f3").unwrap();
+	let address = H160::from_str("2000000000000000000000000000000000000002").unwrap();
+
+	new_test_ext().execute_with(|| {
+		let result = frame_support::storage::with_transaction(|| {
+			EVM::apply_state_override(StateOverride {
+				address,
+				balance: Some(1000),
+				nonce: Some(5),
+				code: Some(code.clone()),
+				state: None,
+				state_diff: vec![(H256::zero(), H256::from_low_u64_be(0x2a))],
+			});
+
+			assert_eq!(balance(address), 1000);
+			assert_eq!(EVM::account_basic(&address).nonce, U256::from(5));
+			assert_eq!(EVM::code_at_address(&address), code);
+			assert!(EVM::contract_info(&address).unwrap().deployed);
+
+			let result = Runner::<Test>::call(
+				alice(),
+				alice(),
+				address,
+				Vec::new(),
+				0,
+				1000000,
+				1000000,
+				<Test as Config>::config(),
+			);
+			TransactionOutcome::Rollback(result)
+		})
+		.unwrap();
+
+		assert_eq!(result.exit_reason, ExitReason::Succeed(ExitSucceed::Returned));
+		assert_eq!(result.output, H256::from_low_u64_be(0x2a).as_bytes().to_vec());
+
+		// overrides are discarded with the transaction
+		assert_eq!(balance(address), 0);
+		assert_eq!(EVM::contract_info(&address), None);
+		assert_eq!(EVM::account_storages(address, H256::zero()), H256::zero());
+		assert_eq!(EVM::code_infos(code_hash(&code)), None);
+	});
+}
+
+#[test]
+fn should_emit_storage_deposit_events() {
+	// PUSH1 0x00 PUSH1 0x00 MSTORE8 PUSH1 0x01 PUSH1 0x00 RETURN
+	let contract = from_hex("0x600060005360016000f3").unwrap();
+
+	new_test_ext().execute_with(|| {
+		let result = Runner::<Test>::create(alice(), contract, 0, 1000000, 1000000, <Test as Config>::config()).unwrap();
+		let address = result.address;
+
+		assert_eq!(reserved_balance(address), 1000);
+		let event = Event::EVM(crate::Event::StorageDepositCharged(alice(), address, 1000));
+		assert!(System::events().iter().any(|record| record.event == event));
+	});
+}
+
+#[test]
+fn should_use_base_fee_as_gas_price() {
+	// GASPRICE PUSH1 0x00 MSTORE BASEFEE PUSH1 0x20 MSTORE PUSH1 0x40 PUSH1 0x00 RETURN
+	let contract = from_hex("0x3a6000524860205260406000f3").unwrap();
+
+	new_test_ext().execute_with(|| {
+		set_base_fee(U256::from(7));
+		assert_ok!(EVM::set_hard_fork(
+			Origin::signed(CouncilAccount::get()),
+			HardFork::London,
+			0
+		));
+
+		let result = Runner::<Test>::create(alice(), contract, 0, 1000000, 1000000, <Test as Config>::config()).unwrap();
+		assert_eq!(result.exit_reason, ExitReason::Succeed(ExitSucceed::Returned));
+		assert_eq!(
+			EVM::code_at_address(&result.address),
+			[H256::from_low_u64_be(7).as_bytes(), H256::from_low_u64_be(7).as_bytes()].concat()
+		);
+
+		set_base_fee(U256::one());
+	});
+}
+
+#[test]
+fn should_warm_access_list_before_call() {
+	// PUSH1 0x00 SLOAD PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN
+	let contract = from_hex("0x600b600c600039600b6000f360005460005260206000f3").unwrap();
+
+	new_test_ext().execute_with(|| {
+		let address = Runner::<Test>::create(alice(), contract, 0, 1000000, 1000000, <Test as Config>::config())
+			.unwrap()
+			.address;
+
+		#[cfg(not(feature = "with-ethereum-compatibility"))]
+		deploy_free(address);
+
+		let access_list = vec![AccessListItem {
+			address,
+			storage_keys: vec![H256::zero()],
+		}];
+		let call_with_access_list = |access_list: Vec<AccessListItem>| {
+			Runner::<Test>::call_with_access_list(
+				alice(),
+				alice(),
+				address,
+				Vec::new(),
+				0,
+				1000000,
+				1000000,
+				access_list,
+				<Test as Config>::config(),
+			)
+		};
+
+		assert_eq!(
+			call_with_access_list(access_list.clone()),
+			Err(Error::<Test>::AccessListNotSupported.into())
+		);
+		assert_eq!(
+			Runner::<Test>::create_access_list(
+				alice(),
+				Some(address),
+				Vec::new(),
+				0,
+				1000000,
+				1000000,
+				<Test as Config>::config()
+			),
+			Err(Error::<Test>::AccessListNotSupported.into())
+		);
+
+		assert_ok!(EVM::set_hard_fork(
+			Origin::signed(CouncilAccount::get()),
+			HardFork::Berlin,
+			0
+		));
+
+		let without = call_with_access_list(Vec::new()).unwrap();
+		let with = call_with_access_list(access_list.clone()).unwrap();
+		assert_eq!(with.exit_reason, ExitReason::Succeed(ExitSucceed::Returned));
+		// 2400 + 1900 intrinsic cost, minus the 2000 saved on the cold SLOAD
+		assert_eq!(with.used_gas, without.used_gas + U256::from(2300));
+
+		let info = Runner::<Test>::create_access_list(
+			alice(),
+			Some(address),
+			Vec::new(),
+			0,
+			1000000,
+			1000000,
+			<Test as Config>::config(),
+		)
+		.unwrap();
+		assert_eq!(info.access_list, access_list);
+		assert_eq!(info.used_gas, with.used_gas);
+	});
+}
+
+#[test]
+fn should_page_through_contract_storage() {
+	// PUSH1 0x00 PUSH1 0x00 MSTORE8 PUSH1 0x01 PUSH1 0x00 RETURN
+	let contract = from_hex("0x600060005360016000f3").unwrap();
+
+	new_test_ext().execute_with(|| {
+		let address = Runner::<Test>::create(alice(), contract, 0, 1000000, 1000000, <Test as Config>::config())
+			.unwrap()
+			.address;
+
+		let mut expected = (1..=5u64)
+			.map(|i| (H256::from_low_u64_be(i), H256::from_low_u64_be(i * 10)))
+			.collect::<Vec<_>>();
+		for (index, value) in expected.iter() {
+			AccountStorages::<Test>::insert(address, index, value);
+		}
+		// storage of other accounts is not included
+		AccountStorages::<Test>::insert(bob(), H256::zero(), H256::from_low_u64_be(1));
+
+		let mut storage = Vec::new();
+		let mut pages = 0;
+		let mut start = None;
+		loop {
+			let range = EVM::storage_range(&address, start, 2);
+			assert_eq!(range.code_info, Some(CodeInfo { code_size: 1, ref_count: 1 }));
+			assert_eq!(range.storage_size, EVM::contract_storage_size(address));
+			assert!(range.storage.len() <= 2);
+
+			storage.extend(range.storage);
+			pages += 1;
+			match range.next_key {
+				Some(next_key) => start = Some(next_key),
+				None => break,
+			}
+		}
+
+		assert_eq!(pages, 3);
+		storage.sort();
+		expected.sort();
+		assert_eq!(storage, expected);
+
+		assert_eq!(EVM::storage_range(&bob(), None, 10).storage.len(), 1);
+		assert_eq!(EVM::storage_range(&bob(), None, 10).code_info, None);
+		assert_eq!(EVM::storage_range(&address, None, 0).storage.len(), 1);
+	});
+}
+
+#[test]
+fn should_upgrade_and_rollback_deployed_contract() {
+	// PUSH1 0x00 PUSH1 0x00 MSTORE8 PUSH1 0x01 PUSH1 0x00 RETURN
+	let contract = from_hex("0x600060005360016000f3").unwrap();
+	let v1_hash = code_hash(&[0x00]);
+	let v2: Vec<u8> = vec![0x01, 0x02];
+	let v2_hash = code_hash(&v2);
+	let v3: Vec<u8> = vec![0x03, 0x03, 0x03];
+	let v3_hash = code_hash(&v3);
+
+	new_test_ext().execute_with(|| {
+		let alice_account_id = <Test as Config>::AddressMapping::get_account_id(&alice());
+		let bob_account_id = <Test as Config>::AddressMapping::get_account_id(&bob());
+		let governance = Origin::signed(NetworkContractAccount::get());
+
+		let address = Runner::<Test>::create(alice(), contract, 0, 1000000, 1000000, <Test as Config>::config())
+			.unwrap()
+			.address;
+		let storage_size = EVM::contract_storage_size(address);
+		let history = || {
+			EVM::code_history(address)
+				.into_iter()
+				.map(|entry| entry.code_hash)
+				.collect::<Vec<_>>()
+		};
+
+		assert_noop!(
+			EVM::propose_upgrade(Origin::signed(alice_account_id.clone()), address, v2.clone()),
+			Error::<Test>::ContractNotDeployed
+		);
+		deploy_free(address);
+		let reserved = Balances::reserved_balance(&alice_account_id);
+		let contract_account_id = <Test as Config>::AddressMapping::get_account_id(&address);
+		let storage_deposit = EVM::storage_deposit(address);
+		let contract_reserved = Balances::reserved_balance(&contract_account_id);
+		assert_noop!(
+			EVM::propose_upgrade(Origin::signed(bob_account_id.clone()), address, v2.clone()),
+			Error::<Test>::NoPermission
+		);
+
+		// upgrade after the delay
+		assert_ok!(EVM::propose_upgrade(
+			Origin::signed(alice_account_id.clone()),
+			address,
+			v2.clone()
+		));
+		assert_eq!(
+			EVM::pending_upgrades(address),
+			Some(PendingUpgrade {
+				code_hash: v2_hash,
+				enact_at: 11,
+				depositor: alice_account_id.clone(),
+				deposit: 20,
+			})
+		);
+		assert_eq!(Balances::reserved_balance(&alice_account_id), reserved + 20);
+		assert_eq!(EVM::code_infos(v2_hash).map(|info| info.ref_count), Some(1));
+		assert_noop!(
+			EVM::propose_upgrade(Origin::signed(alice_account_id.clone()), address, v3.clone()),
+			Error::<Test>::UpgradeAlreadyPending
+		);
+		assert_noop!(
+			EVM::enact_upgrade(Origin::signed(bob_account_id.clone()), address),
+			Error::<Test>::UpgradeTimelocked
+		);
+
+		System::set_block_number(11);
+		assert_ok!(EVM::enact_upgrade(Origin::signed(bob_account_id.clone()), address));
+		assert_eq!(EVM::pending_upgrades(address), None);
+		assert_eq!(EVM::code_at_address(&address), v2);
+		assert_eq!(history(), vec![v1_hash]);
+		assert_eq!(EVM::contract_storage_size(address), storage_size + 1);
+		// the upgrade deposit covers the new code, the deposit of the
+		// replaced code stays reserved with it in the history
+		assert_eq!(Balances::reserved_balance(&alice_account_id), reserved);
+		assert_eq!(EVM::storage_deposit(address), storage_deposit + 10);
+		assert_eq!(
+			Balances::reserved_balance(&contract_account_id),
+			contract_reserved + 10 + 10
+		);
+		let event = Event::EVM(crate::Event::ContractUpgraded(address, v2_hash));
+		assert!(System::events().iter().any(|record| record.event == event));
+
+		// governance approves without waiting for the delay
+		assert_ok!(EVM::propose_upgrade(
+			Origin::signed(alice_account_id.clone()),
+			address,
+			v3.clone()
+		));
+		assert_noop!(
+			EVM::approve_upgrade(Origin::signed(bob_account_id.clone()), address),
+			BadOrigin
+		);
+		assert_ok!(EVM::approve_upgrade(governance.clone(), address));
+		assert_eq!(EVM::code_at_address(&address), v3);
+		assert_eq!(history(), vec![v1_hash, v2_hash]);
+		assert_eq!(Balances::reserved_balance(&alice_account_id), reserved);
+		assert_eq!(EVM::storage_deposit(address), storage_deposit + 20);
+
+		// roll back to the first version
+		assert_noop!(
+			EVM::rollback_contract(governance.clone(), address, H256::zero()),
+			Error::<Test>::CodeNotInHistory
+		);
+		assert_ok!(EVM::rollback_contract(governance.clone(), address, v1_hash));
+		assert_eq!(EVM::code_at_address(&address), vec![0x00]);
+		assert_eq!(history(), vec![v2_hash, v3_hash]);
+		assert_eq!(EVM::contract_storage_size(address), storage_size);
+		assert_eq!(EVM::storage_deposit(address), storage_deposit);
+
+		// only `MaxCodeHistory` versions are kept
+		assert_ok!(EVM::propose_upgrade(
+			Origin::signed(alice_account_id.clone()),
+			address,
+			v2.clone()
+		));
+		assert_ok!(EVM::approve_upgrade(governance.clone(), address));
+		assert_eq!(history(), vec![v3_hash, v1_hash]);
+		assert_eq!(EVM::code_infos(v2_hash).map(|info| info.ref_count), Some(1));
+		// the deposit of the pruned version is released
+		assert_eq!(Balances::reserved_balance(&alice_account_id), reserved);
+		assert_eq!(EVM::storage_deposit(address), storage_deposit + 10);
+		assert_eq!(
+			Balances::reserved_balance(&contract_account_id),
+			contract_reserved + 10 + 30 + 10
+		);
+
+		// canceled upgrades release their code
+		let v4: Vec<u8> = vec![0x04];
+		assert_ok!(EVM::propose_upgrade(
+			Origin::signed(alice_account_id.clone()),
+			address,
+			v4.clone()
+		));
+		assert_noop!(
+			EVM::cancel_upgrade(Origin::signed(bob_account_id), address),
+			Error::<Test>::NoPermission
+		);
+		assert_eq!(Balances::reserved_balance(&alice_account_id), reserved + 10);
+		assert_ok!(EVM::cancel_upgrade(Origin::signed(alice_account_id.clone()), address));
+		assert_eq!(EVM::pending_upgrades(address), None);
+		assert_eq!(Balances::reserved_balance(&alice_account_id), reserved);
+		assert_eq!(EVM::code_infos(code_hash(&v4)), None);
+		assert_noop!(
+			EVM::cancel_upgrade(governance, address),
+			Error::<Test>::UpgradeNotFound
+		);
+	});
+}
+
+#[test]
+fn upgraded_contract_should_stay_collateralised() {
+	// PUSH1 0x00 PUSH1 0x00 MSTORE8 PUSH1 0x01 PUSH1 0x00 RETURN
+	let contract = from_hex("0x600060005360016000f3").unwrap();
+
+	new_test_ext().execute_with(|| {
+		let alice_account_id = <Test as Config>::AddressMapping::get_account_id(&alice());
+		let signer = <Test as Config>::AddressMapping::get_account_id(&bob());
+		let address = Runner::<Test>::create(alice(), contract, 0, 1000000, 1000000, <Test as Config>::config())
+			.unwrap()
+			.address;
+		deploy_free(address);
+
+		assert_ok!(EVM::propose_upgrade(
+			Origin::signed(alice_account_id),
+			address,
+			vec![0x01; 50]
+		));
+		System::set_block_number(11);
+		assert_ok!(EVM::enact_upgrade(Origin::signed(signer.clone()), address));
+
+		assert_eq!(EVM::storage_deposit(&address), EVM::required_storage_deposit(&address));
+		assert!(!EVM::is_under_collateralised(&address));
+		assert_noop!(
+			EVM::check_storage_rent(Origin::signed(signer), address),
+			Error::<Test>::ContractCollateralised
+		);
+	});
+}
+
+#[test]
+fn should_enforce_deployment_policy() {
+	// PUSH1 0x00 PUSH1 0x00 MSTORE8 PUSH1 0x01 PUSH1 0x00 RETURN
+	let contract = from_hex("0x600060005360016000f3").unwrap();
+
+	new_test_ext().execute_with(|| {
+		let alice_account_id = <Test as Config>::AddressMapping::get_account_id(&alice());
+		let bob_account_id = <Test as Config>::AddressMapping::get_account_id(&bob());
+		let policy = DeploymentPolicy {
+			allow_list_enabled: true,
+			quota: 2,
+			quota_period: 10,
+			fee_per_byte: 5,
+		};
+
+		assert_noop!(
+			EVM::set_deployment_policy(Origin::signed(alice_account_id.clone()), policy.clone()),
+			BadOrigin
+		);
+		// a quota without a period would renew on every deployment
+		assert_noop!(
+			EVM::set_deployment_policy(
+				Origin::signed(CouncilAccount::get()),
+				DeploymentPolicy {
+					quota_period: 0,
+					..policy.clone()
+				}
+			),
+			Error::<Test>::InvalidDeploymentPolicy
+		);
+		assert_ok!(EVM::set_deployment_policy(
+			Origin::signed(CouncilAccount::get()),
+			policy
+		));
+		assert_ok!(EVM::set_deployer_allowed(
+			Origin::signed(CouncilAccount::get()),
+			alice(),
+			true
+		));
+
+		// bob is not on the allow-list
+		assert_noop!(
+			EVM::create(Origin::signed(bob_account_id), contract.clone(), 0, 1000000, 1000000),
+			Error::<Test>::DeployerNotAllowed
+		);
+
+		// alice creates her quota within the period
+		assert_ok!(EVM::create(
+			Origin::signed(alice_account_id.clone()),
+			contract.clone(),
+			0,
+			1000000,
+			1000000
+		));
+		assert_ok!(EVM::create2(
+			Origin::signed(alice_account_id.clone()),
+			contract.clone(),
+			H256::zero(),
+			0,
+			1000000,
+			1000000
+		));
+		assert_noop!(
+			EVM::create(Origin::signed(alice_account_id.clone()), contract.clone(), 0, 1000000, 1000000),
+			Error::<Test>::DeploymentQuotaExceeded
+		);
+
+		// the quota renews with the next period
+		System::set_block_number(11);
+		assert_ok!(EVM::create(
+			Origin::signed(alice_account_id.clone()),
+			contract.clone(),
+			0,
+			1000000,
+			1000000
+		));
+		assert_eq!(EVM::deployment_quotas(alice()), (11, 1));
+
+		// the deployment fee scales with the code size
+		let address = Runner::<Test>::create(alice(), contract, 0, 1000000, 1000000, <Test as Config>::config())
+			.unwrap()
+			.address;
+		assert_eq!(EVM::deployment_fee(&address), DeploymentFee::get() + 5);
+		let balance_before = balance(alice());
+		assert_ok!(EVM::deploy(Origin::signed(alice_account_id), address));
+		assert_eq!(balance(alice()), balance_before - DeploymentFee::get() - 5);
+
+		assert_ok!(EVM::set_deployer_allowed(
+			Origin::signed(CouncilAccount::get()),
+			alice(),
+			false
+		));
+		assert!(!EVM::is_allowed_deployer(alice()));
+	});
+}
+
+#[test]
+fn should_enforce_deployment_policy_on_contract_creates() {
+	// deploys a factory, which creates the contract
+	// PUSH1 0x00 PUSH1 0x00 MSTORE8 PUSH1 0x01 PUSH1 0x00 RETURN
+	// on each call and returns its address
+	let factory = from_hex(
+		"0x601d600c600039601d6000f369600060005360016000f3600052600a60166000f060005260206000f3",
+	)
+	.unwrap();
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(EVM::set_deployment_policy(
+			Origin::signed(CouncilAccount::get()),
+			DeploymentPolicy {
+				allow_list_enabled: false,
+				quota: 1,
+				quota_period: 10,
+				fee_per_byte: 0,
+			}
+		));
+		let factory = Runner::<Test>::create(alice(), factory, 0, 1000000, 1000000, <Test as Config>::config())
+			.unwrap()
+			.address;
+		let create = || {
+			let result =
+				Runner::<Test>::call(alice(), alice(), factory, vec![], 0, 1000000, 1000000, <Test as Config>::config())
+					.unwrap();
+			assert_eq!(result.exit_reason, ExitReason::Succeed(ExitSucceed::Returned));
+			H160::from_slice(&result.output[12..])
+		};
+
+		// contracts created by contracts count against the quota of the origin
+		let created = create();
+		assert_ne!(created, H160::zero());
+		assert_eq!(EVM::code_at_address(&created), vec![0x00]);
+		assert_eq!(EVM::deployment_quotas(alice()), (0, 1));
+
+		assert_eq!(create(), H160::zero());
+		assert_eq!(EVM::deployment_quotas(alice()), (0, 1));
+	});
+}
+
+#[test]
+fn should_dispatch_sponsored_call() {
+	// init code returning `PUSH1 0x01 PUSH1 0x00 SSTORE STOP`
+	let contract = from_hex("0x6006600c60003960066000f3600160005500").unwrap();
+	let secret = secp256k1::SecretKey::parse(&[7u8; 32]).unwrap();
+	let public = secp256k1::PublicKey::from_secret_key(&secret);
+	let user = H160::from_slice(&sp_io::hashing::keccak_256(&public.serialize()[1..65])[12..]);
+
+	let sign = |meta_call: &meta_call::MetaCall| {
+		let message = secp256k1::Message::parse(&meta_call.signing_hash(ChainId::get()));
+		let (signature, recovery_id) = secp256k1::sign(&message, &secret);
+		let mut raw = [0u8; 65];
+		raw[0..64].copy_from_slice(&signature.serialize()[..]);
+		raw[64] = recovery_id.serialize();
+		sp_core::ecdsa::Signature::from_raw(raw)
+	};
+
+	new_test_ext().execute_with(|| {
+		let bob_account_id = <Test as Config>::AddressMapping::get_account_id(&bob());
+		let address = Runner::<Test>::create(alice(), contract, 0, 1000000, 1000000, <Test as Config>::config())
+			.unwrap()
+			.address;
+		deploy_free(address);
+
+		let meta_call = meta_call::MetaCall {
+			from: user,
+			to: address,
+			input: vec![],
+			value: U256::zero(),
+			gas_limit: 100000,
+			storage_limit: 100,
+			nonce: U256::zero(),
+			valid_until: 10,
+		};
+		let signature = sign(&meta_call);
+		let sponsored_call = |signature: sp_core::ecdsa::Signature| {
+			EVM::sponsored_call(
+				Origin::signed(bob_account_id.clone()),
+				user,
+				address,
+				vec![],
+				0,
+				100000,
+				100,
+				10,
+				signature,
+			)
+		};
+
+		// bob does not sponsor calls to the contract yet
+		assert_noop!(sponsored_call(signature.clone()), Error::<Test>::SponsorCapExceeded);
+		assert_ok!(EVM::set_sponsor_cap(
+			Origin::signed(bob_account_id.clone()),
+			address,
+			200000
+		));
+
+		// signed by another account
+		let other = sign(&meta_call::MetaCall {
+			storage_limit: 200,
+			..meta_call.clone()
+		});
+		assert_noop!(sponsored_call(other), Error::<Test>::InvalidSignature);
+
+		let bob_balance = balance(bob());
+		let storage_size = EVM::contract_storage_size(address);
+		assert_ok!(sponsored_call(signature.clone()));
+
+		// the user is the sender, bob pays the storage deposit
+		assert_eq!(EVM::account_storages(address, H256::zero()), H256::from_low_u64_be(1));
+		assert_eq!(EVM::account_basic(&user).nonce, U256::one());
+		let storage_deposit = (EVM::contract_storage_size(address) - storage_size) as u64 * StorageDepositPerByte::get();
+		assert_eq!(balance(bob()), bob_balance - storage_deposit);
+		// the mock charges no transaction fee, the cap is debited with the deposit
+		assert_eq!(
+			EVM::sponsor_caps(&bob_account_id, &address),
+			Some(200000 - storage_deposit)
+		);
+		assert_eq!(EVM::storage_deposit_payer(), None);
+
+		// the nonce protects against replays
+		assert_noop!(sponsored_call(signature), Error::<Test>::InvalidSignature);
+
+		System::set_block_number(11);
+		let signature = sign(&meta_call::MetaCall {
+			nonce: U256::one(),
+			..meta_call
+		});
+		assert_noop!(sponsored_call(signature), Error::<Test>::MetaCallExpired);
+	});
+}
+
+#[test]
+fn should_pay_call_fees_from_gas_tank() {
+	// PUSH1 0x00 PUSH1 0x00 MSTORE8 PUSH1 0x01 PUSH1 0x00 RETURN
+	let contract = from_hex("0x600060005360016000f3").unwrap();
+
+	new_test_ext().execute_with(|| {
+		let alice_account_id = <Test as Config>::AddressMapping::get_account_id(&alice());
+		let bob_account_id = <Test as Config>::AddressMapping::get_account_id(&bob());
+		let address = Runner::<Test>::create(alice(), contract, 0, 1000000, 1000000, <Test as Config>::config())
+			.unwrap()
+			.address;
+		deploy_free(address);
+		let contract_account_id = <Test as Config>::AddressMapping::get_account_id(&address);
+		let call = mock::Call::EVM(crate::Call::call(address, vec![], 0, 100000, 0));
+		let sponsor_fee = |call: &mock::Call| EVM::sponsor_fee(&bob_account_id, call, 100);
+
+		assert_eq!(sponsor_fee(&call), None);
+
+		assert_noop!(
+			EVM::fund_gas_tank(Origin::signed(bob_account_id.clone()), address, 1000),
+			Error::<Test>::NoPermission
+		);
+		assert_noop!(
+			EVM::set_gas_tank_limit(Origin::signed(alice_account_id.clone()), address, 2, 10),
+			Error::<Test>::GasTankNotFound
+		);
+
+		let reserved = reserved_balance(address);
+		assert_ok!(EVM::fund_gas_tank(
+			Origin::signed(alice_account_id.clone()),
+			address,
+			1000
+		));
+		assert_eq!(reserved_balance(address), reserved + 1000);
+		assert!(!EVM::is_under_collateralised(&address));
+		assert_ok!(EVM::set_gas_tank_limit(
+			Origin::signed(alice_account_id.clone()),
+			address,
+			2,
+			10
+		));
+
+		// the tank pays two calls of bob per period
+		assert_eq!(sponsor_fee(&call), Some(contract_account_id.clone()));
+		assert_eq!(sponsor_fee(&call), Some(contract_account_id.clone()));
+		assert_eq!(sponsor_fee(&call), None);
+		assert_eq!(EVM::gas_tank_usage(address, bob()), (0, 2));
+		assert_eq!(EVM::gas_tanks(&address).unwrap().balance, 800);
+		assert_eq!(reserved_balance(address), reserved + 800);
+
+		// the unused fees go back to the tank
+		EVM::refund_sponsor(&contract_account_id, 50);
+		assert_eq!(EVM::gas_tanks(&address).unwrap().balance, 850);
+		assert_eq!(reserved_balance(address), reserved + 850);
+
+		System::set_block_number(11);
+		assert_eq!(sponsor_fee(&call), Some(contract_account_id.clone()));
+		assert_eq!(EVM::gas_tank_usage(address, bob()), (11, 1));
+
+		// only calls to the contract are paid
+		let transfer_maintainer = mock::Call::EVM(crate::Call::transfer_maintainer(address, bob()));
+		assert_eq!(sponsor_fee(&transfer_maintainer), None);
+		let call_other = mock::Call::EVM(crate::Call::call(contract_a(), vec![], 0, 100000, 0));
+		assert_eq!(sponsor_fee(&call_other), None);
+
+		assert_noop!(
+			EVM::withdraw_gas_tank(Origin::signed(alice_account_id.clone()), address, 751),
+			Error::<Test>::InsufficientGasTank
+		);
+		let alice_balance = balance(alice());
+		assert_ok!(EVM::withdraw_gas_tank(
+			Origin::signed(alice_account_id),
+			address,
+			750
+		));
+		assert_eq!(balance(alice()), alice_balance + 750);
+		assert_eq!(reserved_balance(address), reserved);
+	});
+}
+
+#[test]
+fn new_gas_tank_should_be_rate_limited() {
+	// PUSH1 0x00 PUSH1 0x00 MSTORE8 PUSH1 0x01 PUSH1 0x00 RETURN
+	let contract = from_hex("0x600060005360016000f3").unwrap();
+
+	new_test_ext().execute_with(|| {
+		let alice_account_id = <Test as Config>::AddressMapping::get_account_id(&alice());
+		let address = Runner::<Test>::create(alice(), contract, 0, 1000000, 1000000, <Test as Config>::config())
+			.unwrap()
+			.address;
+		deploy_free(address);
+		assert_ok!(EVM::fund_gas_tank(Origin::signed(alice_account_id), address, 1000));
+		assert_eq!(
+			EVM::gas_tanks(&address),
+			Some(GasTank {
+				balance: 1000,
+				calls_per_period: GAS_TANK_CALLS_PER_PERIOD,
+				period: GAS_TANK_PERIOD.into(),
+			})
+		);
+
+		// a caller without balance cannot drain the tank
+		let caller_account_id = AccountId32::new([0x42; 32]);
+		assert_eq!(Balances::free_balance(&caller_account_id), 0);
+		let caller = <Test as Config>::AddressMapping::get_or_create_evm_address(&caller_account_id);
+		let call = mock::Call::EVM(crate::Call::call(address, vec![], 0, 100000, 0));
+		for _ in 0..GAS_TANK_CALLS_PER_PERIOD {
+			assert!(EVM::sponsor_fee(&caller_account_id, &call, 10).is_some());
+		}
+		assert_eq!(EVM::sponsor_fee(&caller_account_id, &call, 10), None);
+		assert_eq!(EVM::gas_tank_usage(address, caller), (0, GAS_TANK_CALLS_PER_PERIOD));
+		assert_eq!(
+			EVM::gas_tanks(&address).unwrap().balance,
+			1000 - 10 * GAS_TANK_CALLS_PER_PERIOD as u64
+		);
+	});
+}
+
+#[test]
+fn should_decode_raw_transaction() {
+	// EIP-155 example transaction
+	let raw = from_hex("0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83").unwrap();
+	let transaction = transaction::EthereumTransaction::decode(&raw).unwrap();
+
+	assert_eq!(transaction.chain_id, Some(1));
+	assert_eq!(transaction.nonce, U256::from(9));
+	assert_eq!(transaction.gas_price, U256::from(20_000_000_000u64));
+	assert_eq!(transaction.gas_limit, U256::from(21000));
+	assert_eq!(
+		transaction.action,
+		transaction::TransactionAction::Call(H160::repeat_byte(0x35))
+	);
+	assert_eq!(transaction.value, U256::from(1_000_000_000_000_000_000u64));
+	assert_eq!(
+		transaction.sender(),
+		Some(H160::from_str("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f").unwrap())
+	);
+
+	assert!(transaction::EthereumTransaction::decode(&raw[1..]).is_err());
+	assert!(transaction::EthereumTransaction::decode(&[0x03, 0xc0]).is_err());
+}
+
+#[test]
+fn should_transact_raw_transaction() {
+	// init code returning `PUSH1 0x01 PUSH1 0x00 SSTORE STOP`
+	let contract = from_hex("0x6006600c60003960066000f3600160005500").unwrap();
+	let secret = secp256k1::SecretKey::parse(&[7u8; 32]).unwrap();
+	let public = secp256k1::PublicKey::from_secret_key(&secret);
+	let user = H160::from_slice(&sp_io::hashing::keccak_256(&public.serialize()[1..65])[12..]);
+
+	// legacy EIP-155 transaction calling `to`
+	let sign = |nonce: u64, to: H160| {
+		let fields = |stream: &mut rlp::RlpStream| {
+			stream.append(&nonce);
+			stream.append(&1u8);
+			stream.append(&100000u64);
+			stream.append(&to);
+			stream.append(&0u8);
+			stream.append(&Vec::<u8>::new());
+		};
+		let mut unsigned = rlp::RlpStream::new_list(9);
+		fields(&mut unsigned);
+		unsigned.append(&ChainId::get());
+		unsigned.append(&0u8);
+		unsigned.append(&0u8);
+
+		let message = secp256k1::Message::parse(&sp_io::hashing::keccak_256(&unsigned.out()));
+		let (signature, recovery_id) = secp256k1::sign(&message, &secret);
+		let signature = signature.serialize();
+		let mut signed = rlp::RlpStream::new_list(9);
+		fields(&mut signed);
+		signed.append(&(ChainId::get() * 2 + 35 + recovery_id.serialize() as u64));
+		signed.append(&U256::from_big_endian(&signature[0..32]));
+		signed.append(&U256::from_big_endian(&signature[32..64]));
+		signed.out().to_vec()
+	};
+	let validate = |raw: &Vec<u8>| {
+		<EVM as ValidateUnsigned>::validate_unsigned(
+			TransactionSource::External,
+			&crate::Call::<Test>::transact(raw.clone()),
+		)
+	};
+
+	new_test_ext().execute_with(|| {
+		let address = Runner::<Test>::create(alice(), contract.clone(), 0, 1000000, 1000000, <Test as Config>::config())
+			.unwrap()
+			.address;
+		deploy_free(address);
+		let undeployed = Runner::<Test>::create(alice(), contract, 0, 1000000, 1000000, <Test as Config>::config())
+			.unwrap()
+			.address;
+
+		let transaction = sign(0, address);
+		let next_transaction = sign(1, address);
+
+		let who = <Test as Config>::AddressMapping::get_account_id(&user);
+		let _ = <Test as Config>::Currency::deposit_creating(&who, INITIAL_BALANCE);
+
+		assert!(validate(&transaction).unwrap().requires.is_empty());
+		// transactions ahead of the nonce wait for the previous ones
+		assert_eq!(validate(&next_transaction).unwrap().requires.len(), 1);
+		assert_eq!(
+			<EVM as ValidateUnsigned>::pre_dispatch(&crate::Call::<Test>::transact(next_transaction.clone())),
+			Err(InvalidTransaction::Future.into())
+		);
+		assert_noop!(EVM::transact(Origin::signed(who), transaction.clone()), BadOrigin);
+
+		assert_ok!(EVM::transact(Origin::none(), transaction.clone()));
+		assert_eq!(EVM::account_storages(address, H256::zero()), H256::from_low_u64_be(1));
+		assert_eq!(EVM::account_basic(&user).nonce, U256::one());
+		let event = Event::EVM(crate::Event::Transacted(user, transaction::transaction_hash(&transaction)));
+		assert!(System::events().iter().any(|record| record.event == event));
+
+		// replays are stale
+		assert_eq!(validate(&transaction), Err(InvalidTransaction::Stale.into()));
+		assert_noop!(
+			EVM::transact(Origin::none(), transaction),
+			Error::<Test>::InvalidNonce
+		);
+		assert!(validate(&next_transaction).unwrap().requires.is_empty());
+		assert_ok!(EVM::transact(Origin::none(), next_transaction));
+
+		// failing executions are rejected up front
+		let failing_transaction = sign(2, undeployed);
+		assert_eq!(validate(&failing_transaction), Err(InvalidTransaction::Call.into()));
+		assert_eq!(
+			<EVM as ValidateUnsigned>::pre_dispatch(&crate::Call::<Test>::transact(failing_transaction.clone())),
+			Err(InvalidTransaction::Call.into())
+		);
+
+		// and still pay the fee and use the nonce when dispatched
+		assert!(EVM::transact(Origin::none(), failing_transaction.clone()).is_err());
+		assert_eq!(EVM::account_basic(&user).nonce, U256::from(3));
+		assert_eq!(EVM::account_storages(undeployed, H256::zero()), H256::zero());
+	});
+}
+
+#[test]
+fn should_store_ethereum_header() {
+	// init code returning `PUSH1 0x00 PUSH1 0x00 LOG0 STOP`
+	let contract = from_hex("0x6006600c60003960066000f360006000a000").unwrap();
+
+	new_test_ext().execute_with(|| {
+		let alice_account_id = <Test as Config>::AddressMapping::get_account_id(&alice());
+		let address = Runner::<Test>::create(alice(), contract, 0, 1000000, 1000000, <Test as Config>::config())
+			.unwrap()
+			.address;
+		deploy_free(address);
+		assert_ok!(EVM::call(Origin::signed(alice_account_id), address, vec![], 0, 100000, 0));
+
+		let gas_used = EVM::block_gas_used();
+		assert!(gas_used > 0);
+		assert!(EVM::block_logs_bloom().contains_input(BloomInput::Raw(address.as_bytes())));
+		assert_eq!(EVM::ethereum_headers(1), None);
+
+		EVM::on_finalize(1);
+		let header = EVM::ethereum_headers(1).unwrap();
+		assert_eq!(header.number, 1);
+		assert_eq!(header.parent_hash, System::parent_hash());
+		assert_eq!(header.gas_used, gas_used);
+		assert!(header.logs_bloom.contains_input(BloomInput::Raw(address.as_bytes())));
+		assert!(!header.logs_bloom.contains_input(BloomInput::Raw(bob().as_bytes())));
+
+		// the next block starts empty
+		assert_eq!(EVM::block_gas_used(), 0);
+		assert_eq!(EVM::block_logs_bloom(), Bloom::default());
+
+		// only the recent headers are kept
+		EVM::on_finalize(2);
+		assert_eq!(EVM::ethereum_headers(2).unwrap().number, 2);
+		EVM::on_finalize(1 + ETHEREUM_HEADERS_KEPT as u64);
+		assert!(EVM::ethereum_headers(1).is_none());
+		assert!(EVM::ethereum_headers(2).is_some());
+	});
+}
+
+#[test]
+fn should_apply_dev_operations() {
+	// `PUSH1 0x00 SLOAD PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN`
+	let code = from_hex("0x60005460005260206000f3").unwrap();
+	let address = H160::from_str("2000000000000000000000000000000000000002").unwrap();
+	let state = DevOperation::SetState(StateOverride {
+		address,
+		balance: Some(1000),
+		code: Some(code.clone()),
+		state_diff: vec![(H256::zero(), H256::from_low_u64_be(0x2a))],
+		..Default::default()
+	});
+
+	new_test_ext().execute_with(|| {
+		assert_eq!(
+			<EVM as ValidateUnsigned>::validate_unsigned(
+				TransactionSource::Local,
+				&crate::Call::<Test>::dev_operation(state.clone(), 0),
+			),
+			InvalidTransaction::Call.into()
+		);
+		assert_noop!(
+			EVM::dev_operation(Origin::none(), state.clone(), 0),
+			Error::<Test>::DevModeDisabled
+		);
+
+		DevMode::<Test>::put(true);
+		assert!(<EVM as ValidateUnsigned>::validate_unsigned(
+			TransactionSource::Local,
+			&crate::Call::<Test>::dev_operation(state.clone(), 0),
+		)
+		.is_ok());
+		let alice_account_id = <Test as Config>::AddressMapping::get_account_id(&alice());
+		assert_noop!(EVM::dev_operation(Origin::signed(alice_account_id), state.clone(), 0), BadOrigin);
+
+		assert_ok!(EVM::dev_operation(Origin::none(), state, 0));
+		assert_eq!(balance(address), 1000);
+		assert_eq!(EVM::code_at_address(&address), code);
+		assert_eq!(EVM::account_storages(address, H256::zero()), H256::from_low_u64_be(0x2a));
+
+		// the replaced code is released
+		let new_code = from_hex("0x00").unwrap();
+		assert_ok!(EVM::dev_operation(
+			Origin::none(),
+			DevOperation::SetState(StateOverride {
+				address,
+				code: Some(new_code.clone()),
+				..Default::default()
+			}),
+			1
+		));
+		assert_eq!(EVM::code_at_address(&address), new_code);
+		assert_eq!(EVM::code_infos(code_hash(&code)), None);
+
+		assert_ok!(Timestamp::set(Origin::none(), 10_000));
+		assert_ok!(EVM::dev_operation(Origin::none(), DevOperation::IncreaseTime(5_000), 2));
+		assert_eq!(Timestamp::now(), 15_000);
+		Timestamp::on_finalize(1);
+
+		// the timestamp inherent follows the time without offset
+		EVM::on_initialize(2);
+		assert_eq!(Timestamp::now(), 10_000);
+		assert_ok!(Timestamp::set(Origin::none(), 12_000));
+		assert_eq!(Timestamp::now(), 17_000);
+		Timestamp::on_finalize(2);
+
+		assert_ok!(EVM::dev_operation(
+			Origin::none(),
+			DevOperation::SetNextBlockTimestamp(100_000),
+			3
+		));
+		EVM::on_initialize(3);
+		assert_ok!(Timestamp::set(Origin::none(), 14_000));
+		assert_eq!(Timestamp::now(), 100_000);
+		assert_eq!(EVM::time_offset(), 86_000);
+		Timestamp::on_finalize(3);
+
+		// the time of the chain never goes back
+		let past = DevOperation::SetNextBlockTimestamp(100_000);
+		assert_noop!(
+			EVM::dev_operation(Origin::none(), past.clone(), 4),
+			Error::<Test>::InvalidTimestamp
+		);
+		assert_eq!(
+			<EVM as ValidateUnsigned>::validate_unsigned(
+				TransactionSource::External,
+				&crate::Call::<Test>::dev_operation(past, 4)
+			),
+			Err(InvalidTransaction::Call.into())
+		);
+
+		// the offset is kept when the next block timestamp is already reached
+		assert_ok!(EVM::dev_operation(
+			Origin::none(),
+			DevOperation::SetNextBlockTimestamp(100_500),
+			5
+		));
+		EVM::on_initialize(4);
+		assert_ok!(Timestamp::set(Origin::none(), 15_000));
+		assert_eq!(Timestamp::now(), 101_000);
+		assert_eq!(EVM::time_offset(), 86_000);
+
+		// block number based periods see the block number offset
+		assert_eq!(DevBlockNumber::<Test>::current_block_number(), System::block_number());
+		assert_ok!(EVM::dev_operation(Origin::none(), DevOperation::IncreaseBlockNumber(1_000), 6));
+		assert_eq!(EVM::block_number_offset(), 1_000);
+		assert_eq!(DevBlockNumber::<Test>::current_block_number(), System::block_number() + 1_000);
+	});
+}
+
+#[test]
+fn genesis_should_set_maintainer_and_deployed() {
+	use frame_support::traits::GenesisBuild;
+
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let mut accounts = std::collections::BTreeMap::new();
+	accounts.insert(
+		contract_a(),
+		GenesisAccount {
+			nonce: 1,
+			balance: 0,
+			storage: Default::default(),
+			code: vec![0x00],
+			maintainer: alice(),
+			deployed: false,
+		},
+	);
+	crate::GenesisConfig::<Test> {
+		accounts,
+		dev_mode: false,
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	sp_io::TestExternalities::new(t).execute_with(|| {
+		let contract_info = EVM::contract_info(&contract_a()).unwrap();
+		assert_eq!(contract_info.maintainer, alice());
+		assert!(!contract_info.deployed);
+	});
+}
@@ -0,0 +1,175 @@
+//! Raw Ethereum transactions, as signed by Ethereum wallets: legacy
+//! (EIP-155), access list (EIP-2930) and dynamic fee (EIP-1559) ones.
+
+use primitive_types::{H160, H256, U256};
+use primitives::evm::AccessListItem;
+use rlp::{DecoderError, Rlp, RlpStream};
+use sp_io::hashing::keccak_256;
+use sp_std::{vec, vec::Vec};
+
+use crate::meta_call::recover_signer;
+
+/// EIP-2 upper bound of the `s` value of signatures, `secp256k1n / 2`.
+const SECP256K1N_HALF: U256 = U256([
+	0xdfe92f46681b20a0,
+	0x5d576e7357a4501d,
+	0xffffffffffffffff,
+	0x7fffffffffffffff,
+]);
+
+const ACCESS_LIST_TRANSACTION_TYPE: u8 = 1;
+const DYNAMIC_FEE_TRANSACTION_TYPE: u8 = 2;
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum TransactionAction {
+	Call(H160),
+	Create,
+}
+
+/// Decoded raw Ethereum transaction.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct EthereumTransaction {
+	/// `None` for legacy transactions signed without replay protection.
+	pub chain_id: Option<u64>,
+	pub nonce: U256,
+	/// Gas price, or max fee per gas of EIP-1559 transactions. There are no
+	/// priority fees, the gas price paid is the base fee.
+	pub gas_price: U256,
+	pub gas_limit: U256,
+	pub action: TransactionAction,
+	pub value: U256,
+	pub input: Vec<u8>,
+	pub access_list: Vec<AccessListItem>,
+	/// Hash signed by the sender.
+	pub signing_hash: H256,
+	/// Signature as `r ++ s ++ recovery id`.
+	pub signature: [u8; 65],
+}
+
+fn decode_action(rlp: &Rlp) -> Result<TransactionAction, DecoderError> {
+	if rlp.is_empty() {
+		Ok(TransactionAction::Create)
+	} else {
+		Ok(TransactionAction::Call(rlp.as_val()?))
+	}
+}
+
+fn decode_access_list(rlp: &Rlp) -> Result<Vec<AccessListItem>, DecoderError> {
+	rlp.iter()
+		.map(|item| {
+			Ok(AccessListItem {
+				address: item.val_at(0)?,
+				storage_keys: item.list_at(1)?,
+			})
+		})
+		.collect()
+}
+
+fn decode_signature(recovery_id: u64, r: U256, s: U256) -> Result<[u8; 65], DecoderError> {
+	if recovery_id > 1 || r.is_zero() || s.is_zero() || s > SECP256K1N_HALF {
+		return Err(DecoderError::Custom("invalid signature"));
+	}
+
+	let mut signature = [0u8; 65];
+	r.to_big_endian(&mut signature[0..32]);
+	s.to_big_endian(&mut signature[32..64]);
+	signature[64] = recovery_id as u8;
+	Ok(signature)
+}
+
+/// Hash of the first `count` items of the list, prefixed by the transaction
+/// type of typed transactions.
+fn signing_hash(rlp: &Rlp, count: usize, prefix: Option<u8>, suffix: Option<u64>) -> Result<H256, DecoderError> {
+	let mut stream = RlpStream::new_list(count + if suffix.is_some() { 3 } else { 0 });
+	for index in 0..count {
+		stream.append_raw(rlp.at(index)?.as_raw(), 1);
+	}
+	// EIP-155 replay protection
+	if let Some(chain_id) = suffix {
+		stream.append(&chain_id);
+		stream.append(&0u8);
+		stream.append(&0u8);
+	}
+
+	let mut payload = prefix.map(|prefix| vec![prefix]).unwrap_or_default();
+	payload.extend_from_slice(&stream.out());
+	Ok(H256(keccak_256(&payload)))
+}
+
+impl EthereumTransaction {
+	/// Decode a raw transaction, as sent through `eth_sendRawTransaction`.
+	pub fn decode(raw: &[u8]) -> Result<Self, DecoderError> {
+		match raw.first() {
+			Some(&ACCESS_LIST_TRANSACTION_TYPE) => Self::decode_typed(ACCESS_LIST_TRANSACTION_TYPE, &raw[1..]),
+			Some(&DYNAMIC_FEE_TRANSACTION_TYPE) => Self::decode_typed(DYNAMIC_FEE_TRANSACTION_TYPE, &raw[1..]),
+			Some(&byte) if byte >= 0xc0 => Self::decode_legacy(raw),
+			_ => Err(DecoderError::Custom("unknown transaction type")),
+		}
+	}
+
+	fn decode_legacy(raw: &[u8]) -> Result<Self, DecoderError> {
+		let rlp = Rlp::new(raw);
+		if rlp.item_count()? != 9 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		let v: u64 = rlp.val_at(6)?;
+		let (chain_id, recovery_id) = match v {
+			27 | 28 => (None, v - 27),
+			v if v >= 35 => (Some((v - 35) / 2), (v - 35) % 2),
+			_ => return Err(DecoderError::Custom("invalid signature")),
+		};
+
+		Ok(Self {
+			chain_id,
+			nonce: rlp.val_at(0)?,
+			gas_price: rlp.val_at(1)?,
+			gas_limit: rlp.val_at(2)?,
+			action: decode_action(&rlp.at(3)?)?,
+			value: rlp.val_at(4)?,
+			input: rlp.val_at(5)?,
+			access_list: Vec::new(),
+			signing_hash: signing_hash(&rlp, 6, None, chain_id)?,
+			signature: decode_signature(recovery_id, rlp.val_at(7)?, rlp.val_at(8)?)?,
+		})
+	}
+
+	fn decode_typed(transaction_type: u8, payload: &[u8]) -> Result<Self, DecoderError> {
+		let rlp = Rlp::new(payload);
+		// EIP-1559 transactions have a max priority fee before the max fee
+		let offset = if transaction_type == DYNAMIC_FEE_TRANSACTION_TYPE { 1 } else { 0 };
+		if rlp.item_count()? != 11 + offset {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		Ok(Self {
+			chain_id: Some(rlp.val_at(0)?),
+			nonce: rlp.val_at(1)?,
+			gas_price: rlp.val_at(2 + offset)?,
+			gas_limit: rlp.val_at(3 + offset)?,
+			action: decode_action(&rlp.at(4 + offset)?)?,
+			value: rlp.val_at(5 + offset)?,
+			input: rlp.val_at(6 + offset)?,
+			access_list: decode_access_list(&rlp.at(7 + offset)?)?,
+			signing_hash: signing_hash(&rlp, 8 + offset, Some(transaction_type), None)?,
+			signature: decode_signature(rlp.val_at(8 + offset)?, rlp.val_at(9 + offset)?, rlp.val_at(10 + offset)?)?,
+		})
+	}
+
+	/// Gas limit of a raw transaction, zero if it does not decode.
+	pub fn gas_limit_of(raw: &[u8]) -> u64 {
+		Self::decode(raw).map_or(0, |transaction| {
+			transaction.gas_limit.min(U256::from(u64::max_value())).low_u64()
+		})
+	}
+
+	/// Recover the address which signed the transaction.
+	pub fn sender(&self) -> Option<H160> {
+		recover_signer(&self.signature, &self.signing_hash.0)
+	}
+}
+
+/// Ethereum transaction hash of a raw transaction.
+pub fn transaction_hash(raw: &[u8]) -> H256 {
+	H256(keccak_256(raw))
+}
@@ -19,7 +19,7 @@ use frame_support::{
 use sp_runtime::{Perbill, PerThing};
 use frame_support::sp_runtime::traits::{
 	Zero, Saturating,
-	CheckedAdd, CheckedDiv
+	CheckedAdd, CheckedDiv, BlockNumberProvider
 };
 use frame_system::pallet_prelude::*;
 use sp_std::prelude::*;
@@ -140,6 +140,8 @@ pub mod module {
 		type MaxMembers: Get<u32>;
 		/// The receiver of the signal for when the membership has changed.
 		type MembershipChanged: ChangeMembers<Self::AccountId>;
+		/// Block number the unbonding periods are counted with
+		type BlockNumberProvider: BlockNumberProvider<BlockNumber = Self::BlockNumber>;
 	}
 
 	#[pallet::error]
@@ -448,7 +450,7 @@ pub mod module {
 			ensure!(commitment.state == LockState::Committed, Error::<T>::NotCommitted);
 
 			// record the unbonding block number
-			let current_block: T::BlockNumber = frame_system::Pallet::<T>::block_number();
+			let current_block: T::BlockNumber = T::BlockNumberProvider::current_block_number();
 			commitment.state = LockState::Unbonding(current_block);
 
 			<Commitments<T>>::insert(&origin, commitment.clone());
@@ -476,7 +478,7 @@ pub mod module {
 					LockDuration::TenYears => 3650,
 				} * primitives::time::DAYS;
 				let lock_period: T::BlockNumber = lock_period.into();
-				let current_block: T::BlockNumber = frame_system::Pallet::<T>::block_number();
+				let current_block: T::BlockNumber = T::BlockNumberProvider::current_block_number();
 
 				if start_block + lock_period <= current_block {
 					// credit the user his funds
@@ -94,6 +94,7 @@ impl module_poc::Config for Runtime {
 	type MaxCandidates = TechCouncilMaxCandidates;
 	type MaxMembers = TechCouncilMaxMembers;
 	type MembershipChanged = TechCouncil;
+	type BlockNumberProvider = System;
 	type WeightInfo = ();
 }
 
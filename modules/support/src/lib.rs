@@ -108,6 +108,26 @@ pub trait TransactionPayment<AccountId, Balance, NegativeImbalance> {
 		weight: Weight,
 	) -> Result<(Balance, NegativeImbalance), TransactionValidityError>;
 	fn refund_fee(who: &AccountId, weight: Weight, payed: NegativeImbalance) -> Result<(), TransactionValidityError>;
+	/// Fee charged for the weight of a transaction, adjusted by the fee
+	/// multiplier.
+	fn adjusted_weight_fee(weight: Weight) -> Balance;
+}
+
+/// Pays the transaction fees of some calls instead of their signers.
+pub trait TransactionFeeSponsor<AccountId, Call, Balance> {
+	/// Free `fee` of `call` signed by `who` on the returned sponsor account,
+	/// which pays it. `None` if `who` pays the fee.
+	fn sponsor_fee(who: &AccountId, call: &Call, fee: Balance) -> Option<AccountId>;
+	/// Take back the unused part of a fee paid by `sponsor`.
+	fn refund_sponsor(sponsor: &AccountId, refund: Balance);
+}
+
+impl<AccountId, Call, Balance> TransactionFeeSponsor<AccountId, Call, Balance> for () {
+	fn sponsor_fee(_who: &AccountId, _call: &Call, _fee: Balance) -> Option<AccountId> {
+		None
+	}
+
+	fn refund_sponsor(_sponsor: &AccountId, _refund: Balance) {}
 }
 
 #[cfg(feature = "std")]
@@ -136,4 +156,8 @@ impl<AccountId, Balance: Default + Copy, NegativeImbalance: Imbalance<Balance>>
 	) -> Result<(), TransactionValidityError> {
 		Ok(())
 	}
+
+	fn adjusted_weight_fee(_weight: Weight) -> Balance {
+		Default::default()
+	}
 }
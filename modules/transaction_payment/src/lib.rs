@@ -30,7 +30,7 @@ use sp_runtime::{
 	FixedPointNumber, FixedPointOperand, FixedU128, Perquintill,
 };
 use sp_std::{prelude::*, vec};
-use support::{TransactionPayment};
+use support::{TransactionFeeSponsor, TransactionPayment};
 use frame_support::traits::SameOrOther;
 
 mod default_weight;
@@ -238,6 +238,13 @@ pub mod module {
 		/// block's weight.
 		type FeeMultiplierUpdate: MultiplierUpdate;
 
+		/// Pays the fees of the calls it sponsors instead of their signers.
+		type FeeSponsor: TransactionFeeSponsor<
+			Self::AccountId,
+			<Self as frame_system::Config>::Call,
+			PalletBalanceOf<Self>,
+		>;
+
 		/// Weight information for the extrinsics in this module.
 		type WeightInfo: WeightInfo;
 	}
@@ -607,13 +614,17 @@ where
 		Self(fee)
 	}
 
+	/// Withdraw the fee from `who`, or from the sponsor of the call if it
+	/// has one. Returns the fee, the payment and the sponsor.
+	#[allow(clippy::type_complexity)]
 	fn withdraw_fee(
 		&self,
 		who: &T::AccountId,
-		_call: &<T as frame_system::Config>::Call,
+		call: &<T as frame_system::Config>::Call,
 		info: &DispatchInfoOf<<T as frame_system::Config>::Call>,
 		len: usize,
-	) -> Result<(PalletBalanceOf<T>, Option<NegativeImbalanceOf<T>>), TransactionValidityError> {
+	) -> Result<(PalletBalanceOf<T>, Option<NegativeImbalanceOf<T>>, Option<T::AccountId>), TransactionValidityError>
+	{
 		let tip = self.0;
 		let fee = Pallet::<T>::compute_fee(len as u32, info, tip);
 
@@ -623,12 +634,24 @@ where
 			WithdrawReasons::TRANSACTION_PAYMENT | WithdrawReasons::TIP
 		};
 
-		log::debug!(target: "fee", "who: {:?}, fee: {:?}", who, fee);
-		Pallet::<T>::ensure_can_charge_fee(who, fee, reason);
+		// sponsors do not pay tips
+		let sponsor = if tip.is_zero() {
+			T::FeeSponsor::sponsor_fee(who, call, fee)
+		} else {
+			None
+		};
+		let payer = match sponsor.as_ref() {
+			Some(sponsor) => sponsor,
+			None => {
+				Pallet::<T>::ensure_can_charge_fee(who, fee, reason);
+				who
+			}
+		};
+		log::debug!(target: "fee", "who: {:?}, payer: {:?}, fee: {:?}", who, payer, fee);
 
 		// withdraw native currency as fee
-		match <T as Config>::Currency::withdraw(who, fee, reason, ExistenceRequirement::KeepAlive) {
-			Ok(imbalance) => Ok((fee, Some(imbalance))),
+		match <T as Config>::Currency::withdraw(payer, fee, reason, ExistenceRequirement::KeepAlive) {
+			Ok(imbalance) => Ok((fee, Some(imbalance), sponsor)),
 			Err(_) => Err(InvalidTransaction::Payment.into()),
 		}
 	}
@@ -671,11 +694,14 @@ where
 	type AccountId = T::AccountId;
 	type Call = <T as frame_system::Config>::Call;
 	type AdditionalSigned = ();
+	/// The tip, the payer, the payment, the fee and whether the payer is a
+	/// sponsor.
 	type Pre = (
 		PalletBalanceOf<T>,
 		Self::AccountId,
 		Option<NegativeImbalanceOf<T>>,
 		PalletBalanceOf<T>,
+		bool,
 	);
 
 	fn additional_signed(&self) -> sp_std::result::Result<(), TransactionValidityError> {
@@ -689,7 +715,7 @@ where
 		info: &DispatchInfoOf<Self::Call>,
 		len: usize,
 	) -> TransactionValidity {
-		let (fee, _) = self.withdraw_fee(who, call, info, len)?;
+		let (fee, _, _) = self.withdraw_fee(who, call, info, len)?;
 		Ok(ValidTransaction {
 			priority: Self::get_priority(len, info, fee),
 			..Default::default()
@@ -703,8 +729,9 @@ where
 		info: &DispatchInfoOf<Self::Call>,
 		len: usize,
 	) -> Result<Self::Pre, TransactionValidityError> {
-		let (fee, imbalance) = self.withdraw_fee(who, call, info, len)?;
-		Ok((self.0, who.clone(), imbalance, fee))
+		let (fee, imbalance, sponsor) = self.withdraw_fee(who, call, info, len)?;
+		let sponsored = sponsor.is_some();
+		Ok((self.0, sponsor.unwrap_or_else(|| who.clone()), imbalance, fee, sponsored))
 	}
 
 	fn post_dispatch(
@@ -714,12 +741,15 @@ where
 		len: usize,
 		_result: &DispatchResult,
 	) -> Result<(), TransactionValidityError> {
-		let (tip, who, imbalance, fee) = pre;
+		let (tip, who, imbalance, fee, sponsored) = pre;
 		if let Some(payed) = imbalance {
 			let actual_fee = Pallet::<T>::compute_actual_fee(len as u32, info, post_info, tip);
 			let refund = fee.saturating_sub(actual_fee);
 			let actual_payment = match <T as Config>::Currency::deposit_into_existing(&who, refund) {
 				Ok(refund_imbalance) => {
+					if sponsored {
+						T::FeeSponsor::refund_sponsor(&who, refund);
+					}
 					// The refund cannot be larger than the up front payed max weight.
 					// `PostDispatchInfo::calc_unspent` guards against such a case.
 					match payed.offset(refund_imbalance) {
@@ -803,4 +833,8 @@ where
 
 		Ok(())
 	}
+
+	fn adjusted_weight_fee(weight: Weight) -> PalletBalanceOf<T> {
+		<Pallet<T> as Convert<Weight, PalletBalanceOf<T>>>::convert(weight)
+	}
 }
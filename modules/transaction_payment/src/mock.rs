@@ -13,7 +13,7 @@ use sp_runtime::{
 	testing::Header, traits::IdentityLookup, DispatchError, DispatchResult, Perbill,
 };
 use sp_std::cell::RefCell;
-use support::{EVMBridge, InvokeContext};
+use support::{EVMBridge, InvokeContext, TransactionFeeSponsor};
 
 pub type AccountId = AccountId32;
 pub type BlockNumber = u64;
@@ -172,6 +172,25 @@ parameter_types! {
 	pub static TransactionByteFee: u128 = 1;
 }
 
+parameter_types! {
+	pub static SponsorRefund: u128 = 0;
+}
+
+/// Alice sponsors the remarks.
+pub struct MockFeeSponsor;
+impl TransactionFeeSponsor<AccountId, Call, Balance> for MockFeeSponsor {
+	fn sponsor_fee(_who: &AccountId, call: &Call, _fee: Balance) -> Option<AccountId> {
+		match call {
+			Call::System(frame_system::Call::remark(..)) => Some(ALICE),
+			_ => None,
+		}
+	}
+
+	fn refund_sponsor(_sponsor: &AccountId, refund: Balance) {
+		SPONSOR_REFUND.with(|v| *v.borrow_mut() = refund);
+	}
+}
+
 impl Config for Runtime {
 	type AllNonNativeCurrencyIds = AllNonNativeCurrencyIds;
 	type NativeCurrencyId = GetNativeCurrencyId;
@@ -182,6 +201,7 @@ impl Config for Runtime {
 	type TransactionByteFee = TransactionByteFee;
 	type WeightToFee = WeightToFee;
 	type FeeMultiplierUpdate = ();
+	type FeeSponsor = MockFeeSponsor;
 	type WeightInfo = ();
 }
 
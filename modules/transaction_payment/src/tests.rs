@@ -9,7 +9,7 @@ use frame_support::{
 };
 use mock::{
 	AccountId, BlockWeights, Call, Currencies,
-	ExtBuilder, Origin, Runtime, TransactionPayment,
+	ExtBuilder, Origin, Runtime, SponsorRefund, TransactionPayment,
 	REEF, RUSD, ALICE, BOB
 };
 use orml_traits::MultiCurrency;
@@ -87,6 +87,31 @@ fn refund_fee_according_to_actual_when_post_dispatch_and_native_currency_is_enou
 	});
 }
 
+#[test]
+fn sponsor_pays_fee_of_sponsored_calls() {
+	ExtBuilder::default().build().execute_with(|| {
+		let call = &Call::System(frame_system::Call::remark(vec![]));
+		assert_eq!(Currencies::free_balance(REEF, &BOB), 0);
+
+		let fee = 23 * 2 + 1000; // len * byte + weight
+		let pre = ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, call, &INFO, 23)
+			.unwrap();
+		assert_eq!(Currencies::free_balance(REEF, &ALICE), 100000 - fee);
+
+		let refund = 200; // 1000 - 800
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 23, &Ok(())).is_ok());
+		assert_eq!(Currencies::free_balance(REEF, &ALICE), 100000 - fee + refund);
+		assert_eq!(SponsorRefund::get(), refund);
+
+		// sponsors do not pay tips
+		assert_err!(
+			ChargeTransactionPayment::<Runtime>::from(1).validate(&BOB, call, &INFO, 23),
+			TransactionValidityError::Invalid(InvalidTransaction::Payment)
+		);
+	});
+}
+
 #[test]
 fn charges_fee_when_validate_and_native_is_not_enough() {
 	ExtBuilder::default().build().execute_with(|| {
@@ -107,6 +107,8 @@ pub fn development_config() -> Result<ChainSpec, String> {
 				get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
 				get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
 			],
+			// Dev mode
+			true,
 		),
 		// Bootnodes
 		vec![],
@@ -153,6 +155,8 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 				get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
 				get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
 			],
+			// Dev mode
+			false,
 		),
 		// Bootnodes
 		vec![],
@@ -219,6 +223,8 @@ pub fn public_testnet_config() -> Result<ChainSpec, String> {
 				hex!["9e42365c1a43fe7bd886118f49a2247aabda7079c3e4c5288f41afadd7bb1963"].into(),
 				hex!["6c1371ce4b06b8d191d6f552d716c00da31aca08a291ccbdeaf0f7aeae51201b"].into(),
 			],
+			// Dev mode
+			false,
 		),
 		// Bootnodes
 		vec!["/dns/bootnode-t1.reefscan.com/tcp/30334/p2p/12D3KooWKmFtS7BFtkkKWrP5ZcCpPFokmST2JFXFSsVBNeW5SXWg".parse().unwrap()],
@@ -328,6 +334,7 @@ fn testnet_genesis(
 	initial_authorities: Vec<(AccountId, AccountId, GrandpaId, BabeId, ImOnlineId, AuthorityDiscoveryId)>,
 	root_key: AccountId,
 	endowed_accounts: Vec<AccountId>,
+	dev_mode: bool,
 ) -> GenesisConfig {
 
 	let evm_genesis_accounts = evm_genesis();
@@ -410,6 +417,7 @@ fn testnet_genesis(
 		},
 		evm: EVMConfig {
 			accounts: evm_genesis_accounts,
+			dev_mode,
 		},
 		sudo: SudoConfig { key: root_key },
 		tech_council: Default::default(),
@@ -495,6 +503,7 @@ fn mainnet_genesis(
 		},
 		evm: EVMConfig {
 			accounts: evm_genesis_accounts,
+			dev_mode: false,
 		},
 		sudo: SudoConfig { key: root_key },
 		tech_council: Default::default(),
@@ -524,6 +533,8 @@ pub fn evm_genesis() -> BTreeMap<H160, module_evm::GenesisAccount<Balance, Nonce
 			balance: existential_deposit,
 			storage: Default::default(),
 			code: Bytes::from_str(&code_string).unwrap().0,
+			maintainer: Default::default(),
+			deployed: true,
 		};
 		let addr = H160::from_slice(
 			from_hex(address.as_str())
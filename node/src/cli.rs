@@ -1,6 +1,10 @@
 use structopt::StructOpt;
 use sc_cli::RunCmd;
 
+use crate::evm_snapshot::{EvmExportCmd, EvmImportCmd};
+use crate::fork_spec::ForkSpecCmd;
+use crate::service::Sealing;
+
 
 #[derive(Debug, StructOpt)]
 pub struct Cli {
@@ -9,6 +13,12 @@ pub struct Cli {
 
 	#[structopt(flatten)]
 	pub run: RunCmd,
+
+	/// Seal blocks of development chains without BABE and GRANDPA: `instant`,
+	/// `manual` (through `engine_createBlock`) or `interval:<ms>`. Rejected
+	/// for other chains.
+	#[structopt(long, value_name = "MODE")]
+	pub sealing: Option<Sealing>,
 }
 
 #[derive(Debug, StructOpt)]
@@ -48,6 +58,15 @@ pub enum Subcommand {
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
+	/// Export the EVM accounts of a given block, as one JSON object per line.
+	EvmExport(EvmExportCmd),
+
+	/// Add the EVM accounts exported by `evm-export` to the genesis of a chain spec.
+	EvmImport(EvmImportCmd),
+
+	/// Fork the state of a chain into a raw development chain spec.
+	ForkSpec(ForkSpecCmd),
+
 	/// Remove the whole chain.
 	PurgeChain(sc_cli::PurgeChainCmd),
 
@@ -17,6 +17,7 @@
 
 use crate::{chain_spec, service};
 use crate::cli::{Cli, Subcommand};
+use crate::fork_spec::ForkSpecCmd;
 use sc_cli::{SubstrateCli, RuntimeVersion, Role, ChainSpec};
 use sc_service::PartialComponents;
 use reef_runtime::Block;
@@ -110,6 +111,32 @@ pub fn run() -> sc_cli::Result<()> {
 				Ok((cmd.run(client, import_queue), task_manager))
 			})
 		},
+		Some(Subcommand::EvmExport(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, ..}
+					= service::new_partial(&config)?;
+				cmd.run(client)
+			})
+		},
+		Some(Subcommand::EvmImport(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run(config.chain_spec))
+		},
+		Some(Subcommand::ForkSpec(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let pairs = match &cmd.storage {
+					Some(path) => ForkSpecCmd::dump_pairs(path)?,
+					None => {
+						let PartialComponents { client, ..}
+							= service::new_partial(&config)?;
+						cmd.database_pairs(client)?
+					}
+				};
+				cmd.run(config.chain_spec, pairs)
+			})
+		},
 		Some(Subcommand::PurgeChain(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run(config.database))
@@ -134,10 +161,11 @@ pub fn run() -> sc_cli::Result<()> {
 		},
 		None => {
 			let runner = cli.create_runner(&cli.run)?;
+			let sealing = cli.sealing;
 			runner.run_node_until_exit(|config| async move {
 				match config.role {
 					Role::Light => service::new_light(config),
-					_ => service::new_full(config),
+					_ => service::new_full(config, sealing),
 				}.map_err(sc_cli::Error::Service)
 			})
 		}
@@ -0,0 +1,236 @@
+//! `evm-export` and `evm-import` subcommands, moving the EVM accounts of a
+//! chain into the genesis of another one.
+
+use std::{
+	collections::BTreeMap,
+	fs::File,
+	io::{self, BufRead, BufReader, Write},
+	path::PathBuf,
+	sync::Arc,
+};
+
+use frame_support::{
+	storage::{StorageMap, StoragePrefixedMap},
+	StorageHasher, Twox64Concat,
+};
+use reef_primitives::{AccountId, Balance, Nonce};
+use reef_runtime::{opaque::Block, Runtime};
+use sc_cli::{BlockNumberOrHash, CliConfiguration, PruningParams, SharedParams};
+use sc_client_api::{backend::Backend, StorageProvider};
+use serde::{Deserialize, Serialize};
+use sp_blockchain::HeaderBackend;
+use sp_core::{
+	storage::{StorageData, StorageKey},
+	Bytes, H160, H256,
+};
+use sp_runtime::{
+	codec::{Decode, Encode, Error as CodecError},
+	generic::BlockId,
+};
+use structopt::StructOpt;
+
+use module_evm::{AddressMapping, EvmAccountInfo, GenesisAccount};
+
+/// EVM account of a snapshot, one JSON object per line.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvmAccountSnapshot {
+	pub address: H160,
+	pub nonce: Nonce,
+	/// Free balance of the account.
+	pub balance: Balance,
+	/// Contract code, empty for other accounts.
+	pub code: Bytes,
+	pub storage: BTreeMap<H256, H256>,
+	pub maintainer: Option<H160>,
+	pub deployed: bool,
+}
+
+impl From<EvmAccountSnapshot> for GenesisAccount<Balance, Nonce> {
+	fn from(account: EvmAccountSnapshot) -> Self {
+		GenesisAccount {
+			nonce: account.nonce,
+			balance: account.balance,
+			storage: account.storage,
+			code: account.code.0,
+			maintainer: account.maintainer.unwrap_or_default(),
+			deployed: account.deployed,
+		}
+	}
+}
+
+/// The `evm-export` command, writing the EVM accounts at a block as NDJSON.
+#[derive(Debug, StructOpt)]
+pub struct EvmExportCmd {
+	/// Block hash or number to export the accounts at, the best block by default.
+	#[structopt(long = "at", value_name = "HASH or NUMBER")]
+	pub at: Option<BlockNumberOrHash>,
+
+	/// Output file, stdout by default.
+	#[structopt(long, short, parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl EvmExportCmd {
+	pub fn run<C, BE>(&self, client: Arc<C>) -> sc_cli::Result<()>
+	where
+		C: StorageProvider<Block, BE> + HeaderBackend<Block>,
+		BE: Backend<Block>,
+	{
+		let id = match &self.at {
+			Some(at) => at.parse::<Block>()?,
+			None => BlockId::Hash(client.info().best_hash),
+		};
+		log::info!("Exporting EVM accounts at {:?}", id);
+
+		let storage = |key: Vec<u8>| -> sc_cli::Result<Option<StorageData>> {
+			client
+				.storage(&id, &StorageKey(key))
+				.map_err(|err| format!("read storage failed: {:?}", err).into())
+		};
+		let decode_err = |err: CodecError| sc_cli::Error::Input(format!("invalid EVM storage: {:?}", err));
+
+		let mut output: Box<dyn Write> = match &self.output {
+			Some(path) => Box::new(File::create(path)?),
+			None => Box::new(io::stdout()),
+		};
+
+		let accounts_prefix = module_evm::Accounts::<Runtime>::final_prefix();
+		let accounts = client
+			.storage_pairs(&id, &StorageKey(accounts_prefix.to_vec()))
+			.map_err(|err| format!("read storage failed: {:?}", err))?;
+
+		let mut count = 0usize;
+		for (key, value) in accounts {
+			// `Twox64Concat` keys end with the address
+			let address = H160::from_slice(&key.0[accounts_prefix.len() + 8..]);
+			let info = EvmAccountInfo::<Runtime>::decode(&mut &value.0[..]).map_err(decode_err)?;
+
+			let account_id: AccountId = <Runtime as module_evm::Config>::AddressMapping::get_account_id(&address);
+			let balance = match storage(frame_system::Account::<Runtime>::hashed_key_for(&account_id))? {
+				Some(data) => {
+					frame_system::AccountInfo::<Nonce, <Runtime as frame_system::Config>::AccountData>::decode(
+						&mut &data.0[..],
+					)
+					.map_err(decode_err)?
+					.data
+					.free
+				}
+				None => Default::default(),
+			};
+
+			let mut code = Vec::new();
+			let mut contract_storage = BTreeMap::new();
+			if let Some(contract_info) = &info.contract_info {
+				if let Some(data) = storage(module_evm::Codes::<Runtime>::hashed_key_for(&contract_info.code_hash))? {
+					code = Vec::<u8>::decode(&mut &data.0[..]).map_err(decode_err)?;
+				}
+
+				let mut storage_prefix = module_evm::AccountStorages::<Runtime>::final_prefix().to_vec();
+				storage_prefix.extend(Twox64Concat::hash(&address.encode()));
+				let pairs = client
+					.storage_pairs(&id, &StorageKey(storage_prefix.clone()))
+					.map_err(|err| format!("read storage failed: {:?}", err))?;
+				for (key, value) in pairs {
+					// `Blake2_128Concat` keys end with the storage index
+					let index = H256::from_slice(&key.0[storage_prefix.len() + 16..]);
+					contract_storage.insert(index, H256::decode(&mut &value.0[..]).map_err(decode_err)?);
+				}
+			}
+
+			let account = EvmAccountSnapshot {
+				address,
+				nonce: info.nonce,
+				balance,
+				code: Bytes(code),
+				storage: contract_storage,
+				maintainer: info.contract_info.as_ref().map(|contract_info| contract_info.maintainer),
+				deployed: info.contract_info.as_ref().map_or(false, |contract_info| contract_info.deployed),
+			};
+			serde_json::to_writer(&mut output, &account).map_err(|err| err.to_string())?;
+			output.write_all(b"\n")?;
+			count += 1;
+		}
+
+		output.flush()?;
+		log::info!("Exported {} EVM accounts", count);
+		Ok(())
+	}
+}
+
+impl CliConfiguration for EvmExportCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+}
+
+/// The `evm-import` command, adding the EVM accounts of a snapshot to the
+/// genesis of the chain spec.
+#[derive(Debug, StructOpt)]
+pub struct EvmImportCmd {
+	/// Snapshot written by `evm-export`.
+	#[structopt(parse(from_os_str))]
+	pub input: PathBuf,
+
+	/// Output chain spec file, stdout by default.
+	#[structopt(long, short, parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl EvmImportCmd {
+	pub fn run(&self, spec: Box<dyn sc_service::ChainSpec>) -> sc_cli::Result<()> {
+		let mut accounts = BTreeMap::new();
+		for line in BufReader::new(File::open(&self.input)?).lines() {
+			let line = line?;
+			if line.trim().is_empty() {
+				continue;
+			}
+			let account: EvmAccountSnapshot = serde_json::from_str(&line).map_err(|err| err.to_string())?;
+			accounts.insert(account.address, GenesisAccount::<Balance, Nonce>::from(account));
+		}
+		log::info!("Importing {} EVM accounts into {}", accounts.len(), spec.id());
+
+		let mut json: serde_json::Value = serde_json::from_str(&spec.as_json(false)?).map_err(|err| err.to_string())?;
+		// raw chain specs hold the genesis storage instead of the genesis config
+		let genesis_accounts = json
+			.pointer_mut("/genesis/runtime/evm/accounts")
+			.and_then(|accounts| accounts.as_object_mut())
+			.ok_or_else(|| sc_cli::Error::Input("the chain spec must not be raw".into()))?;
+		for (address, account) in accounts {
+			genesis_accounts.insert(
+				format!("{:?}", address),
+				serde_json::to_value(account).map_err(|err| err.to_string())?,
+			);
+		}
+
+		let mut output: Box<dyn Write> = match &self.output {
+			Some(path) => Box::new(File::create(path)?),
+			None => Box::new(io::stdout()),
+		};
+		serde_json::to_writer_pretty(&mut output, &json).map_err(|err| err.to_string())?;
+		output.flush()?;
+		Ok(())
+	}
+}
+
+impl CliConfiguration for EvmImportCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
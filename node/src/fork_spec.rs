@@ -0,0 +1,226 @@
+//! `fork-spec` subcommand, forking the state of a chain into a raw
+//! development chain spec.
+
+use std::{
+	fs::File,
+	io::{self, BufReader, Write},
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+use reef_runtime::opaque::Block;
+use sc_cli::{BlockNumberOrHash, CliConfiguration, PruningParams, SharedParams};
+use sc_client_api::{backend::Backend, StorageProvider};
+use sp_blockchain::HeaderBackend;
+use sp_core::{bytes::to_hex, hashing::twox_128, storage::StorageKey, Bytes};
+use sp_runtime::generic::BlockId;
+use structopt::StructOpt;
+
+use crate::chain_spec;
+
+/// Storage key/value pairs of a chain, as returned by `state_getPairs`.
+pub type StoragePairs = Vec<(Bytes, Bytes)>;
+
+/// Storage prefix of the items of a module.
+fn module_prefix(module: &str) -> [u8; 16] {
+	twox_128(module.as_bytes())
+}
+
+/// Storage key of a storage value.
+fn storage_value_key(module: &str, item: &str) -> Vec<u8> {
+	[module_prefix(module), twox_128(item.as_bytes())].concat()
+}
+
+/// The `fork-spec` command, copying the state of a chain into the genesis of
+/// the development chain.
+#[derive(Debug, StructOpt)]
+pub struct ForkSpecCmd {
+	/// Block hash or number to fork the state of the local database at, the
+	/// best block by default.
+	#[structopt(long = "at", value_name = "HASH or NUMBER", conflicts_with = "storage")]
+	pub at: Option<BlockNumberOrHash>,
+
+	/// Fork the storage dump instead of the local database, a JSON array of
+	/// `[key, value]` hex pairs.
+	#[structopt(long, parse(from_os_str))]
+	pub storage: Option<PathBuf>,
+
+	/// Modules whose storage is kept from the development chain. `System`
+	/// accounts are always forked.
+	#[structopt(
+		long,
+		value_name = "MODULE",
+		use_delimiter = true,
+		default_value = "System,Session,Babe,Grandpa,GrandpaFinality,FinalityTracker,Authorship,Sudo"
+	)]
+	pub skip_modules: Vec<String>,
+
+	/// Output file, stdout by default.
+	#[structopt(long, short, parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl ForkSpecCmd {
+	/// Read the storage pairs of the local database.
+	pub fn database_pairs<C, BE>(&self, client: Arc<C>) -> sc_cli::Result<StoragePairs>
+	where
+		C: StorageProvider<Block, BE> + HeaderBackend<Block>,
+		BE: Backend<Block>,
+	{
+		let id = match &self.at {
+			Some(at) => at.parse::<Block>()?,
+			None => BlockId::Hash(client.info().best_hash),
+		};
+		log::info!("Reading the state at {:?}", id);
+
+		Ok(client
+			.storage_pairs(&id, &StorageKey(Vec::new()))
+			.map_err(|err| format!("read storage failed: {:?}", err))?
+			.into_iter()
+			.map(|(key, value)| (Bytes(key.0), Bytes(value.0)))
+			.collect())
+	}
+
+	/// Read the storage pairs of a storage dump.
+	pub fn dump_pairs(path: &Path) -> sc_cli::Result<StoragePairs> {
+		log::info!("Reading the state of {}", path.display());
+		serde_json::from_reader(BufReader::new(File::open(path)?)).map_err(|err| err.to_string().into())
+	}
+
+	pub fn run(&self, spec: Box<dyn sc_service::ChainSpec>, pairs: StoragePairs) -> sc_cli::Result<()> {
+		let skipped: Vec<[u8; 16]> = self.skip_modules.iter().map(|module| module_prefix(module)).collect();
+		let accounts_prefix = storage_value_key("System", "Account");
+
+		let forked = chain_spec::development_config()?;
+		let mut json: serde_json::Value = serde_json::from_str(&forked.as_json(true)?).map_err(|err| err.to_string())?;
+		json["name"] = format!("{}-fork", spec.name()).into();
+		json["id"] = format!("{}-fork", spec.id()).into();
+		json["protocolId"] = spec.protocol_id().into();
+
+		let top = json
+			.pointer_mut("/genesis/raw/top")
+			.and_then(|top| top.as_object_mut())
+			.ok_or_else(|| sc_cli::Error::Input("missing raw genesis storage".into()))?;
+
+		let mut count = 0usize;
+		for (key, value) in pairs {
+			// well known keys, such as `:code`, are kept from the development chain
+			if key.0.len() < 16 || key.0.starts_with(b":") {
+				continue;
+			}
+			if skipped.iter().any(|prefix| key.0.starts_with(prefix)) && !key.0.starts_with(&accounts_prefix) {
+				continue;
+			}
+			top.insert(to_hex(&key.0, false), to_hex(&value.0, false).into());
+			count += 1;
+		}
+
+		// trigger `on_runtime_upgrade`
+		top.remove(&to_hex(&storage_value_key("System", "LastRuntimeUpgrade"), false));
+		// keep the validator set from changing, `Forcing::ForceNone`
+		top.insert(to_hex(&storage_value_key("Staking", "ForceEra"), false), "0x02".into());
+		log::info!("Forked {} storage items", count);
+
+		let mut output: Box<dyn Write> = match &self.output {
+			Some(path) => Box::new(File::create(path)?),
+			None => Box::new(io::stdout()),
+		};
+		serde_json::to_writer_pretty(&mut output, &json).map_err(|err| err.to_string())?;
+		output.flush()?;
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ForkSpecCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Raw genesis storage of the spec forked from `pairs`.
+	fn fork(name: &str, pairs: StoragePairs) -> serde_json::Map<String, serde_json::Value> {
+		let output = std::env::temp_dir().join(format!("fork-spec-{}-{}.json", name, std::process::id()));
+		let cmd = ForkSpecCmd::from_iter(&["fork-spec", "--output", output.to_str().unwrap()]);
+		cmd.run(Box::new(chain_spec::development_config().unwrap()), pairs).unwrap();
+
+		let json: serde_json::Value = serde_json::from_reader(File::open(&output).unwrap()).unwrap();
+		std::fs::remove_file(&output).unwrap();
+		json["genesis"]["raw"]["top"].as_object().unwrap().clone()
+	}
+
+	fn pair(key: &[u8], value: &[u8]) -> (Bytes, Bytes) {
+		(Bytes(key.to_vec()), Bytes(value.to_vec()))
+	}
+
+	fn hex(bytes: &[u8]) -> String {
+		to_hex(bytes, false)
+	}
+
+	#[test]
+	fn should_fork_storage_of_modules() {
+		let key = storage_value_key("EVM", "NetworkContractIndex");
+		let top = fork("modules", vec![pair(&key, &[0x2a])]);
+
+		assert_eq!(top.get(&hex(&key)), Some(&hex(&[0x2a]).into()));
+	}
+
+	#[test]
+	fn should_skip_storage_of_skipped_modules() {
+		let key = storage_value_key("Sudo", "Key");
+		let development = fork("skipped-development", vec![]);
+		let top = fork("skipped", vec![pair(&key, &[0x2a; 32])]);
+
+		assert_eq!(top.get(&hex(&key)), development.get(&hex(&key)));
+		assert_ne!(top.get(&hex(&key)), Some(&hex(&[0x2a; 32]).into()));
+	}
+
+	#[test]
+	fn should_keep_system_accounts() {
+		let key = [storage_value_key("System", "Account"), vec![0x01; 48]].concat();
+		let number = storage_value_key("System", "Number");
+		let development = fork("accounts-development", vec![]);
+		let top = fork("accounts", vec![pair(&key, &[0x2a]), pair(&number, &[0x2a; 4])]);
+
+		assert_eq!(top.get(&hex(&key)), Some(&hex(&[0x2a]).into()));
+		assert_eq!(top.get(&hex(&number)), development.get(&hex(&number)));
+	}
+
+	#[test]
+	fn should_keep_well_known_keys_of_development_chain() {
+		let development = fork("well-known-development", vec![]);
+		let top = fork(
+			"well-known",
+			vec![pair(b":code", &[0x2a]), pair(b":heappages", &[0x2a]), pair(&[0x2a; 8], &[0x2a])],
+		);
+
+		assert_eq!(top.get(&hex(b":code")), development.get(&hex(b":code")));
+		assert_eq!(top.get(&hex(b":heappages")), development.get(&hex(b":heappages")));
+		assert_eq!(top.get(&hex(&[0x2a; 8])), None);
+	}
+
+	#[test]
+	fn should_override_runtime_upgrade_and_force_era() {
+		let last_runtime_upgrade = storage_value_key("System", "LastRuntimeUpgrade");
+		let force_era = storage_value_key("Staking", "ForceEra");
+		let top = fork("overrides", vec![pair(&last_runtime_upgrade, &[0x2a]), pair(&force_era, &[0x00])]);
+
+		assert_eq!(top.get(&hex(&last_runtime_upgrade)), None);
+		assert_eq!(top.get(&hex(&force_era)), Some(&"0x02".into()));
+	}
+}
@@ -6,6 +6,8 @@ mod chain_spec;
 mod service;
 mod cli;
 mod command;
+mod evm_snapshot;
+mod fork_spec;
 mod rpc;
 
 fn main() -> sc_cli::Result<()> {
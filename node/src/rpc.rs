@@ -12,6 +12,11 @@ use sp_blockchain::{Error as BlockChainError, HeaderMetadata, HeaderBackend};
 use sp_block_builder::BlockBuilder;
 pub use sc_rpc_api::DenyUnsafe;
 use sc_transaction_pool_api::TransactionPool;
+use sc_consensus_manual_seal::{
+	rpc::{ManualSeal, ManualSealApi},
+	EngineCommand,
+};
+use futures::channel::mpsc::Sender;
 
 use reef_runtime::{
 	AccountId, Balance, Nonce, BlockNumber, Hash,
@@ -27,7 +32,10 @@ use sc_finality_grandpa::{
 	SharedAuthoritySet,
 	SharedVoterState
 };
-pub use evm_rpc::{EVMApi, EVMApiServer, EVMRuntimeRPCApi};
+pub use evm_rpc::{
+	DebugApi, DebugApiServer, DevApi, DevApiServer, DryRunApi, DryRunApiServer, DryRunRuntimeApi, EVMApi, EVMApiServer,
+	EVMRuntimeRPCApi, EVMTraceApi, EthApi, EthApiServer, EthPubSubApi, EthPubSubApiServer, EvmMetrics,
+};
 pub use sc_rpc::SubscriptionTaskExecutor;
 
 /// Extra dependencies for BABE.
@@ -64,6 +72,12 @@ pub struct FullDeps<C, P, SC, B> {
 	pub select_chain: SC,
 	/// Whether to deny unsafe calls
 	pub deny_unsafe: DenyUnsafe,
+	/// Channel to the manual seal task, if blocks are sealed on demand.
+	pub command_sink: Option<Sender<EngineCommand<Hash>>>,
+	/// Whether the development chain RPCs are enabled.
+	pub dev: bool,
+	/// EVM metrics of the rpc handlers, if Prometheus is enabled.
+	pub evm_metrics: Option<EvmMetrics>,
 	/// BABE specific dependencies.
 	pub babe: BabeDeps,
 	/// GRANDPA specific dependencies.
@@ -76,13 +90,17 @@ pub fn create_full<C, P, SC, B>(
 ) -> Result<jsonrpc_core::IoHandler<sc_rpc::Metadata>, Box<dyn std::error::Error + Send + Sync>> where
 	C: ProvideRuntimeApi<Block>,
 	C: HeaderBackend<Block> + HeaderMetadata<Block, Error=BlockChainError> + 'static,
+	C: sc_client_api::BlockBackend<Block> + sc_client_api::backend::AuxStore,
+	C: sc_client_api::BlockchainEvents<Block>,
 	C: Send + Sync + 'static,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: EVMRuntimeRPCApi<Block, Balance>,
+	C::Api: EVMTraceApi<Block, Balance>,
+	C::Api: DryRunRuntimeApi<Block, AccountId, Balance>,
 	C::Api: sp_consensus_babe::BabeApi<Block>,
 	C::Api: BlockBuilder<Block>,
-	P: TransactionPool + Sync + Send + 'static,
+	P: TransactionPool<Block = Block> + Sync + Send + 'static,
 	SC: sp_consensus::SelectChain<Block> + 'static,
 	B: sc_client_api::Backend<Block> + Send + Sync + 'static,
 	B::State: sc_client_api::StateBackend<sp_runtime::traits::HashFor<Block>>,
@@ -100,6 +118,9 @@ pub fn create_full<C, P, SC, B>(
 		pool,
 		select_chain,
 		deny_unsafe,
+		command_sink,
+		dev,
+		evm_metrics,
 		babe,
 		grandpa,
 	} = deps;
@@ -118,7 +139,7 @@ pub fn create_full<C, P, SC, B>(
 
 
 	io.extend_with(
-		SystemApi::to_delegate(FullSystem::new(client.clone(), pool, deny_unsafe))
+		SystemApi::to_delegate(FullSystem::new(client.clone(), pool.clone(), deny_unsafe))
 	);
 
 	io.extend_with(
@@ -130,6 +151,10 @@ pub fn create_full<C, P, SC, B>(
 	// to call into the runtime.
 	// `io.extend_with(YourRpcTrait::to_delegate(YourRpcStruct::new(ReferenceToClient, ...)));`
 
+	if let Some(command_sink) = command_sink.clone() {
+		io.extend_with(ManualSealApi::to_delegate(ManualSeal::new(command_sink)));
+	}
+
 	io.extend_with(sc_consensus_babe_rpc::BabeApi::to_delegate(BabeRpcHandler::new(
 		client.clone(),
 		shared_epoch_changes,
@@ -142,10 +167,32 @@ pub fn create_full<C, P, SC, B>(
 		shared_authority_set,
 		shared_voter_state,
 		justification_stream,
-		subscription_executor,
+		subscription_executor.clone(),
 		finality_provider,
 	)));
-	io.extend_with(EVMApiServer::to_delegate(EVMApi::new(client)));
+	io.extend_with(EthPubSubApiServer::to_delegate(EthPubSubApi::<Block, _, _, Balance>::new(
+		client.clone(),
+		pool.clone(),
+		subscription_executor,
+	)));
+	io.extend_with(EVMApiServer::to_delegate(EVMApi::new(client.clone()).with_metrics(evm_metrics)));
+	if dev {
+		io.extend_with(DevApiServer::to_delegate(DevApi::<Block, _, _, Balance>::new(
+			client.clone(),
+			pool.clone(),
+			command_sink,
+			deny_unsafe,
+		)));
+	}
+	io.extend_with(EthApiServer::to_delegate(EthApi::<Block, _, _, Balance>::new(client.clone(), pool)));
+	io.extend_with(DebugApiServer::to_delegate(DebugApi::<Block, _, Balance>::new(
+		client.clone(),
+		deny_unsafe,
+	)));
+	io.extend_with(DryRunApiServer::to_delegate(DryRunApi::<Block, _, AccountId, Balance>::new(
+		client,
+		deny_unsafe,
+	)));
 
 	Ok(io)
 }
@@ -1,10 +1,17 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
+use futures::{channel::mpsc, stream, StreamExt};
 use sc_client_api::{ExecutorProvider, RemoteBackend};
-use reef_runtime::{self, opaque::Block, RuntimeApi};
-use sc_service::{error::Error as ServiceError, Configuration, TaskManager};
+use sc_consensus_manual_seal::{
+	consensus::babe::{BabeConsensusDataProvider, SlotTimestampProvider},
+	EngineCommand, ManualSealParams,
+};
+use sc_transaction_pool_api::TransactionPool;
+use reef_runtime::{self, opaque::Block, Hash, RuntimeApi};
+use sc_service::{error::Error as ServiceError, ChainType, Configuration, TaskManager};
 use sc_executor::native_executor_instance;
 pub use sc_executor::NativeExecutor;
 use sc_finality_grandpa::SharedVoterState;
@@ -131,8 +138,75 @@ fn remote_keystore(_url: &str) -> Result<Arc<LocalKeystore>, &'static str> {
 	Err("Remote Keystore not supported.")
 }
 
-/// Builds a new service for a full client.
-pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError> {
+/// Block sealing mode of development chains.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Sealing {
+	/// Seal a block as soon as transactions enter the pool.
+	Instant,
+	/// Seal blocks on `engine_createBlock` calls only.
+	Manual,
+	/// Seal a block every given number of milliseconds.
+	Interval(u64),
+}
+
+impl FromStr for Sealing {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"instant" => Ok(Sealing::Instant),
+			"manual" => Ok(Sealing::Manual),
+			_ => match s.strip_prefix("interval:").map(u64::from_str) {
+				Some(Ok(millis)) if millis > 0 => Ok(Sealing::Interval(millis)),
+				_ => Err(format!("invalid sealing mode `{}`, expected `instant`, `manual` or `interval:<ms>`", s)),
+			},
+		}
+	}
+}
+
+/// Stream of the commands sealing blocks in the given mode, merged with the
+/// `engine_*` RPC commands.
+fn sealing_commands<P>(
+	sealing: Sealing,
+	pool: &P,
+	rpc_commands: mpsc::Receiver<EngineCommand<Hash>>,
+) -> stream::BoxStream<'static, EngineCommand<Hash>>
+where
+	P: TransactionPool<Block = Block>,
+{
+	// blocks are left unfinalized, `evm_revert` seals blocks on earlier ones
+	let seal = |create_empty| EngineCommand::SealNewBlock {
+		create_empty,
+		finalize: false,
+		parent_hash: None,
+		sender: None,
+	};
+
+	match sealing {
+		Sealing::Manual => rpc_commands.boxed(),
+		Sealing::Instant => stream::select(
+			rpc_commands,
+			pool.import_notification_stream().map(move |_| seal(false)),
+		)
+		.boxed(),
+		Sealing::Interval(millis) => stream::select(
+			rpc_commands,
+			stream::unfold((), move |()| async move {
+				futures_timer::Delay::new(Duration::from_millis(millis)).await;
+				Some((seal(true), ()))
+			}),
+		)
+		.boxed(),
+	}
+}
+
+/// Builds a new service for a full client, sealing blocks in the given mode
+/// instead of running BABE and GRANDPA if any.
+pub fn new_full(mut config: Configuration, sealing: Option<Sealing>) -> Result<TaskManager, ServiceError> {
+	if sealing.is_some() && config.chain_spec.chain_type() != ChainType::Development {
+		return Err(ServiceError::Other("--sealing is only supported on development chains".into()));
+	}
+
 	let sc_service::PartialComponents {
 		client,
 		backend,
@@ -200,11 +274,22 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	let babe_config = babe_link.config().clone();
 	let shared_epoch_changes = babe_link.epoch_changes().clone();
 
+	let (command_sink, rpc_commands) = mpsc::channel(1024);
+
+	let evm_metrics = prometheus_registry
+		.as_ref()
+		.map(evm_rpc::EvmMetrics::register)
+		.transpose()?;
+
 	let rpc_extensions_builder = {
 		let client = client.clone();
 		let pool = transaction_pool.clone();
 		let keystore = keystore_container.sync_keystore();
 		let select_chain = select_chain.clone();
+		let evm_metrics = evm_metrics.clone();
+		let command_sink = sealing.map(|_| command_sink);
+		// time travel and state manipulation, for contract tests
+		let dev = config.chain_spec.chain_type() == ChainType::Development;
 
 		Box::new(move |deny_unsafe, subscription_executor| {
 			let deps = crate::rpc::FullDeps {
@@ -212,6 +297,9 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 				pool: pool.clone(),
 				select_chain: select_chain.clone(),
 				deny_unsafe,
+				command_sink: command_sink.clone(),
+				dev,
+				evm_metrics: evm_metrics.clone(),
 				babe: crate::rpc::BabeDeps {
 					babe_config: babe_config.clone(),
 					shared_epoch_changes: shared_epoch_changes.clone(),
@@ -247,6 +335,63 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		},
 	)?;
 
+	task_manager.spawn_handle().spawn(
+		"evm-log-indexer",
+		evm_rpc::log_indexer_task::<Block, _, reef_runtime::Balance>(client.clone()),
+	);
+	if let Some(evm_metrics) = evm_metrics {
+		task_manager.spawn_handle().spawn(
+			"evm-metrics",
+			evm_rpc::metrics_task::<Block, _, reef_runtime::Balance>(client.clone(), evm_metrics),
+		);
+	}
+
+	if let Some(sealing) = sealing {
+		let proposer = sc_basic_authorship::ProposerFactory::new(
+			task_manager.spawn_handle(),
+			client.clone(),
+			transaction_pool.clone(),
+			prometheus_registry.as_ref(),
+			telemetry.as_ref().map(|x| x.handle())
+		);
+
+		let consensus_data_provider = BabeConsensusDataProvider::new(
+			client.clone(),
+			keystore_container.sync_keystore(),
+			babe_link.epoch_changes().clone(),
+			babe_link.config().authorities.clone(),
+		)
+		.map_err(|err| ServiceError::Other(format!("Error creating BABE consensus data provider: {:?}", err)))?;
+
+		let commands_stream = sealing_commands(sealing, &*transaction_pool, rpc_commands);
+		let inherent_client = client.clone();
+		let manual_seal = sc_consensus_manual_seal::run_manual_seal(ManualSealParams {
+			block_import,
+			env: proposer,
+			client,
+			pool: transaction_pool.pool().clone(),
+			commands_stream,
+			select_chain,
+			consensus_data_provider: Some(Box::new(consensus_data_provider)),
+			create_inherent_data_providers: move |_, ()| {
+				let client = inherent_client.clone();
+				async move {
+					// timestamps advance by one slot per block, whatever the sealing interval
+					let timestamp = SlotTimestampProvider::new(client).map_err(|err| format!("{:?}", err))?;
+					let slot = sp_consensus_babe::inherents::InherentDataProvider::new(timestamp.slot().into());
+
+					Ok((timestamp, slot))
+				}
+			},
+		});
+
+		task_manager
+			.spawn_essential_handle()
+			.spawn_blocking("manual-seal", manual_seal);
+
+		network_starter.start_network();
+		return Ok(task_manager);
+	}
 
 	if role.is_authority() {
 		let proposer = sc_basic_authorship::ProposerFactory::new(
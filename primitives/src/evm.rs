@@ -1,12 +1,13 @@
 use crate::Balance;
 use codec::{Decode, Encode};
 use evm::ExitReason;
-use ethereum_types::{H160, U256};
+use ethereum_types::{H160, H256, U256};
 use sp_runtime::RuntimeDebug;
 use sp_std::vec::Vec;
 
 pub use evm::backend::{Basic as Account, Log};
 pub use evm::Config;
+pub use ethereum_types::{Bloom, BloomInput};
 
 #[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
@@ -68,3 +69,289 @@ pub struct EstimateResourcesRequest {
 	/// Data
 	pub data: Option<Vec<u8>>,
 }
+
+/// Output format of the EVM tracer.
+#[derive(Clone, Copy, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub enum TracerKind {
+	/// Record every executed opcode.
+	StructLogger,
+	/// Record the call frames only.
+	CallTracer,
+}
+
+impl Default for TracerKind {
+	fn default() -> Self {
+		TracerKind::StructLogger
+	}
+}
+
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, Default)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase", default))]
+pub struct TraceConfig {
+	/// Output format.
+	pub tracer: TracerKind,
+	/// Do not record the stack of each step.
+	pub disable_stack: bool,
+	/// Do not record the storage changes of each step.
+	pub disable_storage: bool,
+	/// Record the memory of each step.
+	pub enable_memory: bool,
+}
+
+/// A single executed opcode.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct StructLog {
+	/// Program counter.
+	pub pc: u64,
+	/// Opcode.
+	pub op: u8,
+	/// Gas left before executing the opcode.
+	pub gas: u64,
+	/// Gas used by the opcode, including sub calls.
+	pub gas_cost: u64,
+	/// Call depth, starts at 1.
+	pub depth: u32,
+	/// Stack before executing the opcode.
+	pub stack: Vec<H256>,
+	/// Memory before executing the opcode.
+	pub memory: Vec<u8>,
+	/// Storage slots written by the opcode.
+	pub storage: Vec<(H256, H256)>,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "UPPERCASE"))]
+pub enum CallType {
+	Call,
+	CallCode,
+	DelegateCall,
+	StaticCall,
+	Create,
+	Create2,
+}
+
+/// A call frame.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct CallTrace {
+	#[cfg_attr(feature = "std", serde(rename = "type"))]
+	pub call_type: CallType,
+	pub from: EvmAddress,
+	pub to: EvmAddress,
+	pub input: Vec<u8>,
+	pub value: U256,
+	pub gas: u64,
+	pub gas_used: u64,
+	pub output: Vec<u8>,
+	/// Exit reason if the frame did not succeed.
+	pub error: Option<ExitReason>,
+	pub calls: Vec<CallTrace>,
+}
+
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub enum Trace {
+	StructLogs(Vec<StructLog>),
+	CallTrace(Option<CallTrace>),
+}
+
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct TraceInfo {
+	pub exit_reason: ExitReason,
+	pub output: Vec<u8>,
+	pub used_gas: U256,
+	pub trace: Trace,
+}
+
+/// Address and storage keys a transaction plans to access (EIP-2930).
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, Default)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct AccessListItem {
+	pub address: EvmAddress,
+	pub storage_keys: Vec<H256>,
+}
+
+/// Result of a call run to generate its access list.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct AccessListInfo {
+	pub exit_reason: ExitReason,
+	pub output: Vec<u8>,
+	/// Gas used when sending the call with the access list.
+	pub used_gas: U256,
+	pub access_list: Vec<AccessListItem>,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct CodeInfo {
+	pub code_size: u32,
+	pub ref_count: u32,
+}
+
+/// A page of the storage of a contract.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, Default)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct StorageRange {
+	pub code_hash: Option<H256>,
+	pub code_info: Option<CodeInfo>,
+	/// Storage bytes charged by the storage meter, including code and extra bytes.
+	pub storage_size: u32,
+	pub storage: Vec<(H256, H256)>,
+	/// Storage key to start the next page from, `None` on the last page.
+	pub next_key: Option<H256>,
+}
+
+/// Source metadata submitted for a contract code hash.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, Default)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct SourceInfo {
+	pub code_hash: H256,
+	/// Hash of the Solidity metadata JSON, as embedded by the compiler at the
+	/// end of the bytecode.
+	pub metadata_hash: Vec<u8>,
+	pub compiler_version: Vec<u8>,
+	/// JSON encoded contract ABI.
+	pub abi: Vec<u8>,
+	/// Whether a verifier recompiled the source to the submitted code.
+	pub verified: bool,
+}
+
+/// Override of the state of an account, applied before a simulated call.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, Default)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct StateOverride {
+	pub address: EvmAddress,
+	/// Free balance.
+	pub balance: Option<Balance>,
+	pub nonce: Option<u64>,
+	/// Code, the account becomes a deployed contract.
+	pub code: Option<Vec<u8>>,
+	/// Replace the whole storage of the account.
+	pub state: Option<Vec<(H256, H256)>>,
+	/// Replace the given storage slots of the account.
+	pub state_diff: Vec<(H256, H256)>,
+}
+
+/// State manipulation of development chains, dispatched through the
+/// `dev_operation` extrinsic.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub enum DevOperation {
+	/// Move the time of the current and next blocks forward, in milliseconds.
+	IncreaseTime(u64),
+	/// Set the timestamp of the next block, in milliseconds. Later blocks
+	/// keep the offset to the block time.
+	SetNextBlockTimestamp(u64),
+	/// Move the block number seen by the block number based periods forward.
+	IncreaseBlockNumber(u32),
+	/// Override the state of an account.
+	SetState(StateOverride),
+}
+
+/// A simulated call of a batch, run against its own state overrides.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct BatchCall<Balance> {
+	pub from: EvmAddress,
+	pub to: EvmAddress,
+	pub data: Vec<u8>,
+	pub value: Balance,
+	pub gas_limit: u64,
+	pub storage_limit: u32,
+	pub access_list: Vec<AccessListItem>,
+	pub overrides: Vec<StateOverride>,
+}
+
+/// Balance of an account before and after a dry run.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct BalanceChange<AccountId, Balance> {
+	pub who: AccountId,
+	pub free_before: Balance,
+	pub free_after: Balance,
+	pub reserved_before: Balance,
+	pub reserved_after: Balance,
+}
+
+/// Storage deposit reserved for or refunded by a contract.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct StorageDeposit<Balance> {
+	/// Account paying or receiving the deposit.
+	pub who: EvmAddress,
+	pub contract: EvmAddress,
+	pub amount: Balance,
+	pub refunded: bool,
+}
+
+/// Side effects of a dry run extrinsic.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct DryRunInfo<AccountId, Balance> {
+	pub result: sp_runtime::ApplyExtrinsicResult,
+	/// SCALE encoded event records emitted by the extrinsic.
+	pub events: Vec<Vec<u8>>,
+	pub logs: Vec<Log>,
+	pub balance_changes: Vec<BalanceChange<AccountId, Balance>>,
+	pub storage_deposits: Vec<StorageDeposit<Balance>>,
+	/// Exit reason and output of the failed EVM execution, if any.
+	pub evm_failure: Option<(ExitReason, Vec<u8>)>,
+}
+
+/// Outcome of an EVM extrinsic, derived from the events of its block.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct Receipt {
+	/// Index of the extrinsic in the block.
+	pub extrinsic_index: u32,
+	pub from: EvmAddress,
+	/// Called contract, `None` for creates.
+	pub to: Option<EvmAddress>,
+	/// Created contract, `None` for calls.
+	pub contract_address: Option<EvmAddress>,
+	pub succeeded: bool,
+	/// Exit reason of the first failed execution, `None` if all succeeded.
+	pub exit_reason: Option<ExitReason>,
+	pub used_gas: u64,
+	/// Storage bytes charged, negative if the storage was released.
+	pub used_storage: i32,
+	pub logs: Vec<Log>,
+	/// Hash of the raw Ethereum transaction executed by the extrinsic, if any.
+	pub transaction_hash: Option<H256>,
+}
+
+/// Add the address and topics of the log to an Ethereum logs bloom.
+pub fn accrue_log_bloom(bloom: &mut Bloom, log: &Log) {
+	bloom.accrue(BloomInput::Raw(log.address.as_bytes()));
+	for topic in &log.topics {
+		bloom.accrue(BloomInput::Raw(topic.as_bytes()));
+	}
+}
+
+/// Ethereum header projection of a block, summarizing its EVM executions.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, Default)]
+pub struct EthereumHeader {
+	pub parent_hash: H256,
+	/// Always zero in the runtime, the state root of a block is only known
+	/// once it is built. Clients take it from the block header.
+	pub state_root: H256,
+	pub number: u64,
+	/// Gas used by the EVM executions of the block.
+	pub gas_used: u64,
+	/// Block timestamp in seconds.
+	pub timestamp: u64,
+	pub logs_bloom: Bloom,
+}
@@ -133,6 +133,7 @@ impl module_transaction_payment::Config for Test {
 	type TransactionByteFee = TransactionByteFee;
 	type WeightToFee = IdentityFee<Balance>;
 	type FeeMultiplierUpdate = ();
+	type FeeSponsor = ();
 	type WeightInfo = ();
 }
 pub type ChargeTransactionPayment = module_transaction_payment::ChargeTransactionPayment<Test>;
@@ -267,6 +268,12 @@ impl module_evm::Config for Test {
 	type DeveloperDeposit = DeveloperDeposit;
 	type DeploymentFee = DeploymentFee;
 	type FreeDeploymentOrigin = EnsureSignedBy<CouncilAccount, AccountId>;
+	type BaseFee = ();
+	type HardForkOrigin = EnsureSignedBy<CouncilAccount, AccountId>;
+	type DeploymentPolicyOrigin = EnsureSignedBy<CouncilAccount, AccountId>;
+	type RentGracePeriod = ();
+	type UpgradeDelay = ();
+	type MaxCodeHistory = ();
 	type WeightInfo = ();
 }
 
@@ -290,6 +297,8 @@ pub fn evm_genesis() -> BTreeMap<H160, module_evm::GenesisAccount<Balance, u64>>
 			balance: 0u128,
 			storage: Default::default(),
 			code: Bytes::from_str(&code_string).unwrap().0,
+			maintainer: Default::default(),
+			deployed: true,
 		};
 		let addr = H160::from_slice(
 			from_hex(address.as_str())
@@ -343,6 +352,8 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 			balance: INITIAL_BALANCE,
 			storage: Default::default(),
 			code: Default::default(),
+			maintainer: Default::default(),
+			deployed: true,
 		},
 	);
 	accounts.insert(
@@ -352,6 +363,8 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 			balance: INITIAL_BALANCE,
 			storage: Default::default(),
 			code: Default::default(),
+			maintainer: Default::default(),
+			deployed: true,
 		},
 	);
 
@@ -360,6 +373,7 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 		.unwrap();
 	module_evm::GenesisConfig::<Test> {
 		accounts,
+		dev_mode: false,
 	}
 	.assimilate_storage(&mut storage)
 	.unwrap();
@@ -12,7 +12,7 @@ use sp_std::prelude::*;
 use sp_core::{
 	crypto::KeyTypeId,
 	u32_trait::{_2, _3, _4},
-	H160, OpaqueMetadata, Decode,
+	H160, H256, U256, OpaqueMetadata, Decode,
 };
 use sp_runtime::{
 	ApplyExtrinsicResult, generic, create_runtime_str, impl_opaque_keys,
@@ -29,6 +29,7 @@ use sp_runtime::traits::{
 	StaticLookup,
 	BadOrigin,
 	OpaqueKeys,
+	Convert,
 };
 pub use sp_runtime::{
 	Perbill, Percent, Permill, Perquintill,
@@ -54,6 +55,7 @@ pub use frame_support::{
 	construct_runtime, parameter_types,
 	StorageValue,
 	traits::{
+		Get,
 		WithdrawReasons,
 		KeyOwnerProofSystem, Randomness, EnsureOrigin, OriginTrait, U128CurrencyToVote,
 		schedule::Priority,
@@ -68,7 +70,9 @@ pub use frame_system::{ensure_root, EnsureOneOf, EnsureRoot, RawOrigin};
 use orml_traits::{parameter_type_with_key};
 use orml_authority::EnsureDelayed;
 
-use module_evm::{CallInfo, CreateInfo};
+use module_evm::{
+	AccessListInfo, AccessListItem, AddressMapping, CallInfo, CreateInfo, DevOperation, TraceConfig, TraceInfo,
+};
 use module_evm_accounts::EvmAddressMapping;
 use module_currencies::{BasicCurrencyAdapter};
 use module_transaction_payment::{Multiplier, TargetedFeeAdjustment};
@@ -77,7 +81,7 @@ use module_transaction_payment::{Multiplier, TargetedFeeAdjustment};
 
 pub use pallet_staking::StakerStatus;
 pub use primitives::{
-	evm::EstimateResourcesRequest,
+	evm::{BalanceChange, BatchCall, DryRunInfo, EstimateResourcesRequest, StorageDeposit},
 	AccountId, AccountIndex, Amount, Balance, BlockNumber,
 	CurrencyId, EraIndex, Hash, Moment, Nonce, Signature, TokenSymbol,
 	AuthoritysOriginId,
@@ -231,8 +235,8 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("reef"),
 	impl_name: create_runtime_str!("reef"),
 	authoring_version: 1,
-	spec_version: 11,
-	impl_version: 11,
+	spec_version: 12,
+	impl_version: 12,
 	apis: RUNTIME_API_VERSIONS,
 	transaction_version: 2,
 };
@@ -428,7 +432,7 @@ parameter_types! {
 impl pallet_timestamp::Config for Runtime {
 	/// A timestamp: milliseconds since the unix epoch.
 	type Moment = u64;
-	type OnTimestampSet = Babe;
+	type OnTimestampSet = (Babe, EVM);
 	type MinimumPeriod = MinimumPeriod;
 	type WeightInfo = ();
 }
@@ -555,6 +559,7 @@ impl module_transaction_payment::Config for Runtime {
 	type TransactionByteFee = TransactionByteFee;
 	type WeightToFee = fee::WeightToFee;
 	type FeeMultiplierUpdate = TargetedFeeAdjustment<Self, TargetBlockFullness, AdjustmentVariable, MinimumMultiplier>;
+	type FeeSponsor = EVM;
 	type WeightInfo = weights::transaction_payment::WeightInfo<Runtime>;
 }
 
@@ -586,6 +591,31 @@ impl module_evm_accounts::Config for Runtime {
 #[cfg(feature = "with-ethereum-compatibility")]
 static ISTANBUL_CONFIG: evm::Config = evm::Config::istanbul();
 
+// EIP-2929 warm access costs, the cold access surcharge is charged by the EVM runner.
+#[cfg(feature = "with-ethereum-compatibility")]
+static BERLIN_CONFIG: evm::Config = evm::Config {
+	gas_ext_code: 100,
+	gas_ext_code_hash: 100,
+	gas_balance: 100,
+	gas_sload: 100,
+	gas_sstore_reset: 2900,
+	gas_call: 100,
+	..evm::Config::istanbul()
+};
+
+// EIP-3529 reduced refunds.
+#[cfg(feature = "with-ethereum-compatibility")]
+static LONDON_CONFIG: evm::Config = evm::Config {
+	gas_ext_code: 100,
+	gas_ext_code_hash: 100,
+	gas_balance: 100,
+	gas_sload: 100,
+	gas_sstore_reset: 2900,
+	gas_call: 100,
+	refund_sstore_clears: 4800,
+	..evm::Config::istanbul()
+};
+
 parameter_types! {
 	//In [3]: random.randint(1000, 100_000)
 	//Out[3]: 13939
@@ -597,6 +627,9 @@ parameter_types! {
 	pub NetworkContractSource: H160 = H160::from_low_u64_be(0);
 	pub const DeveloperDeposit: Balance = 1_000 * REEF;
 	pub const DeploymentFee: Balance    = 100 * REEF;
+	pub const RentGracePeriod: BlockNumber = 30 * DAYS;
+	pub const ContractUpgradeDelay: BlockNumber = 7 * DAYS;
+	pub const MaxCodeHistory: u32 = 10;
 }
 
 pub type MultiCurrencyPrecompile =
@@ -613,6 +646,17 @@ pub type ScheduleCallPrecompile = runtime_common::ScheduleCallPrecompile<
 	Runtime,
 >;
 
+/// EVM base fee, the transaction payment fee of a unit of gas weight.
+pub struct EvmBaseFee;
+impl Get<U256> for EvmBaseFee {
+	fn get() -> U256 {
+		// price a million gas so the fee of a single gas does not round to zero
+		const GAS: u64 = 1_000_000;
+		let fee: Balance = <TransactionPayment as Convert<Weight, Balance>>::convert(GasToWeight::convert(GAS));
+		U256::from(fee / Balance::from(GAS)).max(U256::one())
+	}
+}
+
 impl module_evm::Config for Runtime {
 	type AddressMapping = EvmAddressMapping<Runtime>;
 	type Currency = Balances;
@@ -630,16 +674,26 @@ impl module_evm::Config for Runtime {
 	type ChainId = ChainId;
 	type GasToWeight = GasToWeight;
 	type ChargeTransactionPayment = module_transaction_payment::ChargeTransactionPayment<Runtime>;
+	type BaseFee = EvmBaseFee;
 	type NetworkContractOrigin = EnsureRoot<AccountId>; // todo: EnsureRootOrTwoThridsTechCouncil
 	type NetworkContractSource = NetworkContractSource;
 	type DeveloperDeposit = DeveloperDeposit;
 	type DeploymentFee = DeploymentFee;
 	type FreeDeploymentOrigin = EnsureRoot<AccountId>; // todo: EnsureRootOrTwoThridsTechCouncil
+	type HardForkOrigin = EnsureRoot<AccountId>;
+	type DeploymentPolicyOrigin = EnsureRootOrTwoThridsTechCouncil;
+	type RentGracePeriod = RentGracePeriod;
+	type UpgradeDelay = ContractUpgradeDelay;
+	type MaxCodeHistory = MaxCodeHistory;
 	type WeightInfo = weights::evm::WeightInfo<Runtime>;
 
 	#[cfg(feature = "with-ethereum-compatibility")]
 	fn config() -> &'static evm::Config {
-		&ISTANBUL_CONFIG
+		match EVM::hard_fork() {
+			module_evm::HardFork::Istanbul => &ISTANBUL_CONFIG,
+			module_evm::HardFork::Berlin => &BERLIN_CONFIG,
+			module_evm::HardFork::London => &LONDON_CONFIG,
+		}
 	}
 }
 
@@ -647,6 +701,20 @@ impl module_evm_bridge::Config for Runtime {
 	type EVM = EVM;
 }
 
+parameter_types! {
+	pub const SourceDepositPerByte: Balance = 10 * MILLI_REEF;
+	pub const MaxSourceSize: u32 = 64 * 1024;
+}
+
+impl module_evm_verification::Config for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type DepositPerByte = SourceDepositPerByte;
+	type MaxSourceSize = MaxSourceSize;
+	type VerifierOrigin = EnsureRootOrTwoThridsTechCouncil;
+	type WeightInfo = weights::evm_verification::WeightInfo<Runtime>;
+}
+
 parameter_types! {
 	// note: if we add other native tokens (RUSD) we have to set native
 	// existential deposit to 0 or check for other tokens on account pruning
@@ -756,6 +824,7 @@ impl module_poc::Config for Runtime {
 	type MaxCandidates = TechCouncilMaxCandidates;
 	type MaxMembers = TechCouncilMaxMembers;
 	type MembershipChanged = TechCouncil;
+	type BlockNumberProvider = module_evm::DevBlockNumber<Runtime>;
 	type WeightInfo = ();
 }
 
@@ -820,8 +889,9 @@ construct_runtime!(
 
 		// Smart contracts
 		EvmAccounts: module_evm_accounts::{Pallet, Call, Storage, Event<T>} = 20,
-		EVM: module_evm::{Pallet, Config<T>, Call, Storage, Event<T>} = 21,
+		EVM: module_evm::{Pallet, Config<T>, Call, Storage, Event<T>, ValidateUnsigned} = 21,
 		EVMBridge: module_evm_bridge::{Pallet} = 22,
+		EvmVerification: module_evm_verification::{Pallet, Call, Storage, Event<T>} = 23,
 
 		// Consensus
 		Authorship: pallet_authorship::{Pallet, Call, Storage, Inherent} = 30,
@@ -1121,13 +1191,14 @@ impl_runtime_apis! {
 			value: Balance,
 			gas_limit: u64,
 			storage_limit: u32,
+			access_list: Option<Vec<AccessListItem>>,
 			estimate: bool,
 		) -> Result<CallInfo, sp_runtime::DispatchError> {
 			let mut config = <Runtime as module_evm::Config>::config().clone();
 			if estimate {
 				config.estimate = true;
 			}
-			module_evm::Runner::<Runtime>::call(
+			module_evm::Runner::<Runtime>::call_with_access_list(
 				from,
 				from,
 				to,
@@ -1135,6 +1206,7 @@ impl_runtime_apis! {
 				value,
 				gas_limit,
 				storage_limit,
+				access_list.unwrap_or_default(),
 				&config,
 			)
 		}
@@ -1145,18 +1217,21 @@ impl_runtime_apis! {
 			value: Balance,
 			gas_limit: u64,
 			storage_limit: u32,
+			access_list: Option<Vec<AccessListItem>>,
 			estimate: bool,
 		) -> Result<CreateInfo, sp_runtime::DispatchError> {
 			let mut config = <Runtime as module_evm::Config>::config().clone();
 			if estimate {
 				config.estimate = true;
 			}
-			module_evm::Runner::<Runtime>::create(
+			module_evm::Runner::<Runtime>::create_with_access_list(
 				from,
 				data,
+				None,
 				value,
 				gas_limit,
 				storage_limit,
+				access_list.unwrap_or_default(),
 				&config,
 			)
 		}
@@ -1169,7 +1244,8 @@ impl_runtime_apis! {
 				.map_err(|_| sp_runtime::DispatchError::Other("Invalid parameter extrinsic, decode failed"))?;
 
 			let request = match utx.function {
-				Call::EVM(module_evm::Call::call(to, data, value, gas_limit, storage_limit)) => {
+				Call::EVM(module_evm::Call::call(to, data, value, gas_limit, storage_limit))
+				| Call::EVM(module_evm::Call::call_with_access_list(to, data, value, gas_limit, storage_limit, _)) => {
 					Some(EstimateResourcesRequest {
 						from: None,
 						to: Some(to),
@@ -1179,7 +1255,19 @@ impl_runtime_apis! {
 						data: Some(data),
 					})
 				}
-				Call::EVM(module_evm::Call::create(data, value, gas_limit, storage_limit)) => {
+				Call::EVM(module_evm::Call::sponsored_call(from, to, data, value, gas_limit, storage_limit, _, _)) => {
+					Some(EstimateResourcesRequest {
+						from: Some(from),
+						to: Some(to),
+						gas_limit: Some(gas_limit),
+						storage_limit: Some(storage_limit),
+						value: Some(value),
+						data: Some(data),
+					})
+				}
+				Call::EVM(module_evm::Call::create(data, value, gas_limit, storage_limit))
+				| Call::EVM(module_evm::Call::create_with_access_list(data, value, gas_limit, storage_limit, _))
+				| Call::EVM(module_evm::Call::create2_with_access_list(data, _, value, gas_limit, storage_limit, _)) => {
 					Some(EstimateResourcesRequest {
 						from: None,
 						to: None,
@@ -1195,6 +1283,472 @@ impl_runtime_apis! {
 			request.ok_or(sp_runtime::DispatchError::Other("Invalid parameter extrinsic, not evm Call"))
 		}
 
+		fn chain_id() -> u64 {
+			ChainId::get()
+		}
+
+		fn account_basic(address: H160) -> module_evm::Account {
+			EVM::account_basic(&address)
+		}
+
+		fn code_at(address: H160) -> Vec<u8> {
+			EVM::code_at_address(&address)
+		}
+
+		fn storage_at(address: H160, index: H256) -> H256 {
+			EVM::account_storages(address, index)
+		}
+
+		fn storage_range(address: H160, start: Option<H256>, limit: u32) -> module_evm::StorageRange {
+			EVM::storage_range(&address, start, limit)
+		}
+
+		fn source_info(address: H160) -> Option<module_evm::SourceInfo> {
+			EvmVerification::source_info(&address)
+		}
+
+		fn block_logs() -> Vec<(u32, module_evm::Log)> {
+			System::events()
+				.into_iter()
+				.filter_map(|record| match (record.phase, record.event) {
+					(frame_system::Phase::ApplyExtrinsic(index), Event::EVM(module_evm::Event::Log(log))) => {
+						Some((index, log))
+					}
+					_ => None,
+				})
+				.collect()
+		}
+
+		fn block_receipts() -> Vec<module_evm::Receipt> {
+			let mut receipts: Vec<module_evm::Receipt> = Vec::new();
+			let mut logs = Vec::new();
+			let mut transaction_hashes = Vec::new();
+			for record in System::events() {
+				let index = match record.phase {
+					frame_system::Phase::ApplyExtrinsic(index) => index,
+					_ => continue,
+				};
+				let (from, to, contract_address, exit_reason, (used_gas, used_storage)) = match record.event {
+					Event::EVM(module_evm::Event::Executed(from, to, used)) => (from, Some(to), None, None, used),
+					Event::EVM(module_evm::Event::ExecutedFailed(from, to, exit_reason, _, used)) => {
+						(from, Some(to), None, Some(exit_reason), used)
+					}
+					Event::EVM(module_evm::Event::Created(from, address, used)) => {
+						(from, None, Some(address), None, used)
+					}
+					Event::EVM(module_evm::Event::CreatedFailed(from, address, exit_reason, _, used)) => {
+						(from, None, Some(address), Some(exit_reason), used)
+					}
+					Event::EVM(module_evm::Event::Log(log)) => {
+						logs.push((index, log));
+						continue;
+					}
+					Event::EVM(module_evm::Event::Transacted(_, hash)) => {
+						transaction_hashes.push((index, hash));
+						continue;
+					}
+					_ => continue,
+				};
+
+				// extrinsics executing several times, like batches, get a single receipt
+				match receipts.last_mut() {
+					Some(receipt) if receipt.extrinsic_index == index => {
+						receipt.succeeded &= exit_reason.is_none();
+						if receipt.exit_reason.is_none() {
+							receipt.exit_reason = exit_reason;
+						}
+						receipt.used_gas = receipt.used_gas.saturating_add(used_gas);
+						receipt.used_storage = receipt.used_storage.saturating_add(used_storage);
+					}
+					_ => receipts.push(module_evm::Receipt {
+						extrinsic_index: index,
+						from,
+						to,
+						contract_address,
+						succeeded: exit_reason.is_none(),
+						exit_reason,
+						used_gas,
+						used_storage,
+						logs: Vec::new(),
+						transaction_hash: None,
+					}),
+				}
+			}
+
+			for receipt in receipts.iter_mut() {
+				let index = receipt.extrinsic_index;
+				receipt.logs = logs
+					.iter()
+					.filter(|(log_index, _)| *log_index == index)
+					.map(|(_, log)| log.clone())
+					.collect();
+				receipt.transaction_hash = transaction_hashes
+					.iter()
+					.find(|(hash_index, _)| *hash_index == index)
+					.map(|(_, hash)| *hash);
+			}
+
+			receipts
+		}
+
+		fn ethereum_header(number: u64) -> Option<module_evm::EthereumHeader> {
+			EVM::ethereum_headers(number.saturated_into::<BlockNumber>())
+		}
+
+		fn base_fee() -> U256 {
+			EvmBaseFee::get()
+		}
+
+		fn block_fullness() -> Perbill {
+			Perbill::from_rational(System::block_weight().total(), BlockWeights::get().max_block)
+		}
+
+		fn call_batch(calls: Vec<BatchCall<Balance>>) -> Vec<Result<CallInfo, sp_runtime::DispatchError>> {
+			calls
+				.into_iter()
+				.map(|call| {
+					frame_support::storage::with_transaction(|| {
+						for state_override in call.overrides {
+							EVM::apply_state_override(state_override);
+						}
+
+						let result = module_evm::Runner::<Runtime>::call_with_access_list(
+							call.from,
+							call.from,
+							call.to,
+							call.data,
+							call.value,
+							call.gas_limit,
+							call.storage_limit,
+							call.access_list,
+							<Runtime as module_evm::Config>::config(),
+						);
+
+						sp_runtime::TransactionOutcome::Rollback(result)
+					})
+				})
+				.collect()
+		}
+
+		fn create_access_list(
+			from: H160,
+			to: Option<H160>,
+			data: Vec<u8>,
+			value: Balance,
+			gas_limit: u64,
+			storage_limit: u32,
+		) -> Result<AccessListInfo, sp_runtime::DispatchError> {
+			module_evm::Runner::<Runtime>::create_access_list(
+				from,
+				to,
+				data,
+				value,
+				gas_limit,
+				storage_limit,
+				<Runtime as module_evm::Config>::config(),
+			)
+		}
+
+		fn transact_extrinsic(transaction: Vec<u8>) -> <Block as BlockT>::Extrinsic {
+			UncheckedExtrinsic::new_unsigned(Call::EVM(module_evm::Call::transact(transaction)))
+		}
+
+		fn transaction_hash(extrinsic: <Block as BlockT>::Extrinsic) -> Option<H256> {
+			match &extrinsic.function {
+				Call::EVM(module_evm::Call::transact(transaction)) => {
+					Some(module_evm::transaction::transaction_hash(transaction))
+				}
+				Call::EVM(module_evm::Call::call(..))
+				| Call::EVM(module_evm::Call::call_with_access_list(..))
+				| Call::EVM(module_evm::Call::sponsored_call(..))
+				| Call::EVM(module_evm::Call::create(..))
+				| Call::EVM(module_evm::Call::create2(..))
+				| Call::EVM(module_evm::Call::create_with_access_list(..))
+				| Call::EVM(module_evm::Call::create2_with_access_list(..)) => {
+					Some(H256::from(extrinsic.using_encoded(sp_io::hashing::blake2_256)))
+				}
+				_ => None,
+			}
+		}
+
+		fn dev_extrinsic(operation: DevOperation, salt: u64) -> Option<<Block as BlockT>::Extrinsic> {
+			if !EVM::dev_mode() {
+				return None;
+			}
+			Some(UncheckedExtrinsic::new_unsigned(Call::EVM(module_evm::Call::dev_operation(operation, salt))))
+		}
+	}
+
+	impl module_evm_rpc_runtime_api::EVMTraceApi<Block, Balance> for Runtime {
+		fn trace_call(
+			from: H160,
+			to: H160,
+			data: Vec<u8>,
+			value: Balance,
+			gas_limit: u64,
+			storage_limit: u32,
+			access_list: Option<Vec<AccessListItem>>,
+			config: TraceConfig,
+		) -> Result<TraceInfo, sp_runtime::DispatchError> {
+			module_evm::Runner::<Runtime>::trace_call(
+				from,
+				from,
+				to,
+				data,
+				value,
+				gas_limit,
+				storage_limit,
+				access_list.unwrap_or_default(),
+				<Runtime as module_evm::Config>::config(),
+				config,
+			)
+		}
+
+		fn trace_create(
+			from: H160,
+			data: Vec<u8>,
+			value: Balance,
+			gas_limit: u64,
+			storage_limit: u32,
+			access_list: Option<Vec<AccessListItem>>,
+			config: TraceConfig,
+		) -> Result<TraceInfo, sp_runtime::DispatchError> {
+			module_evm::Runner::<Runtime>::trace_create(
+				from,
+				data,
+				None,
+				value,
+				gas_limit,
+				storage_limit,
+				access_list.unwrap_or_default(),
+				<Runtime as module_evm::Config>::config(),
+				config,
+			)
+		}
+
+		fn trace_extrinsic(
+			pending: Vec<<Block as BlockT>::Extrinsic>,
+			extrinsic: <Block as BlockT>::Extrinsic,
+			config: TraceConfig,
+		) -> Result<TraceInfo, sp_runtime::DispatchError> {
+			for xt in pending {
+				let _ = Executive::apply_extrinsic(xt);
+			}
+
+			let who = match extrinsic.signature.as_ref() {
+				Some((address, _, _)) => Indices::lookup(address.clone())
+					.map_err(|_| sp_runtime::DispatchError::Other("Invalid parameter extrinsic, unknown signer"))?,
+				None => return Err(sp_runtime::DispatchError::Other("Invalid parameter extrinsic, not signed")),
+			};
+			let from = EvmAddressMapping::<Runtime>::get_evm_address(&who)
+				.unwrap_or_else(|| EvmAddressMapping::<Runtime>::get_default_evm_address(&who));
+			module_evm::ExtrinsicOrigin::<Runtime>::put(who);
+
+			let evm_config = <Runtime as module_evm::Config>::config();
+			match extrinsic.function {
+				Call::EVM(module_evm::Call::call(to, data, value, gas_limit, storage_limit)) => {
+					module_evm::Runner::<Runtime>::trace_call(
+						from,
+						from,
+						to,
+						data,
+						value,
+						gas_limit,
+						storage_limit,
+						Vec::new(),
+						evm_config,
+						config,
+					)
+				}
+				Call::EVM(module_evm::Call::call_with_access_list(to, data, value, gas_limit, storage_limit, access_list)) => {
+					module_evm::Runner::<Runtime>::trace_call(
+						from,
+						from,
+						to,
+						data,
+						value,
+						gas_limit,
+						storage_limit,
+						access_list,
+						evm_config,
+						config,
+					)
+				}
+				Call::EVM(module_evm::Call::create(data, value, gas_limit, storage_limit)) => {
+					module_evm::Runner::<Runtime>::trace_create(
+						from,
+						data,
+						None,
+						value,
+						gas_limit,
+						storage_limit,
+						Vec::new(),
+						evm_config,
+						config,
+					)
+				}
+				Call::EVM(module_evm::Call::create_with_access_list(data, value, gas_limit, storage_limit, access_list)) => {
+					module_evm::Runner::<Runtime>::trace_create(
+						from,
+						data,
+						None,
+						value,
+						gas_limit,
+						storage_limit,
+						access_list,
+						evm_config,
+						config,
+					)
+				}
+				Call::EVM(module_evm::Call::create2(data, salt, value, gas_limit, storage_limit)) => {
+					module_evm::Runner::<Runtime>::trace_create(
+						from,
+						data,
+						Some(salt),
+						value,
+						gas_limit,
+						storage_limit,
+						Vec::new(),
+						evm_config,
+						config,
+					)
+				}
+				Call::EVM(module_evm::Call::create2_with_access_list(data, salt, value, gas_limit, storage_limit, access_list)) => {
+					module_evm::Runner::<Runtime>::trace_create(
+						from,
+						data,
+						Some(salt),
+						value,
+						gas_limit,
+						storage_limit,
+						access_list,
+						evm_config,
+						config,
+					)
+				}
+				_ => Err(sp_runtime::DispatchError::Other("Invalid parameter extrinsic, not evm Call")),
+			}
+		}
+
+	}
+
+	impl module_evm_rpc_runtime_api::DryRunApi<Block, AccountId, Balance> for Runtime {
+		fn dry_run_extrinsic(
+			extrinsic: <Block as BlockT>::Extrinsic,
+			from: Option<AccountId>,
+		) -> DryRunInfo<AccountId, Balance> {
+			use sp_runtime::traits::Dispatchable;
+
+			let signer = match extrinsic.signature.as_ref() {
+				Some((address, _, _)) => Indices::lookup(address.clone()).ok(),
+				None => from.clone(),
+			};
+
+			let (result, records, balances_after) = frame_support::storage::with_transaction(|| {
+				let event_count = System::event_count() as usize;
+				let result = match from {
+					Some(who) if extrinsic.signature.is_none() => {
+						module_evm::ExtrinsicOrigin::<Runtime>::put(who.clone());
+						let result = extrinsic.function.dispatch(Origin::signed(who));
+						module_evm::ExtrinsicOrigin::<Runtime>::kill();
+						Ok(result.map(|_| ()).map_err(|err| err.error))
+					}
+					_ => Executive::apply_extrinsic(extrinsic),
+				};
+				let records = System::events().into_iter().skip(event_count).collect::<Vec<_>>();
+
+				// every account whose balance may have been changed by the extrinsic
+				let mut accounts = sp_std::collections::btree_set::BTreeSet::new();
+				accounts.extend(signer);
+				for record in records.iter() {
+					match &record.event {
+						Event::Balances(pallet_balances::Event::Endowed(who, _))
+						| Event::Balances(pallet_balances::Event::DustLost(who, _))
+						| Event::Balances(pallet_balances::Event::BalanceSet(who, _, _))
+						| Event::Balances(pallet_balances::Event::Deposit(who, _))
+						| Event::Balances(pallet_balances::Event::Reserved(who, _))
+						| Event::Balances(pallet_balances::Event::Unreserved(who, _)) => {
+							accounts.insert(who.clone());
+						}
+						Event::Balances(pallet_balances::Event::Transfer(from, to, _))
+						| Event::Balances(pallet_balances::Event::ReserveRepatriated(from, to, _, _)) => {
+							accounts.insert(from.clone());
+							accounts.insert(to.clone());
+						}
+						Event::EVM(module_evm::Event::StorageDepositCharged(who, contract, _))
+						| Event::EVM(module_evm::Event::StorageDepositRefunded(who, contract, _)) => {
+							accounts.insert(EvmAddressMapping::<Runtime>::get_account_id(who));
+							accounts.insert(EvmAddressMapping::<Runtime>::get_account_id(contract));
+						}
+						_ => {}
+					}
+				}
+
+				let balances_after = accounts
+					.into_iter()
+					.map(|who| {
+						let free = Balances::free_balance(&who);
+						let reserved = Balances::reserved_balance(&who);
+						(who, free, reserved)
+					})
+					.collect::<Vec<_>>();
+
+				sp_runtime::TransactionOutcome::Rollback((result, records, balances_after))
+			});
+
+			let balance_changes = balances_after
+				.into_iter()
+				.map(|(who, free_after, reserved_after)| BalanceChange {
+					free_before: Balances::free_balance(&who),
+					reserved_before: Balances::reserved_balance(&who),
+					who,
+					free_after,
+					reserved_after,
+				})
+				.filter(|change| {
+					change.free_before != change.free_after || change.reserved_before != change.reserved_after
+				})
+				.collect();
+
+			let mut logs = Vec::new();
+			let mut storage_deposits = Vec::new();
+			let mut evm_failure = None;
+			for record in records.iter() {
+				match &record.event {
+					Event::EVM(module_evm::Event::Log(log)) => logs.push(log.clone()),
+					Event::EVM(module_evm::Event::StorageDepositCharged(who, contract, amount)) => {
+						storage_deposits.push(StorageDeposit {
+							who: *who,
+							contract: *contract,
+							amount: *amount,
+							refunded: false,
+						});
+					}
+					Event::EVM(module_evm::Event::StorageDepositRefunded(who, contract, amount)) => {
+						storage_deposits.push(StorageDeposit {
+							who: *who,
+							contract: *contract,
+							amount: *amount,
+							refunded: true,
+						});
+					}
+					Event::EVM(module_evm::Event::ExecutedFailed(_, _, exit_reason, output, _))
+					| Event::EVM(module_evm::Event::CreatedFailed(_, _, exit_reason, output, _)) => {
+						evm_failure = Some((exit_reason.clone(), output.clone()));
+					}
+					_ => {}
+				}
+			}
+
+			DryRunInfo {
+				result,
+				events: records.iter().map(|record| record.encode()).collect(),
+				logs,
+				balance_changes,
+				storage_deposits,
+				evm_failure,
+			}
+		}
 	}
 
 	#[cfg(feature = "runtime-benchmarks")]
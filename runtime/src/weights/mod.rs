@@ -3,4 +3,5 @@
 
 pub mod evm;
 pub mod evm_accounts;
+pub mod evm_verification;
 pub mod transaction_payment;